@@ -0,0 +1,227 @@
+use ark_ff::PrimeField;
+use ark_relations::r1cs::Matrix;
+use ark_std::vec::Vec;
+
+use crate::bd_as::ccs::CCS;
+use crate::bd_as::r1cs_nark::{CircuitDigest, UnsatisfiedReport};
+
+use super::AirError;
+
+/// cell of a transition, a column of the current or of the next row
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceCell {
+    Current(usize),
+    Next(usize),
+}
+
+impl TraceCell {
+    fn column(&self) -> usize {
+        match self {
+            TraceCell::Current(column) | TraceCell::Next(column) => *column,
+        }
+    }
+}
+
+/// polynomial over the cells of two consecutive rows, as a sum of coefficients times
+/// products of cells. A term without cells is a constant.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransitionPolynomial<F: PrimeField> {
+    pub(crate) terms: Vec<(F, Vec<TraceCell>)>,
+}
+
+impl<F: PrimeField> TransitionPolynomial<F> {
+    pub fn new() -> Self {
+        TransitionPolynomial { terms: vec![] }
+    }
+
+    /// adds coeff · ∏ cells
+    pub fn term(mut self, coeff: F, cells: Vec<TraceCell>) -> Self {
+        self.terms.push((coeff, cells));
+        self
+    }
+
+    pub fn degree(&self) -> usize {
+        self.terms.iter().map(|(_, cells)| cells.len()).max().unwrap_or(0)
+    }
+}
+
+/// pins the cell (row, column) of the trace to the next public input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundaryConstraint {
+    pub(crate) row: usize,
+    pub(crate) column: usize,
+}
+
+impl BoundaryConstraint {
+    pub fn new(row: usize, column: usize) -> Self {
+        BoundaryConstraint { row, column }
+    }
+}
+
+/// algebraic intermediate representation of a computation over a trace of `num_rows`
+/// rows of `width` cells. Every transition polynomial vanishes on each pair of
+/// consecutive rows, and boundary constraint i pins its cell to the public input x_i.
+#[derive(Clone, Debug)]
+pub struct AirRelation<F: PrimeField> {
+    pub(crate) width: usize,
+    pub(crate) num_rows: usize,
+    pub(crate) transitions: Vec<TransitionPolynomial<F>>,
+    pub(crate) boundaries: Vec<BoundaryConstraint>,
+    /// digest of `to_ccs`, computed once by `new`
+    pub(crate) digest: CircuitDigest,
+}
+
+impl<F: PrimeField> AirRelation<F> {
+    pub fn new(
+        width: usize,
+        num_rows: usize,
+        transitions: Vec<TransitionPolynomial<F>>,
+        boundaries: Vec<BoundaryConstraint>,
+    ) -> Result<Self, AirError> {
+        if width == 0 || num_rows == 0 {
+            return Err(AirError::EmptyTrace);
+        }
+        for (transition, polynomial) in transitions.iter().enumerate() {
+            let cells = polynomial.terms.iter().flat_map(|(_, cells)| cells);
+            if let Some(cell) = cells.into_iter().find(|cell| cell.column() >= width) {
+                return Err(AirError::ColumnOutOfRange {
+                    transition,
+                    column: cell.column(),
+                });
+            }
+        }
+        for (boundary, constraint) in boundaries.iter().enumerate() {
+            if constraint.row >= num_rows || constraint.column >= width {
+                return Err(AirError::BoundaryOutOfRange {
+                    boundary,
+                    row: constraint.row,
+                    column: constraint.column,
+                });
+            }
+        }
+
+        let mut relation = AirRelation {
+            width,
+            num_rows,
+            transitions,
+            boundaries,
+            digest: CircuitDigest([0; 32]),
+        };
+        relation.digest = relation.to_ccs().digest();
+        Ok(relation)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// the digest of `to_ccs`, which keys indexed from the relation carry
+    pub fn digest(&self) -> CircuitDigest {
+        self.digest
+    }
+
+    /// largest degree of a transition, the boundaries are linear
+    pub fn degree(&self) -> usize {
+        self.transitions.iter().map(|polynomial| polynomial.degree()).max().unwrap_or(0).max(1)
+    }
+
+    /// the relation as a CCS over z = (1, x, trace row by row). Transition t on the rows
+    /// (i, i + 1) is constraint t·(n − 1) + i for n rows, and boundary j follows them as
+    /// trace cell − x_j. Each factor of a term gets a matrix selecting its cell on the
+    /// rows of its transition only, so the term vanishes on the other constraints; a
+    /// constant term selects the constant one the same way.
+    pub fn to_ccs(&self) -> CCS<F> {
+        let num_instance_variables = 1 + self.boundaries.len();
+        let num_steps = self.num_rows - 1;
+        let num_constraints = self.transitions.len() * num_steps + self.boundaries.len();
+        let cell = |row: usize, column: usize| num_instance_variables + row * self.width + column;
+
+        let (mut matrices, mut multisets, mut constants) = (vec![], vec![], vec![]);
+        for (transition, polynomial) in self.transitions.iter().enumerate() {
+            let first_row = transition * num_steps;
+            for (coeff, cells) in &polynomial.terms {
+                let mut multiset = vec![];
+                let mut select = |column_of_step: &dyn Fn(usize) -> usize| {
+                    let mut matrix: Matrix<F> = vec![vec![]; num_constraints];
+                    for step in 0..num_steps {
+                        matrix[first_row + step] = vec![(F::one(), column_of_step(step))];
+                    }
+                    multiset.push(matrices.len());
+                    matrices.push(matrix);
+                };
+                if cells.is_empty() {
+                    select(&|_| 0);
+                }
+                for trace_cell in cells {
+                    match *trace_cell {
+                        TraceCell::Current(column) => select(&|step| cell(step, column)),
+                        TraceCell::Next(column) => select(&|step| cell(step + 1, column)),
+                    }
+                }
+                multisets.push(multiset);
+                constants.push(*coeff);
+            }
+        }
+
+        let mut boundary_matrix: Matrix<F> = vec![vec![]; num_constraints];
+        for (boundary, constraint) in self.boundaries.iter().enumerate() {
+            boundary_matrix[self.transitions.len() * num_steps + boundary] = vec![
+                (F::one(), cell(constraint.row, constraint.column)),
+                (-F::one(), 1 + boundary),
+            ];
+        }
+        multisets.push(vec![matrices.len()]);
+        matrices.push(boundary_matrix);
+        constants.push(F::one());
+
+        CCS::new(
+            num_instance_variables,
+            self.num_rows * self.width,
+            matrices,
+            multisets,
+            constants,
+        )
+        .expect("the matrices select cells of the trace")
+    }
+
+    /// the CCS assignment (input, witness) of `public_inputs` and `trace`
+    pub fn assignment(
+        &self,
+        public_inputs: &[F],
+        trace: &[Vec<F>],
+    ) -> Result<(Vec<F>, Vec<F>), AirError> {
+        if public_inputs.len() != self.boundaries.len() {
+            return Err(AirError::PublicInputCount {
+                expected: self.boundaries.len(),
+                found: public_inputs.len(),
+            });
+        }
+        let bad_row = trace.iter().find(|row| row.len() != self.width);
+        if trace.len() != self.num_rows || bad_row.is_some() {
+            return Err(AirError::TraceShape {
+                rows: trace.len(),
+                width: bad_row.map_or(self.width, |row| row.len()),
+            });
+        }
+
+        let input = ark_std::iter::once(F::one())
+            .chain(public_inputs.iter().copied())
+            .collect();
+        Ok((input, trace.concat()))
+    }
+
+    /// checks the transitions and the boundaries, reporting the failing constraints in
+    /// the order of `to_ccs`
+    pub fn check_assignment(
+        &self,
+        public_inputs: &[F],
+        trace: &[Vec<F>],
+    ) -> Result<Option<UnsatisfiedReport>, AirError> {
+        let (input, witness) = self.assignment(public_inputs, trace)?;
+        Ok(self.to_ccs().check_assignment(&input, &witness))
+    }
+}
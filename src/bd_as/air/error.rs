@@ -0,0 +1,53 @@
+use ark_std::fmt;
+
+/// reason an AIR, or a trace of it, was rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AirError {
+    /// a trace has at least one row and one column
+    EmptyTrace,
+    /// `transition` reads a column the trace does not have
+    ColumnOutOfRange { transition: usize, column: usize },
+    /// `boundary` pins a cell outside of the trace
+    BoundaryOutOfRange { boundary: usize, row: usize, column: usize },
+    /// the trace does not have the shape of the relation
+    TraceShape { rows: usize, width: usize },
+    /// there has to be one public input per boundary constraint
+    PublicInputCount { expected: usize, found: usize },
+    /// the key was indexed from another relation
+    KeyMismatch,
+}
+
+impl fmt::Display for AirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AirError::EmptyTrace => write!(f, "the trace has no rows or no columns"),
+            AirError::ColumnOutOfRange { transition, column } => write!(
+                f,
+                "transition {} reads column {} which does not exist",
+                transition, column
+            ),
+            AirError::BoundaryOutOfRange {
+                boundary,
+                row,
+                column,
+            } => write!(
+                f,
+                "boundary {} pins cell ({}, {}) which is outside of the trace",
+                boundary, row, column
+            ),
+            AirError::TraceShape { rows, width } => write!(
+                f,
+                "a trace of {} rows of width {} does not fit the relation",
+                rows, width
+            ),
+            AirError::PublicInputCount { expected, found } => write!(
+                f,
+                "expected {} public inputs but found {}",
+                expected, found
+            ),
+            AirError::KeyMismatch => write!(f, "the key does not match the relation"),
+        }
+    }
+}
+
+impl ark_std::error::Error for AirError {}
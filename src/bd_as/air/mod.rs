@@ -0,0 +1,221 @@
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+use crate::bd_as::ccs::{CCSAccumulationScheme, CCSNark, CCSProverKey, CCSVerifierKey};
+use crate::bd_as::r1cs_nark::{Proof as NarkProof, PublicParameters};
+
+mod data_structures;
+mod error;
+pub use data_structures::*;
+pub use error::*;
+
+/// NARK for AIRs, proving the CCS of `AirRelation::to_ccs`. The instance is the
+/// boundary values and the Merkle root of the encoded trace.
+pub struct AirNark<F: PrimeField + Absorb> {
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> AirNark<F> {
+    /// generates index prover key and verifier key
+    pub fn index(
+        pp: &PublicParameters,
+        relation: &AirRelation<F>,
+    ) -> (CCSProverKey<F>, CCSVerifierKey<F>) {
+        CCSNark::index(pp, relation.to_ccs())
+    }
+
+    /// generates a proof for the boundary values and the trace of `relation`.
+    /// Satisfaction is not checked here, see `AirRelation::check_assignment`.
+    pub fn prove(
+        ipk: &CCSProverKey<F>,
+        relation: &AirRelation<F>,
+        public_inputs: &[F],
+        trace: &[Vec<F>],
+    ) -> Result<NarkProof<F>, AirError> {
+        if !ipk.is_compatible(&relation.digest()) {
+            return Err(AirError::KeyMismatch);
+        }
+        let (input, witness) = relation.assignment(public_inputs, trace)?;
+        Ok(CCSNark::prove(ipk, input, witness).expect("the assignment has the shape of the key"))
+    }
+}
+
+/// accumulation scheme for AIRs. Transitions of degree d leave d − 1 cross terms per
+/// fold, committed to like the accumulated trace, see `CCSAccumulationScheme`.
+pub type AirAccumulationScheme<F> = CCSAccumulationScheme<F>;
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::ccs::test::fold_all;
+    use crate::bd_as::r1cs_nark::{CodeParameters, R1CSNark};
+    use crate::bd_as::DecideError;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_ff::{One, Zero};
+
+    const IS_ADD: usize = 0;
+    const IS_MUL: usize = 1;
+    const ACC: usize = 2;
+    const ARG: usize = 3;
+
+    #[derive(Clone, Copy)]
+    enum Op {
+        Add(u64),
+        Mul(u64),
+    }
+
+    /// accumulator machine executing one instruction per row: the row holds the opcode
+    /// flags, the accumulator before the instruction and its argument, and the next row
+    /// the accumulator after it. The initial and final accumulator are public.
+    fn vm_air(num_instructions: usize) -> AirRelation<Fr> {
+        use TraceCell::{Current, Next};
+        let boolean = TransitionPolynomial::new()
+            .term(Fr::one(), vec![Current(IS_ADD), Current(IS_ADD)])
+            .term(-Fr::one(), vec![Current(IS_ADD)]);
+        let one_op = TransitionPolynomial::new()
+            .term(Fr::one(), vec![Current(IS_ADD)])
+            .term(Fr::one(), vec![Current(IS_MUL)])
+            .term(-Fr::one(), vec![]);
+        // acc' = is_add · (acc + arg) + is_mul · acc · arg
+        let step = TransitionPolynomial::new()
+            .term(Fr::one(), vec![Next(ACC)])
+            .term(-Fr::one(), vec![Current(IS_ADD), Current(ACC)])
+            .term(-Fr::one(), vec![Current(IS_ADD), Current(ARG)])
+            .term(-Fr::one(), vec![Current(IS_MUL), Current(ACC), Current(ARG)]);
+        AirRelation::new(
+            4,
+            num_instructions + 1,
+            vec![boolean, one_op, step],
+            vec![
+                BoundaryConstraint::new(0, ACC),
+                BoundaryConstraint::new(num_instructions, ACC),
+            ],
+        )
+        .unwrap()
+    }
+
+    /// the boundary values and the trace of running `program` from `input`
+    fn vm_trace(program: &[Op], input: u64) -> (Vec<Fr>, Vec<Vec<Fr>>) {
+        let mut acc = Fr::from(input);
+        let mut trace = vec![];
+        for op in program {
+            let (row, next) = match *op {
+                Op::Add(arg) => ([Fr::one(), Fr::zero(), acc, arg.into()], acc + Fr::from(arg)),
+                Op::Mul(arg) => ([Fr::zero(), Fr::one(), acc, arg.into()], acc * Fr::from(arg)),
+            };
+            trace.push(row.to_vec());
+            acc = next;
+        }
+        trace.push(vec![Fr::one(), Fr::zero(), acc, Fr::zero()]);
+        (vec![Fr::from(input), acc], trace)
+    }
+
+    #[test]
+    pub fn test_air_vm() {
+        let pp = R1CSNark::<Fr>::setup();
+        let air = vm_air(4);
+        assert_eq!(air.degree(), 3);
+        let (ipk, ivk) = AirNark::index(&pp, &air);
+        let ipk = ipk.with_code_params(CodeParameters::with_num_queries(4));
+        let idk = ipk.decider_key();
+        assert_eq!(ivk.degree(), 3);
+        assert_eq!(ipk.digest(), air.digest());
+
+        let runs = [
+            ([Op::Add(3), Op::Mul(2), Op::Add(1), Op::Mul(5)], 1),
+            ([Op::Mul(4), Op::Mul(4), Op::Add(7), Op::Add(0)], 2),
+            ([Op::Add(9), Op::Add(9), Op::Mul(0), Op::Add(6)], 3),
+        ];
+        let proofs: Vec<_> = runs
+            .iter()
+            .map(|(program, input)| {
+                let (public_inputs, trace) = vm_trace(program, *input);
+                assert_eq!(air.check_assignment(&public_inputs, &trace), Ok(None));
+                AirNark::prove(&ipk, &air, &public_inputs, &trace).unwrap()
+            })
+            .collect();
+        assert_eq!(proofs[0].instance.input, vec![Fr::one(), Fr::one(), Fr::from(45u64)]);
+        for proof in &proofs {
+            assert_eq!(CCSNark::verify(&idk, &proof.instance.input, proof), Ok(()));
+        }
+
+        let (instance, witness) = fold_all(&ipk, &proofs);
+        assert_eq!(
+            AirAccumulationScheme::decide_with_report(&idk, (&instance, &witness)),
+            Ok(())
+        );
+
+        // a wrong accumulator after the second instruction breaks two steps, constraints
+        // 4·2 + 1 and 4·2 + 2, and folds into an accumulator that is not decided
+        let (public_inputs, mut trace) = vm_trace(&runs[0].0, runs[0].1);
+        trace[2][ACC] += Fr::one();
+        let Ok(Some(report)) = air.check_assignment(&public_inputs, &trace) else {
+            panic!["wrong step passed the check"]
+        };
+        assert_eq!(report.rows, vec![9, 10]);
+        let bad_proof = AirNark::prove(&ipk, &air, &public_inputs, &trace).unwrap();
+        let (instance, witness) = fold_all(&ipk, &[proofs[1].clone(), bad_proof]);
+        let Err(DecideError::UnsatisfiedConstraints(decided)) =
+            AirAccumulationScheme::decide_with_report(&idk, (&instance, &witness))
+        else {
+            panic!["accumulated a wrong step"]
+        };
+        assert_eq!(decided.rows, report.rows);
+
+        // a wrong output breaks the second boundary
+        let (mut public_inputs, trace) = vm_trace(&runs[1].0, runs[1].1);
+        public_inputs[1] += Fr::one();
+        let Ok(Some(report)) = air.check_assignment(&public_inputs, &trace) else {
+            panic!["wrong output passed the check"]
+        };
+        assert_eq!(report.rows, vec![13]);
+    }
+
+    #[test]
+    pub fn test_air_rejects_malformed() {
+        let pp = R1CSNark::<Fr>::setup();
+        let wide = TransitionPolynomial::new().term(Fr::one(), vec![TraceCell::Next(4)]);
+
+        assert_eq!(
+            AirRelation::<Fr>::new(4, 0, vec![], vec![]).err(),
+            Some(AirError::EmptyTrace)
+        );
+        assert_eq!(
+            AirRelation::new(4, 2, vec![TransitionPolynomial::new(), wide], vec![]).err(),
+            Some(AirError::ColumnOutOfRange {
+                transition: 1,
+                column: 4
+            })
+        );
+        assert_eq!(
+            AirRelation::<Fr>::new(4, 2, vec![], vec![BoundaryConstraint::new(2, 0)]).err(),
+            Some(AirError::BoundaryOutOfRange {
+                boundary: 0,
+                row: 2,
+                column: 0
+            })
+        );
+
+        let air = vm_air(2);
+        let (ipk, _) = AirNark::index(&pp, &air);
+        let (public_inputs, mut trace) = vm_trace(&[Op::Add(1), Op::Mul(2)], 3);
+        assert_eq!(
+            AirNark::prove(&ipk, &air, &public_inputs[..1], &trace).err(),
+            Some(AirError::PublicInputCount {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            AirNark::prove(&ipk, &vm_air(3), &public_inputs, &trace).err(),
+            Some(AirError::KeyMismatch)
+        );
+        trace[1].pop();
+        assert_eq!(
+            AirNark::prove(&ipk, &air, &public_inputs, &trace).err(),
+            Some(AirError::TraceShape { rows: 3, width: 3 })
+        );
+    }
+}
@@ -0,0 +1,464 @@
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::Matrix;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::vec::Vec;
+
+use crate::bd_as::r1cs_nark::{
+    matrix_vec_mul, CircuitDigest, CodeParameters, HashParameters, IndexError, IndexInfo,
+    IndexProverKey, IndexVerifierKey, UnsatisfiedReport, MAX_REPORTED_ROWS,
+};
+
+use crate::bd_as::{CrossTerm, Proof};
+
+use super::CCSError;
+
+/// customizable constraint system over z = (x, w), with the constant one at z[0]:
+/// Σ_i c_i · ∘_{j ∈ S_i} M_j z = 0, where each multiset S_i lists the matrices whose
+/// products are multiplied together. R1CS is M = (A, B, C), S = ({0, 1}, {2}) and
+/// c = (1, −1); Plonkish gates and AIR transitions are sums of such products as well.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CCS<F: PrimeField> {
+    pub(crate) index_info: IndexInfo,
+    pub(crate) matrices: Vec<Matrix<F>>,
+    pub(crate) multisets: Vec<Vec<usize>>,
+    pub(crate) constants: Vec<F>,
+}
+
+impl<F: PrimeField> CCS<F> {
+    /// builds a CCS from its matrices, multisets and constants. Columns index the
+    /// assignment (instance || witness), with the constant one at column 0, and every
+    /// matrix has one row per constraint.
+    pub fn new(
+        num_instance_variables: usize,
+        num_witness_variables: usize,
+        matrices: Vec<Matrix<F>>,
+        multisets: Vec<Vec<usize>>,
+        constants: Vec<F>,
+    ) -> Result<Self, CCSError> {
+        if num_instance_variables == 0 {
+            return Err(CCSError::MissingConstantVariable);
+        }
+        if multisets.len() != constants.len() {
+            return Err(CCSError::ConstantCountMismatch {
+                multisets: multisets.len(),
+                constants: constants.len(),
+            });
+        }
+
+        let num_constraints = matrices.first().map_or(0, |matrix| matrix.len());
+        let num_variables = num_instance_variables + num_witness_variables;
+        for (index, matrix) in matrices.iter().enumerate() {
+            if matrix.len() != num_constraints {
+                return Err(CCSError::RowCountMismatch {
+                    matrix: index,
+                    rows: matrix.len(),
+                    expected: num_constraints,
+                });
+            }
+            for (row, entries) in matrix.iter().enumerate() {
+                let out_of_range = entries.iter().find(|(_, column)| *column >= num_variables);
+                if let Some((_, column)) = out_of_range {
+                    return Err(CCSError::ColumnOutOfRange {
+                        matrix: index,
+                        row,
+                        column: *column,
+                    });
+                }
+            }
+        }
+        for (index, multiset) in multisets.iter().enumerate() {
+            if let Some(matrix) = multiset.iter().find(|matrix| **matrix >= matrices.len()) {
+                return Err(CCSError::MatrixOutOfRange {
+                    multiset: index,
+                    matrix: *matrix,
+                });
+            }
+        }
+
+        Ok(CCS {
+            index_info: IndexInfo {
+                num_constraints,
+                num_variables,
+                num_instance_variables,
+            },
+            matrices,
+            multisets,
+            constants,
+        })
+    }
+
+    /// the R1CS of `ipk` as A z ∘ B z − C z = 0
+    pub fn from_r1cs(ipk: &IndexProverKey<F>) -> Self {
+        CCS {
+            index_info: ipk.index_info,
+            matrices: vec![ipk.a.clone(), ipk.b.clone(), ipk.c.clone()],
+            multisets: vec![vec![0, 1], vec![2]],
+            constants: vec![F::one(), -F::one()],
+        }
+    }
+
+    /// largest number of matrices multiplied together in a term
+    pub fn degree(&self) -> usize {
+        self.multisets.iter().map(|multiset| multiset.len()).max().unwrap_or(0)
+    }
+
+    /// degree every term is made homogeneous to when folding. A fold leaves one cross
+    /// term less than this, and never less than one.
+    pub(crate) fn folding_degree(&self) -> usize {
+        self.degree().max(2)
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.index_info.num_constraints
+    }
+
+    /// collision-resistant digest of the relation, bound into keys and instances the
+    /// same way as the digest of R1CS matrices
+    pub fn digest(&self) -> CircuitDigest {
+        let mut bytes = b"ccs".to_vec();
+        self.serialize_compressed(&mut bytes).unwrap();
+        CircuitDigest::from_bytes(bytes)
+    }
+
+    /// M_j z for every matrix
+    pub(crate) fn products(&self, input: &[F], witness: &[F]) -> Vec<Vec<F>> {
+        self.matrices
+            .iter()
+            .map(|matrix| matrix_vec_mul(matrix, input, witness))
+            .collect()
+    }
+
+    /// Σ_i c_i · u^{degree − |S_i|} · ∘_{j ∈ S_i} M_j z given the products M_j z, the
+    /// relation made homogeneous of `degree` in (z, u)
+    pub(crate) fn relaxed_evaluation(&self, products: &[Vec<F>], u: F, degree: usize) -> Vec<F> {
+        (0..self.index_info.num_constraints)
+            .map(|row| {
+                self.multisets
+                    .iter()
+                    .zip(&self.constants)
+                    .map(|(multiset, constant)| {
+                        let u_power = u.pow([(degree - multiset.len()) as u64]);
+                        multiset
+                            .iter()
+                            .fold(*constant * u_power, |acc, matrix| acc * products[*matrix][row])
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// checks Σ_i c_i · ∘_{j ∈ S_i} M_j z = 0 for z = (input, witness) and reports the
+    /// failing rows
+    pub fn check_assignment(&self, input: &[F], witness: &[F]) -> Option<UnsatisfiedReport> {
+        let products = self.products(input, witness);
+        let evaluation = self.relaxed_evaluation(&products, F::one(), self.degree());
+
+        let failing_rows: Vec<usize> = (0..evaluation.len())
+            .filter(|row| !evaluation[*row].is_zero())
+            .collect();
+        if failing_rows.is_empty() {
+            return None;
+        }
+
+        Some(UnsatisfiedReport {
+            num_unsatisfied: failing_rows.len(),
+            rows: failing_rows.into_iter().take(MAX_REPORTED_ROWS).collect(),
+            namespace: vec![],
+        })
+    }
+}
+
+/// prover key of a CCS. `members` holds the member digests of a `UniversalIndex` and
+/// is empty for any other relation.
+#[derive(Clone, CanonicalDeserialize, CanonicalSerialize)]
+pub struct CCSProverKey<F: PrimeField> {
+    pub(crate) ccs: CCS<F>,
+    pub(crate) hash_params: HashParameters<F>,
+    pub(crate) code_params: CodeParameters,
+    pub(crate) digest: CircuitDigest,
+    pub(crate) members: Vec<CircuitDigest>,
+}
+
+impl<F: PrimeField> CCSProverKey<F> {
+    pub(crate) fn new(ccs: CCS<F>) -> Self {
+        let digest = ccs.digest();
+        CCSProverKey {
+            code_params: CodeParameters::default().fitted_to(&ccs.index_info),
+            ccs,
+            hash_params: HashParameters::default(),
+            digest,
+            members: vec![],
+        }
+    }
+
+    /// key of a `UniversalIndex` over `ccs`, whose digest also covers the digests of
+    /// the members
+    pub(crate) fn universal(ccs: CCS<F>, members: Vec<CircuitDigest>) -> Self {
+        let mut bytes = b"universal".to_vec();
+        bytes.extend(ccs.digest().0);
+        for member in &members {
+            bytes.extend(member.0);
+        }
+        CCSProverKey {
+            digest: CircuitDigest::from_bytes(bytes),
+            members,
+            ..Self::new(ccs)
+        }
+    }
+
+    /// the same key folding with `code_params`, lengthened like the code of an
+    /// `IndexProverKey` if it is too short for the relation
+    pub fn with_code_params(mut self, code_params: CodeParameters) -> Self {
+        self.code_params = code_params.fitted_to(&self.ccs.index_info);
+        self
+    }
+
+    /// the same key committing with `hash_params`
+    pub fn with_hash_params(mut self, hash_params: HashParameters<F>) -> Self {
+        self.hash_params = hash_params;
+        self
+    }
+
+    pub fn code_params(&self) -> &CodeParameters {
+        &self.code_params
+    }
+
+    pub fn ccs(&self) -> &CCS<F> {
+        &self.ccs
+    }
+
+    /// digest of the relation this key was indexed from
+    pub fn digest(&self) -> CircuitDigest {
+        self.digest
+    }
+
+    /// whether `digest` describes the same relation as this key
+    pub fn is_compatible(&self, digest: &CircuitDigest) -> bool {
+        self.digest == *digest
+    }
+
+    /// the verifier key of the same relation
+    pub fn verifier_key(&self) -> CCSVerifierKey<F> {
+        CCSVerifierKey {
+            verifier_key: IndexVerifierKey {
+                index_info: self.ccs.index_info,
+                code_params: self.code_params,
+                hash_params: self.hash_params.clone(),
+                digest: self.digest,
+            },
+            degree: self.ccs.folding_degree(),
+            members: self.members.clone(),
+        }
+    }
+
+    /// the decider key of the same relation
+    pub fn decider_key(&self) -> CCSDeciderKey<F> {
+        CCSDeciderKey {
+            ccs: self.ccs.clone(),
+            code_params: self.code_params,
+            hash_params: self.hash_params.clone(),
+            digest: self.digest,
+        }
+    }
+}
+
+/// Decider key, the relation, code and hash parameters and digest. The decider
+/// evaluates the relation and recommits to the accumulator at the code length.
+#[derive(Clone, CanonicalDeserialize, CanonicalSerialize)]
+pub struct CCSDeciderKey<F: PrimeField> {
+    pub(crate) ccs: CCS<F>,
+    pub(crate) code_params: CodeParameters,
+    pub(crate) hash_params: HashParameters<F>,
+    pub(crate) digest: CircuitDigest,
+}
+
+impl<F: PrimeField> CCSDeciderKey<F> {
+    pub fn ccs(&self) -> &CCS<F> {
+        &self.ccs
+    }
+
+    /// digest of the relation this key was indexed from
+    pub fn digest(&self) -> CircuitDigest {
+        self.digest
+    }
+
+    /// whether `digest` describes the same relation as this key
+    pub fn is_compatible(&self, digest: &CircuitDigest) -> bool {
+        self.digest == *digest
+    }
+}
+
+/// Verifier key, the sizes and parameters of the R1CS verifier key, the folding degree,
+/// which fixes the number of cross terms of a fold, and the member digests of a
+/// `UniversalIndex`
+#[derive(Clone, CanonicalDeserialize, CanonicalSerialize)]
+pub struct CCSVerifierKey<F: PrimeField> {
+    pub(crate) verifier_key: IndexVerifierKey<F>,
+    pub(crate) degree: usize,
+    pub(crate) members: Vec<CircuitDigest>,
+}
+
+impl<F: PrimeField> CCSVerifierKey<F> {
+    /// the key of the R1CS scheme with the same sizes and parameters. Folds of degree
+    /// two are checked by the verifiers of that scheme, the in-circuit one included.
+    pub fn verifier_key(&self) -> &IndexVerifierKey<F> {
+        &self.verifier_key
+    }
+
+    /// number of matrices multiplied together in a term after folding
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// digest of the relation this key was indexed from
+    pub fn digest(&self) -> CircuitDigest {
+        self.verifier_key.digest
+    }
+}
+
+/// accumulation proof of `CCSAccumulationScheme`: the fold proof of the R1CS scheme,
+/// whose `t` is the cross term of r, and the cross terms of r², …, r^{d−1}
+#[derive(Clone)]
+pub struct CCSProof<F: PrimeField + Absorb> {
+    pub(crate) proof: Proof<F>,
+    pub(crate) cross_terms: Vec<CrossTerm<F>>,
+}
+
+impl<F: PrimeField + Absorb> CCSProof<F> {
+    /// the fold proof without the cross terms above r, all there is for degree two
+    pub fn proof(&self) -> &Proof<F> {
+        &self.proof
+    }
+}
+
+/// circuits padded to one envelope sharing a single CCS of that envelope, so that one
+/// accumulator absorbs proofs of any of them. The assignment is z = (1, s, x, w) with
+/// public selectors s_1, …, s_m ahead of the input and witness of the members, and
+/// member j contributes s_j · (A_j z ∘ B_j z − C_j z) to every row of the envelope.
+/// The rows after the envelope enforce s_j · s_j = s_j and Σ_j s_j = 1, so a proof
+/// selects exactly one member. Every fold absorbs the digest of the index and the
+/// digest of the member selected by the input into the transcript.
+#[derive(Clone, CanonicalDeserialize, CanonicalSerialize)]
+pub struct UniversalIndex<F: PrimeField> {
+    pub(crate) members: Vec<IndexProverKey<F>>,
+    pub(crate) key: CCSProverKey<F>,
+}
+
+impl<F: PrimeField> UniversalIndex<F> {
+    /// combines the keys of `members`, which all have to be padded to the same envelope
+    /// with the same number of instance variables
+    pub fn new(members: Vec<IndexProverKey<F>>) -> Result<Self, IndexError> {
+        let Some(first) = members.first() else {
+            return Err(IndexError::NoMembers);
+        };
+        let (envelope, index_info, code_params) =
+            (first.envelope(), first.index_info, first.code_params);
+        if let Some(member) = members.iter().position(|key| {
+            key.envelope() != envelope
+                || key.index_info.num_instance_variables != index_info.num_instance_variables
+        }) {
+            return Err(IndexError::EnvelopeMismatch { member });
+        }
+
+        let num_members = members.len();
+        let num_rows = index_info.num_constraints;
+        let num_constraints = num_rows + num_members + 1;
+        let selector = |member: usize| 1 + member;
+        let column = |column: usize| if column == 0 { 0 } else { num_members + column };
+
+        let (mut matrices, mut multisets, mut constants) = (vec![], vec![], vec![]);
+        for (member, key) in members.iter().enumerate() {
+            let mut gate: Matrix<F> = vec![vec![(F::one(), selector(member))]; num_rows];
+            gate.resize(num_constraints, vec![]);
+            let first_matrix = matrices.len();
+            matrices.push(gate);
+            for member_matrix in [&key.a, &key.b, &key.c] {
+                let mut matrix: Matrix<F> = member_matrix
+                    .iter()
+                    .map(|row| row.iter().map(|(coeff, index)| (*coeff, column(*index))).collect())
+                    .collect();
+                matrix.resize(num_constraints, vec![]);
+                matrices.push(matrix);
+            }
+            // s_j · A_j z ∘ B_j z − s_j · C_j z
+            multisets.push(vec![first_matrix, first_matrix + 1, first_matrix + 2]);
+            multisets.push(vec![first_matrix, first_matrix + 3]);
+            constants.extend([F::one(), -F::one()]);
+        }
+
+        // s_j · s_j − s_j on the row after the envelope of each member, Σ_j s_j − 1 on
+        // the last one
+        let (mut selectors, mut linear): (Matrix<F>, Matrix<F>) =
+            (vec![vec![]; num_rows], vec![vec![]; num_rows]);
+        for member in 0..num_members {
+            selectors.push(vec![(F::one(), selector(member))]);
+            linear.push(vec![(-F::one(), selector(member))]);
+        }
+        selectors.push(vec![]);
+        linear.push(
+            (0..num_members)
+                .map(|member| (F::one(), selector(member)))
+                .chain([(-F::one(), 0)])
+                .collect(),
+        );
+        multisets.push(vec![matrices.len(), matrices.len()]);
+        multisets.push(vec![matrices.len() + 1]);
+        constants.extend([F::one(), F::one()]);
+        matrices.extend([selectors, linear]);
+
+        let ccs = CCS::new(
+            num_members + index_info.num_instance_variables,
+            index_info.num_witness_variables(),
+            matrices,
+            multisets,
+            constants,
+        )
+        .expect("the rows refer to the selectors and the variables of the envelope");
+        let digests = members.iter().map(|key| key.digest).collect();
+        let key = CCSProverKey::universal(ccs, digests).with_code_params(code_params);
+        Ok(UniversalIndex { members, key })
+    }
+
+    /// the same index folding with `code_params`
+    pub fn with_code_params(mut self, code_params: CodeParameters) -> Self {
+        self.key = self.key.with_code_params(code_params);
+        self
+    }
+
+    /// the same index committing with `hash_params`
+    pub fn with_hash_params(mut self, hash_params: HashParameters<F>) -> Self {
+        self.key = self.key.with_hash_params(hash_params);
+        self
+    }
+
+    /// the key of the combined relation, used to fold, verify and decide
+    pub fn key(&self) -> &CCSProverKey<F> {
+        &self.key
+    }
+
+    pub fn num_members(&self) -> usize {
+        self.members.len()
+    }
+
+    /// digest of the combined relation and the member digests
+    pub fn digest(&self) -> CircuitDigest {
+        self.key.digest
+    }
+
+    /// digest of the padded circuit of `member`
+    pub fn member_digest(&self, member: usize) -> CircuitDigest {
+        self.members[member].digest
+    }
+
+    /// the input of the combined relation for an `input` of `member`, constant one
+    /// included: the constant one, the selectors with s_member = 1, and the rest of
+    /// `input`
+    pub fn embed_input(&self, member: usize, input: &[F]) -> Vec<F> {
+        let mut embedded = vec![F::zero(); 1 + self.members.len()];
+        embedded[0] = F::one();
+        embedded[1 + member] = F::one();
+        embedded.extend(input.iter().skip(1));
+        embedded
+    }
+}
@@ -0,0 +1,66 @@
+use ark_relations::r1cs::SynthesisError;
+use ark_std::fmt;
+
+/// reason a customizable constraint system, or an assignment to it, was rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CCSError {
+    /// there has to be at least one instance variable for the constant one
+    MissingConstantVariable,
+    /// `matrix` does not have the same number of rows as the first matrix
+    RowCountMismatch { matrix: usize, rows: usize, expected: usize },
+    /// an entry of `matrix` refers to a variable that does not exist
+    ColumnOutOfRange { matrix: usize, row: usize, column: usize },
+    /// `multiset` refers to a matrix that does not exist
+    MatrixOutOfRange { multiset: usize, matrix: usize },
+    /// there has to be one constant per multiset
+    ConstantCountMismatch { multisets: usize, constants: usize },
+    /// the assignment does not have the instance/witness split of the index
+    AssignmentShape { input: usize, witness: usize },
+    /// the circuit could not be synthesized, or is not the circuit of the key
+    Synthesis(SynthesisError),
+}
+
+impl fmt::Display for CCSError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CCSError::MissingConstantVariable => {
+                write!(f, "the instance must contain the constant one")
+            }
+            CCSError::RowCountMismatch {
+                matrix,
+                rows,
+                expected,
+            } => write!(
+                f,
+                "matrix {} has {} rows but the first matrix has {}",
+                matrix, rows, expected
+            ),
+            CCSError::ColumnOutOfRange {
+                matrix,
+                row,
+                column,
+            } => write!(
+                f,
+                "row {} of matrix {} refers to variable {} which does not exist",
+                row, matrix, column
+            ),
+            CCSError::MatrixOutOfRange { multiset, matrix } => write!(
+                f,
+                "multiset {} refers to matrix {} which does not exist",
+                multiset, matrix
+            ),
+            CCSError::ConstantCountMismatch {
+                multisets,
+                constants,
+            } => write!(f, "{} multisets but {} constants", multisets, constants),
+            CCSError::AssignmentShape { input, witness } => write!(
+                f,
+                "an assignment of {} input and {} witness variables does not fit the index",
+                input, witness
+            ),
+            CCSError::Synthesis(err) => write!(f, "synthesis failed: {}", err),
+        }
+    }
+}
+
+impl ark_std::error::Error for CCSError {}
@@ -0,0 +1,1063 @@
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisError, SynthesisMode,
+};
+use ark_std::cfg_into_iter;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+use crate::bd_as::r1cs_nark::{
+    CircuitDigest, IndexProverKey, Instance, Proof as NarkProof, PublicParameters, R1CSNark,
+    UnsatisfiedReport, VerificationError, Witness, MAX_REPORTED_ROWS,
+};
+use crate::bd_as::{
+    commit_accumulator, AccumulatorInstance, AccumulatorWitness, BDASAccumulationScheme,
+    CommittedVector, DecideError,
+};
+use crate::AccumulationScheme;
+
+mod data_structures;
+mod error;
+pub use data_structures::*;
+pub use error::*;
+
+/// NARK for CCS: the instance is the input and the Merkle root of the encoded witness,
+/// as for `R1CSNark`, so CCS proofs are folded by the same accumulation verifier
+pub struct CCSNark<F: PrimeField + Absorb> {
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CCSNark<F> {
+    /// generates index prover key and verifier key
+    pub fn index(_pp: &PublicParameters, ccs: CCS<F>) -> (CCSProverKey<F>, CCSVerifierKey<F>) {
+        let ipk = CCSProverKey::new(ccs);
+        let ivk = ipk.verifier_key();
+        (ipk, ivk)
+    }
+
+    /// indexes the R1CS of an arkworks circuit as a CCS
+    pub fn index_circuit<C: ConstraintSynthesizer<F>>(
+        pp: &PublicParameters,
+        circuit: C,
+    ) -> Result<(CCSProverKey<F>, CCSVerifierKey<F>), CCSError> {
+        let (r1cs_ipk, _) = R1CSNark::index(pp, circuit).map_err(CCSError::Synthesis)?;
+        Ok(Self::index(pp, CCS::from_r1cs(&r1cs_ipk)))
+    }
+
+    /// generates a proof for the assignment z = (input, witness). Satisfaction is not
+    /// checked here, see `CCS::check_assignment`.
+    pub fn prove(
+        ipk: &CCSProverKey<F>,
+        input: Vec<F>,
+        witness: Vec<F>,
+    ) -> Result<NarkProof<F>, CCSError> {
+        if !ipk.ccs.index_info.matches_split(input.len(), witness.len()) {
+            return Err(CCSError::AssignmentShape {
+                input: input.len(),
+                witness: witness.len(),
+            });
+        }
+
+        let witness_commitment =
+            CommittedVector::commit(&witness, &ipk.code_params, &ipk.hash_params);
+        Ok(NarkProof {
+            instance: Instance {
+                circuit_digest: ipk.digest,
+                input,
+                blinded_witness: witness_commitment.root(),
+            },
+            witness: Witness { witness },
+            commitment: Some(witness_commitment),
+        })
+    }
+
+    /// generates a proof from an arkworks circuit indexed by `index_circuit`. Fails with
+    /// `SynthesisError::MalformedVerifyingKey` if `ipk` was indexed from another circuit.
+    pub fn prove_circuit<C: ConstraintSynthesizer<F>>(
+        ipk: &CCSProverKey<F>,
+        circuit: C,
+    ) -> Result<NarkProof<F>, CCSError> {
+        let pcs = ConstraintSystem::new_ref();
+        pcs.set_optimization_goal(OptimizationGoal::Constraints);
+        pcs.set_mode(SynthesisMode::Prove {
+            construct_matrices: true,
+        });
+        circuit
+            .generate_constraints(pcs.clone())
+            .map_err(CCSError::Synthesis)?;
+        pcs.finalize();
+
+        let (input, witness) = {
+            let pcs = pcs.borrow().unwrap();
+            (
+                pcs.instance_assignment.clone(),
+                pcs.witness_assignment.clone(),
+            )
+        };
+        let matrices = pcs.to_matrices().expect("should not be `None`");
+        let r1cs_ipk = IndexProverKey::from_matrices(
+            input.len(),
+            witness.len(),
+            matrices.a,
+            matrices.b,
+            matrices.c,
+        )
+        .expect("the constraint system produced malformed matrices");
+        if !ipk.is_compatible(&CCS::from_r1cs(&r1cs_ipk).digest()) {
+            return Err(CCSError::Synthesis(SynthesisError::MalformedVerifyingKey));
+        }
+
+        Self::prove(ipk, input, witness)
+    }
+
+    /// generates a proof for `r1cs` as member `member` of `universal`, checked against the
+    /// key of that member. Its input is `universal.embed_input(member, ..)` of the input
+    /// of the member circuit, and its witness is the padded witness of the member. Fails
+    /// with `SynthesisError::MalformedVerifyingKey` if `r1cs` is not the circuit of
+    /// `member`.
+    pub fn prove_universal<C: ConstraintSynthesizer<F>>(
+        universal: &UniversalIndex<F>,
+        member: usize,
+        r1cs: C,
+    ) -> Result<NarkProof<F>, CCSError> {
+        let Some(member_key) = universal.members.get(member) else {
+            return Err(CCSError::Synthesis(SynthesisError::MalformedVerifyingKey));
+        };
+        let (_, input, witness) =
+            R1CSNark::synthesize(member_key, r1cs).map_err(CCSError::Synthesis)?;
+        Self::prove(&universal.key, universal.embed_input(member, &input), witness)
+    }
+
+    /// verifies a given proof and input with the decider key `idk` the way
+    /// `R1CSNark::verify` does, evaluating the CCS instead of the R1CS matrices
+    pub fn verify(
+        idk: &CCSDeciderKey<F>,
+        input: &[F],
+        proof: &NarkProof<F>,
+    ) -> Result<(), VerificationError> {
+        let index_info = &idk.ccs.index_info;
+        if !idk.is_compatible(&proof.instance.circuit_digest) {
+            return Err(VerificationError::KeyMismatch);
+        }
+        if input != proof.instance.input.as_slice() {
+            return Err(VerificationError::InputMismatch);
+        }
+        if input.len() != index_info.num_instance_variables {
+            return Err(VerificationError::InputLength {
+                expected: index_info.num_instance_variables,
+                found: input.len(),
+            });
+        }
+        if proof.witness.witness.len() != index_info.num_witness_variables() {
+            return Err(VerificationError::WitnessLength {
+                expected: index_info.num_witness_variables(),
+                found: proof.witness.witness.len(),
+            });
+        }
+
+        let witness_commitment =
+            CommittedVector::commit(&proof.witness.witness, &idk.code_params, &idk.hash_params);
+        if witness_commitment.root() != proof.instance.blinded_witness {
+            return Err(VerificationError::CommitmentMismatch);
+        }
+
+        match idk.ccs.check_assignment(input, &proof.witness.witness) {
+            Some(report) => Err(VerificationError::UnsatisfiedConstraint {
+                row: report.rows[0],
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// accumulation scheme for CCS. With d the folding degree of the relation (its degree,
+/// at least two), the accumulator is the relaxed relation
+/// Σ_i c_i · u^{d − |S_i|} · ∘_{j ∈ S_i} M_j (x, w) = err with u the accumulated c,
+/// which for R1CS is the accumulator of `BDASAccumulationScheme`. Folding leaves d − 1
+/// cross terms, each encoded and committed to, and the verifier spot-checks
+/// err' = err + r·t_1 + … + r^{d−1}·t_{d−1}. For degree two the proof is the one of
+/// `BDASAccumulationScheme` and is checked by the same verifiers, in-circuit included.
+#[derive(Clone)]
+pub struct CCSAccumulationScheme<F: PrimeField + Absorb> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> AccumulationScheme<F> for CCSAccumulationScheme<F> {
+    type AccumulatorInstance = AccumulatorInstance<F>;
+    type AccumulatorWitness = AccumulatorWitness<F>;
+    type Proof = CCSProof<F>;
+    type InputInstance = Instance<F>;
+    type InputWitness = Witness<F>;
+    type ProverKey = CCSProverKey<F>;
+    type VerifierKey = CCSVerifierKey<F>;
+    type DeciderKey = CCSDeciderKey<F>;
+
+    fn prove<'a>(
+        prover_key: &'a Self::ProverKey,
+        old_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
+        input: (&'a Self::InputInstance, &'a Self::InputWitness),
+    ) -> Result<
+        (
+            (Self::AccumulatorInstance, Self::AccumulatorWitness),
+            Self::Proof,
+        ),
+        SynthesisError,
+    > {
+        let (w_commitment, err_commitment) = commit_accumulator(
+            &prover_key.code_params,
+            &prover_key.hash_params,
+            old_accumulator,
+        )?;
+
+        let (new_accumulator, proof, _, _) = Self::fold(
+            prover_key,
+            old_accumulator,
+            input,
+            (&w_commitment, &err_commitment),
+            None,
+        )?;
+        Ok((new_accumulator, proof))
+    }
+
+    fn verify<'a>(
+        verifier_key: &'a Self::VerifierKey,
+        proof: &Self::Proof,
+        old_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
+        new_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
+        input_instance: &'a Self::InputInstance,
+    ) -> Result<bool, SynthesisError> {
+        // one cross term is in `proof.proof`, the others follow it
+        if proof.cross_terms.len() + 2 != verifier_key.degree {
+            return Ok(false);
+        }
+        let Some(domain) = transcript_domain(
+            verifier_key.digest(),
+            &verifier_key.members,
+            &input_instance.input,
+        ) else {
+            return Ok(false);
+        };
+        BDASAccumulationScheme::verify_with_cross_terms(
+            &verifier_key.verifier_key,
+            &domain,
+            (&proof.proof, &proof.cross_terms),
+            old_accumulator,
+            new_accumulator,
+            input_instance,
+        )
+    }
+
+    fn decide<'a>(
+        decider_key: &'a Self::DeciderKey,
+        accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
+    ) -> Result<bool, SynthesisError> {
+        Ok(Self::decide_with_report(decider_key, accumulator).is_ok())
+    }
+}
+
+impl<F: PrimeField + Absorb> CCSAccumulationScheme<F> {
+    /// the accumulator to start folding from
+    pub fn zero_accumulator(
+        prover_key: &CCSProverKey<F>,
+    ) -> (AccumulatorInstance<F>, AccumulatorWitness<F>) {
+        (
+            AccumulatorInstance::zero_for(prover_key.digest, &prover_key.ccs.index_info),
+            AccumulatorWitness::zero_for(&prover_key.code_params, &prover_key.hash_params),
+        )
+    }
+
+    /// same checks as `decide`, reporting the first one that fails like
+    /// `BDASAccumulationScheme::decide_with_report`
+    pub fn decide_with_report(
+        decider_key: &CCSDeciderKey<F>,
+        accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+    ) -> Result<(), DecideError> {
+        let (instance, witness) = accumulator;
+        let index_info = &decider_key.ccs.index_info;
+
+        if !decider_key.is_compatible(&instance.circuit_digest) {
+            return Err(DecideError::KeyMismatch);
+        }
+        if instance.x.len() != index_info.num_instance_variables {
+            return Err(DecideError::InputLength {
+                expected: index_info.num_instance_variables,
+                found: instance.x.len(),
+            });
+        }
+        if instance.w.len() != index_info.num_witness_variables() {
+            return Err(DecideError::WitnessLength {
+                expected: index_info.num_witness_variables(),
+                found: instance.w.len(),
+            });
+        }
+        if instance.err.len() != index_info.num_constraints {
+            return Err(DecideError::ErrLength {
+                expected: index_info.num_constraints,
+                found: instance.err.len(),
+            });
+        }
+
+        let (code_params, hash_params) = (&decider_key.code_params, &decider_key.hash_params);
+        let w_commitment = CommittedVector::commit(&instance.w, code_params, hash_params);
+        if w_commitment.root() != witness.blinded_w {
+            return Err(DecideError::WitnessCommitment);
+        }
+        let err_commitment = CommittedVector::commit(&instance.err, code_params, hash_params);
+        if err_commitment.root() != witness.blinded_err {
+            return Err(DecideError::ErrCommitment);
+        }
+
+        let ccs = &decider_key.ccs;
+        let evaluation = ccs.relaxed_evaluation(
+            &ccs.products(&instance.x, &instance.w),
+            instance.c,
+            ccs.folding_degree(),
+        );
+        let failing_rows: Vec<usize> = (0..evaluation.len())
+            .filter(|row| evaluation[*row] != instance.err[*row])
+            .collect();
+        if failing_rows.is_empty() {
+            return Ok(());
+        }
+        Err(DecideError::UnsatisfiedConstraints(UnsatisfiedReport {
+            num_unsatisfied: failing_rows.len(),
+            rows: failing_rows.into_iter().take(MAX_REPORTED_ROWS).collect(),
+            namespace: vec![],
+        }))
+    }
+
+    /// folds `input` into `old_accumulator` given the prover's commitments to the old
+    /// accumulator, with the same failure cases as `BDASAccumulationScheme::fold`. An
+    /// input of a `UniversalIndex` that does not select exactly one member fails with
+    /// `SynthesisError::Unsatisfiable`.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn fold(
+        prover_key: &CCSProverKey<F>,
+        old_accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        input: (&Instance<F>, &Witness<F>),
+        commitments: (&CommittedVector<F>, &CommittedVector<F>),
+        input_commitment: Option<&CommittedVector<F>>,
+    ) -> Result<
+        (
+            (AccumulatorInstance<F>, AccumulatorWitness<F>),
+            CCSProof<F>,
+            CommittedVector<F>,
+            CommittedVector<F>,
+        ),
+        SynthesisError,
+    > {
+        let (acc_instance, _) = old_accumulator;
+        let (input_instance, input_witness) = input;
+        if !prover_key.is_compatible(&acc_instance.circuit_digest)
+            || !prover_key.is_compatible(&input_instance.circuit_digest)
+        {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+
+        let ccs = &prover_key.ccs;
+        let index_info = &ccs.index_info;
+        if !index_info.matches_split(input_instance.input.len(), input_witness.witness.len())
+            || !index_info.matches_split(acc_instance.x.len(), acc_instance.w.len())
+            || acc_instance.err.len() != index_info.num_constraints
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // a fresh input is an instance of the relaxed relation with c = 1
+        let mut cross_terms = cross_terms(
+            ccs,
+            ccs.folding_degree(),
+            (&ccs.products(&acc_instance.x, &acc_instance.w), acc_instance.c),
+            (&ccs.products(&input_instance.input, &input_witness.witness), F::one()),
+        );
+        let t = cross_terms.remove(0);
+
+        let domain =
+            transcript_domain(prover_key.digest, &prover_key.members, &input_instance.input)
+                .ok_or(SynthesisError::Unsatisfiable)?;
+        let (new_accumulator, proof, cross_terms, new_w_commitment, new_err_commitment) =
+            BDASAccumulationScheme::fold_cross_terms(
+                (&prover_key.hash_params, &prover_key.code_params, prover_key.digest),
+                &domain,
+                old_accumulator,
+                input,
+                (t, cross_terms),
+                commitments,
+                input_commitment,
+            )?;
+        Ok((
+            new_accumulator,
+            CCSProof { proof, cross_terms },
+            new_w_commitment,
+            new_err_commitment,
+        ))
+    }
+}
+
+/// field elements a fold of `input` absorbs into its transcript ahead of the roots: none
+/// for a plain relation, and `digest` followed by the digest of the member selected by
+/// `input` for a key with `members`, see `UniversalIndex`. `None` if the selectors of
+/// `input` do not select exactly one member.
+fn transcript_domain<F: PrimeField>(
+    digest: CircuitDigest,
+    members: &[CircuitDigest],
+    input: &[F],
+) -> Option<Vec<F>> {
+    if members.is_empty() {
+        return Some(vec![]);
+    }
+    let selectors = input.get(1..=members.len())?;
+    let member = selectors.iter().position(|selector| selector.is_one())?;
+    if selectors.iter().enumerate().any(|(j, selector)| j != member && !selector.is_zero()) {
+        return None;
+    }
+    Some([digest.to_field_elements(), members[member].to_field_elements()].concat())
+}
+
+/// coefficients of r, …, r^{degree − 1} in the relaxed evaluation at
+/// (z_acc + r·z_in, u_acc + r·u_in), given the products M_j z and u of both sides. The
+/// coefficients of 1 and r^degree are the evaluations of the two sides themselves.
+pub(crate) fn cross_terms<F: PrimeField>(
+    ccs: &CCS<F>,
+    degree: usize,
+    accumulator: (&[Vec<F>], F),
+    input: (&[Vec<F>], F),
+) -> Vec<Vec<F>> {
+    let (acc_products, acc_u) = accumulator;
+    let (input_products, input_u) = input;
+
+    let rows: Vec<Vec<F>> = cfg_into_iter!(0..ccs.index_info.num_constraints)
+        .map(|row| {
+            let mut coeffs = vec![F::zero(); degree + 1];
+            for (multiset, constant) in ccs.multisets.iter().zip(&ccs.constants) {
+                let factors = multiset
+                    .iter()
+                    .map(|matrix| (acc_products[*matrix][row], input_products[*matrix][row]))
+                    .chain(ark_std::iter::repeat_n((acc_u, input_u), degree - multiset.len()));
+
+                // multiply out ∏ (a + r·b) one linear factor at a time
+                let mut term = vec![*constant];
+                for (a, b) in factors {
+                    let mut next = vec![F::zero(); term.len() + 1];
+                    for (k, coeff) in term.iter().enumerate() {
+                        next[k] += a * coeff;
+                        next[k + 1] += b * coeff;
+                    }
+                    term = next;
+                }
+                for (k, coeff) in term.into_iter().enumerate() {
+                    coeffs[k] += coeff;
+                }
+            }
+            coeffs
+        })
+        .collect();
+
+    (1..degree)
+        .map(|k| rows.iter().map(|coeffs| coeffs[k]).collect())
+        .collect()
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::r1cs_nark::CodeParameters;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_ff::{Field, One, Zero};
+    use crate::bd_as::r1cs_nark::{IndexEnvelope, IndexError};
+    use ark_relations::lc;
+    use ark_relations::r1cs::{Matrix, Variable};
+
+    /// x * x = y enforced twice over (1, y, x)
+    #[derive(Clone)]
+    pub struct SquareCirc {
+        x: Fr,
+    }
+    impl ConstraintSynthesizer<Fr> for SquareCirc {
+        fn generate_constraints(
+            self,
+            cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+        ) -> ark_relations::r1cs::Result<()> {
+            let y = cs.new_input_variable(|| Ok(self.x * self.x))?;
+            let x = cs.new_witness_variable(|| Ok(self.x))?;
+            for _ in 0..2 {
+                cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + y)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// x_1 + x_2 + x_3 = y as a single constraint over (1, y, x_1, x_2, x_3)
+    #[derive(Clone)]
+    pub struct SumCirc {
+        values: [u64; 3],
+    }
+    impl ConstraintSynthesizer<Fr> for SumCirc {
+        fn generate_constraints(
+            self,
+            cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+        ) -> ark_relations::r1cs::Result<()> {
+            let sum = cs.new_input_variable(|| Ok(Fr::from(self.values.iter().sum::<u64>())))?;
+            let mut sum_lc = lc!();
+            for value in self.values {
+                sum_lc = sum_lc + cs.new_witness_variable(|| Ok(Fr::from(value)))?;
+            }
+            cs.enforce_constraint(sum_lc, lc!() + Variable::One, lc!() + sum)?;
+            Ok(())
+        }
+    }
+
+    /// universal index of `SquareCirc` and `SumCirc` padded to 4 constraints and 6
+    /// variables
+    fn universal_index() -> UniversalIndex<Fr> {
+        let pp = R1CSNark::<Fr>::setup();
+        let envelope = IndexEnvelope::new(4, 6);
+        let (square_ipk, _) =
+            R1CSNark::<Fr>::index_padded(&pp, SquareCirc { x: Fr::from(2u64) }, envelope).unwrap();
+        let (sum_ipk, _) =
+            R1CSNark::<Fr>::index_padded(&pp, SumCirc { values: [1, 2, 3] }, envelope).unwrap();
+        UniversalIndex::new(vec![square_ipk, sum_ipk])
+            .unwrap()
+            .with_code_params(CodeParameters::with_num_queries(4))
+    }
+
+    /// single custom gate x·y + y·z − 3·o + 5 = 0 over z = (1, o, x, y, z), with the
+    /// constant as a term without matrices
+    fn gate_ccs() -> CCS<Fr> {
+        let select = |column: usize| -> Matrix<Fr> { vec![vec![(Fr::one(), column)]] };
+        CCS::new(
+            2,
+            3,
+            vec![select(2), select(3), select(4), select(1)],
+            vec![vec![0, 1], vec![1, 2], vec![3], vec![]],
+            vec![Fr::one(), Fr::one(), -Fr::from(3u64), Fr::from(5u64)],
+        )
+        .unwrap()
+    }
+
+    /// assignment of the gate for x, y, z, with o = (x·y + y·z + 5) / 3
+    fn gate_assignment(x: u64, y: u64, z: u64) -> (Vec<Fr>, Vec<Fr>) {
+        let (x, y, z) = (Fr::from(x), Fr::from(y), Fr::from(z));
+        let o = (x * y + y * z + Fr::from(5u64)) / Fr::from(3u64);
+        (vec![Fr::one(), o], vec![x, y, z])
+    }
+
+    /// folds `proofs` into the zero accumulator, checking every fold with the verifier.
+    /// Folds of degree two relations are also checked as R1CS folds.
+    pub(crate) fn fold_all(
+        ipk: &CCSProverKey<Fr>,
+        proofs: &[NarkProof<Fr>],
+    ) -> (AccumulatorInstance<Fr>, AccumulatorWitness<Fr>) {
+        let ivk = ipk.verifier_key();
+        let (mut acc_instance, mut acc_witness) = CCSAccumulationScheme::zero_accumulator(ipk);
+        for proof in proofs {
+            let Ok(((new_instance, new_witness), acc_proof)) = CCSAccumulationScheme::prove(
+                ipk,
+                (&acc_instance, &acc_witness),
+                (&proof.instance, &proof.witness),
+            ) else {
+                panic!["accumulation proof not generated"]
+            };
+            assert!(CCSAccumulationScheme::verify(
+                &ivk,
+                &acc_proof,
+                (&acc_instance, &acc_witness),
+                (&new_instance, &new_witness),
+                &proof.instance,
+            )
+            .unwrap());
+            if ivk.degree() == 2 {
+                assert!(BDASAccumulationScheme::verify(
+                    ivk.verifier_key(),
+                    acc_proof.proof(),
+                    (&acc_instance, &acc_witness),
+                    (&new_instance, &new_witness),
+                    &proof.instance,
+                )
+                .unwrap());
+            }
+            acc_instance = new_instance;
+            acc_witness = new_witness;
+        }
+        (acc_instance, acc_witness)
+    }
+
+    #[test]
+    pub fn test_ccs_code_fits_relation() {
+        // w_i = 0 on 300 rows, too many for the default code
+        let rows: Matrix<Fr> = (1..=300).map(|column| vec![(Fr::one(), column)]).collect();
+        let ccs = CCS::new(1, 300, vec![rows], vec![vec![0]], vec![Fr::one()]).unwrap();
+        let ipk = CCSProverKey::new(ccs);
+        assert_eq!(ipk.code_params().code_length(), 2 * crate::bd_as::CODE_LENGTH);
+
+        let ipk = ipk.with_code_params(CodeParameters::with_num_queries(4));
+        assert_eq!(ipk.code_params().code_length(), 2 * crate::bd_as::CODE_LENGTH);
+        assert_eq!(ipk.code_params().num_queries(), 4);
+
+        let (instance, witness) = CCSAccumulationScheme::zero_accumulator(&ipk);
+        assert!(CCSAccumulationScheme::decide(&ipk.decider_key(), (&instance, &witness)).unwrap());
+    }
+
+    #[test]
+    pub fn test_ccs_from_r1cs() {
+        let pp = R1CSNark::<Fr>::setup();
+        let (ipk, _) = CCSNark::index_circuit(&pp, SquareCirc { x: Fr::from(2u64) }).unwrap();
+        assert_eq!(ipk.ccs().degree(), 2);
+        let ipk = ipk.with_code_params(CodeParameters::with_num_queries(4));
+        let idk = ipk.decider_key();
+
+        let proofs: Vec<_> = (3..6u64)
+            .map(|x| CCSNark::prove_circuit(&ipk, SquareCirc { x: Fr::from(x) }).unwrap())
+            .collect();
+        for proof in &proofs {
+            assert_eq!(CCSNark::verify(&idk, &proof.instance.input, proof), Ok(()));
+        }
+
+        let (instance, witness) = fold_all(&ipk, &proofs);
+        assert_eq!(
+            CCSAccumulationScheme::decide_with_report(&idk, (&instance, &witness)),
+            Ok(())
+        );
+
+        // a degree two fold has no cross terms beyond t and is an R1CS fold proof
+        let (acc_instance, acc_witness) = CCSAccumulationScheme::zero_accumulator(&ipk);
+        let ((new_instance, new_witness), acc_proof) = CCSAccumulationScheme::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&proofs[0].instance, &proofs[0].witness),
+        )
+        .unwrap();
+        assert!(acc_proof.cross_terms.is_empty());
+        assert!(BDASAccumulationScheme::verify(
+            ipk.verifier_key().verifier_key(),
+            acc_proof.proof(),
+            (&acc_instance, &acc_witness),
+            (&new_instance, &new_witness),
+            &proofs[0].instance,
+        )
+        .unwrap());
+
+        // the R1CS key of the same circuit decides the same relaxed relation
+        let (r1cs_ipk, _) = R1CSNark::index(&pp, SquareCirc { x: Fr::from(2u64) }).unwrap();
+        let r1cs_idk = r1cs_ipk.decider_key();
+        let r1cs_instance = AccumulatorInstance {
+            circuit_digest: r1cs_ipk.digest(),
+            ..instance.clone()
+        };
+        assert!(BDASAccumulationScheme::decide(&r1cs_idk, (&r1cs_instance, &witness)).unwrap());
+
+        let mut tampered = instance;
+        tampered.c += Fr::one();
+        assert!(!CCSAccumulationScheme::decide(&idk, (&tampered, &witness)).unwrap());
+    }
+
+    #[test]
+    pub fn test_ccs_custom_gate() {
+        let pp = R1CSNark::<Fr>::setup();
+        let ccs = gate_ccs();
+        let (input, witness) = gate_assignment(2, 3, 4);
+        assert!(ccs.check_assignment(&input, &witness).is_none());
+        let unsatisfying = [Fr::from(2u64), Fr::from(3u64), Fr::one()];
+        let Some(report) = ccs.check_assignment(&input, &unsatisfying) else {
+            panic!["unsatisfying assignment passed the check"]
+        };
+        assert_eq!(report.rows, vec![0]);
+
+        let (ipk, ivk) = CCSNark::index(&pp, ccs);
+        let ipk = ipk.with_code_params(CodeParameters::with_num_queries(4));
+        let idk = ipk.decider_key();
+        let proofs: Vec<_> = [(2, 3, 4), (5, 7, 1), (0, 9, 6)]
+            .into_iter()
+            .map(|(x, y, z)| {
+                let (input, witness) = gate_assignment(x, y, z);
+                CCSNark::prove(&ipk, input, witness).unwrap()
+            })
+            .collect();
+        let (instance, witness) = fold_all(&ipk, &proofs);
+        assert!(CCSAccumulationScheme::decide(&idk, (&instance, &witness)).unwrap());
+
+        // an unsatisfying input folds but the accumulator is not decided
+        let mut bad = gate_assignment(1, 1, 1);
+        bad.0[1] += Fr::one();
+        let bad_proof = CCSNark::prove(&ipk, bad.0, bad.1).unwrap();
+        assert_eq!(
+            CCSNark::verify(&idk, &bad_proof.instance.input, &bad_proof),
+            Err(VerificationError::UnsatisfiedConstraint { row: 0 })
+        );
+        let (instance, witness) = fold_all(&ipk, &[proofs[0].clone(), bad_proof]);
+        let Err(DecideError::UnsatisfiedConstraints(report)) =
+            CCSAccumulationScheme::decide_with_report(&idk, (&instance, &witness))
+        else {
+            panic!["accumulated an unsatisfying input"]
+        };
+        assert_eq!(report.rows, vec![0]);
+
+        // proofs of another relation are not folded
+        let (other_ipk, _) = CCSNark::index_circuit(&pp, SquareCirc { x: Fr::from(2u64) }).unwrap();
+        let other_proof =
+            CCSNark::prove_circuit(&other_ipk, SquareCirc { x: Fr::from(3u64) }).unwrap();
+        let (acc_instance, acc_witness) = CCSAccumulationScheme::zero_accumulator(&ipk);
+        let Err(SynthesisError::MalformedVerifyingKey) = CCSAccumulationScheme::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&other_proof.instance, &other_proof.witness),
+        ) else {
+            panic!["folded a proof of another relation"]
+        };
+        assert_eq!(ivk.digest(), ipk.digest());
+
+        // roots that do not commit to the accumulator or the input are rejected
+        let mut bad_witness = acc_witness.clone();
+        bad_witness.blinded_err += Fr::one();
+        let Err(SynthesisError::Unsatisfiable) = CCSAccumulationScheme::prove(
+            &ipk,
+            (&acc_instance, &bad_witness),
+            (&proofs[0].instance, &proofs[0].witness),
+        ) else {
+            panic!["accumulator with a wrong root accumulated"]
+        };
+        let mut bad_input = proofs[0].instance.clone();
+        bad_input.blinded_witness += Fr::one();
+        let Err(SynthesisError::Unsatisfiable) = CCSAccumulationScheme::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&bad_input, &proofs[0].witness),
+        ) else {
+            panic!["input with a wrong root accumulated"]
+        };
+    }
+
+    #[test]
+    pub fn test_ccs_rejects_malformed() {
+        let pp = R1CSNark::<Fr>::setup();
+        let select = |column: usize| -> Matrix<Fr> { vec![vec![(Fr::one(), column)]] };
+
+        assert_eq!(
+            CCS::new(0, 1, vec![select(0)], vec![vec![0]], vec![Fr::one()]).err(),
+            Some(CCSError::MissingConstantVariable)
+        );
+        assert_eq!(
+            CCS::new(1, 1, vec![select(0), vec![]], vec![vec![0]], vec![Fr::one()]).err(),
+            Some(CCSError::RowCountMismatch {
+                matrix: 1,
+                rows: 0,
+                expected: 1
+            })
+        );
+        assert_eq!(
+            CCS::new(1, 1, vec![select(2)], vec![vec![0]], vec![Fr::one()]).err(),
+            Some(CCSError::ColumnOutOfRange {
+                matrix: 0,
+                row: 0,
+                column: 2
+            })
+        );
+        assert_eq!(
+            CCS::new(1, 1, vec![select(1)], vec![vec![1]], vec![Fr::one()]).err(),
+            Some(CCSError::MatrixOutOfRange {
+                multiset: 0,
+                matrix: 1
+            })
+        );
+        assert_eq!(
+            CCS::new(1, 1, vec![select(1)], vec![vec![0]], vec![]).err(),
+            Some(CCSError::ConstantCountMismatch {
+                multisets: 1,
+                constants: 0
+            })
+        );
+
+        let (ipk, _) = CCSNark::index(&pp, gate_ccs());
+        assert_eq!(
+            CCSNark::prove(&ipk, vec![Fr::one()], vec![Fr::one(); 4]).err(),
+            Some(CCSError::AssignmentShape {
+                input: 1,
+                witness: 4
+            })
+        );
+    }
+
+    /// rounds y_{i+1} = (y_i + k_i)^5 of a hash permutation, one degree five gate per
+    /// round over z = (1, y_0, y_n, y_1, …, y_{n−1})
+    fn hash_rounds_ccs(round_constants: &[Fr]) -> CCS<Fr> {
+        let num_rounds = round_constants.len();
+        let column = |i: usize| match i {
+            0 => 1,
+            i if i == num_rounds => 2,
+            i => 2 + i,
+        };
+        let (state, next): (Matrix<Fr>, Matrix<Fr>) = round_constants
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (vec![(Fr::one(), column(i)), (*k, 0)], vec![(Fr::one(), column(i + 1))]))
+            .unzip();
+        CCS::new(
+            3,
+            num_rounds - 1,
+            vec![state, next],
+            vec![vec![0; 5], vec![1]],
+            vec![Fr::one(), -Fr::one()],
+        )
+        .unwrap()
+    }
+
+    fn hash_rounds_assignment(round_constants: &[Fr], y: Fr) -> (Vec<Fr>, Vec<Fr>) {
+        let mut states = vec![y];
+        for k in round_constants {
+            let next = (*states.last().unwrap() + k).pow([5]);
+            states.push(next);
+        }
+        let output = states.pop().unwrap();
+        (vec![Fr::one(), states[0], output], states[1..].to_vec())
+    }
+
+    #[test]
+    pub fn test_high_degree_gates() {
+        let pp = R1CSNark::<Fr>::setup();
+        let round_constants: Vec<Fr> = (1..5u64).map(Fr::from).collect();
+        let (ipk, _) = CCSNark::index(&pp, hash_rounds_ccs(&round_constants));
+        let ipk = ipk.with_code_params(CodeParameters::with_num_queries(4));
+        let idk = ipk.decider_key();
+        let ivk = ipk.verifier_key();
+        assert_eq!(ivk.degree(), 5);
+
+        let proofs: Vec<_> = (2..5u64)
+            .map(|y| {
+                let (input, witness) = hash_rounds_assignment(&round_constants, Fr::from(y));
+                CCSNark::prove(&ipk, input, witness).unwrap()
+            })
+            .collect();
+        for proof in &proofs {
+            assert_eq!(CCSNark::verify(&idk, &proof.instance.input, proof), Ok(()));
+        }
+
+        // four cross terms: t in the R1CS proof and three committed beside it
+        let (mut acc_instance, mut acc_witness) = CCSAccumulationScheme::zero_accumulator(&ipk);
+        for proof in &proofs {
+            let ((new_instance, new_witness), acc_proof) = CCSAccumulationScheme::prove(
+                &ipk,
+                (&acc_instance, &acc_witness),
+                (&proof.instance, &proof.witness),
+            )
+            .unwrap();
+            assert_eq!(acc_proof.cross_terms.len(), 3);
+            let verify = |acc_proof: &CCSProof<Fr>| {
+                CCSAccumulationScheme::verify(
+                    &ivk,
+                    acc_proof,
+                    (&acc_instance, &acc_witness),
+                    (&new_instance, &new_witness),
+                    &proof.instance,
+                )
+                .unwrap()
+            };
+            assert!(verify(&acc_proof));
+
+            let mut tampered = acc_proof.clone();
+            tampered.cross_terms[2].t[0] += Fr::one();
+            assert!(!verify(&tampered));
+            let mut tampered = acc_proof.clone();
+            tampered.cross_terms.pop();
+            assert!(!verify(&tampered));
+            // the degree two verifier does not absorb the roots of the higher terms, so it
+            // derives another challenge and rejects the fold
+            assert!(!BDASAccumulationScheme::verify(
+                ivk.verifier_key(),
+                acc_proof.proof(),
+                (&acc_instance, &acc_witness),
+                (&new_instance, &new_witness),
+                &proof.instance,
+            )
+            .unwrap());
+
+            acc_instance = new_instance;
+            acc_witness = new_witness;
+        }
+        assert_eq!(
+            CCSAccumulationScheme::decide_with_report(&idk, (&acc_instance, &acc_witness)),
+            Ok(())
+        );
+
+        // a wrong round output is caught by the decider
+        let (input, mut witness) = hash_rounds_assignment(&round_constants, Fr::from(7u64));
+        witness[1] += Fr::one();
+        let bad_proof = CCSNark::prove(&ipk, input, witness).unwrap();
+        let ((instance, witness), _) = CCSAccumulationScheme::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&bad_proof.instance, &bad_proof.witness),
+        )
+        .unwrap();
+        let Err(DecideError::UnsatisfiedConstraints(report)) =
+            CCSAccumulationScheme::decide_with_report(&idk, (&instance, &witness))
+        else {
+            panic!["accumulated a wrong round output"]
+        };
+        assert_eq!(report.rows, vec![1, 2]);
+    }
+
+    #[test]
+    pub fn test_universal_index() {
+        let pp = R1CSNark::<Fr>::setup();
+        let universal = universal_index();
+        let (square_ipk, _) = R1CSNark::<Fr>::index_padded(
+            &pp,
+            SquareCirc { x: Fr::from(2u64) },
+            IndexEnvelope::new(4, 6),
+        )
+        .unwrap();
+        assert_eq!(universal.num_members(), 2);
+        assert_eq!(universal.member_digest(0), square_ipk.digest());
+        assert_ne!(universal.digest(), universal.member_digest(0));
+
+        // the envelope, with the selectors as two more variables and a row for each of
+        // them and the one-hot row after the envelope
+        let ccs = universal.key().ccs();
+        assert_eq!(ccs.degree(), 3);
+        assert_eq!(ccs.num_constraints(), 4 + 2 + 1);
+        assert_eq!(ccs.index_info.num_variables, 6 + 2);
+
+        let idk = universal.key().decider_key();
+        let proof = CCSNark::prove_universal(&universal, 1, SumCirc { values: [1, 2, 3] }).unwrap();
+        let input = universal.embed_input(1, &[Fr::one(), Fr::from(6u64)]);
+        assert_eq!(proof.instance.input, input);
+        assert_eq!(CCSNark::verify(&idk, &input, &proof), Ok(()));
+
+        // the same input claimed for the other member is not satisfied
+        let mut wrong_member = proof.clone();
+        wrong_member.instance.input = universal.embed_input(0, &[Fr::one(), Fr::from(6u64)]);
+        assert!(CCSNark::verify(&idk, &wrong_member.instance.input, &wrong_member).is_err());
+
+        let Err(CCSError::Synthesis(SynthesisError::MalformedVerifyingKey)) =
+            CCSNark::prove_universal(&universal, 0, SumCirc { values: [1, 2, 3] })
+        else {
+            panic!["proof generated for the wrong member"]
+        };
+
+        let (unpadded_ipk, _) =
+            R1CSNark::<Fr>::index(&pp, SquareCirc { x: Fr::from(2u64) }).unwrap();
+        assert_eq!(
+            UniversalIndex::new(vec![square_ipk.clone(), unpadded_ipk]).err(),
+            Some(IndexError::EnvelopeMismatch { member: 1 })
+        );
+        // the same envelope with another instance/witness split
+        let empty = vec![vec![]; 4];
+        let wide_ipk =
+            IndexProverKey::from_matrices(3, 3, empty.clone(), empty.clone(), empty).unwrap();
+        assert_eq!(
+            UniversalIndex::new(vec![square_ipk, wide_ipk]).err(),
+            Some(IndexError::EnvelopeMismatch { member: 1 })
+        );
+        assert_eq!(
+            UniversalIndex::<Fr>::new(vec![]).err(),
+            Some(IndexError::NoMembers)
+        );
+    }
+
+    #[test]
+    pub fn test_universal_accumulator() {
+        let universal = universal_index();
+        let ipk = universal.key();
+        let idk = ipk.decider_key();
+
+        // proofs of both circuits fold into one accumulator of the size of the envelope
+        let proofs = vec![
+            CCSNark::prove_universal(&universal, 0, SquareCirc { x: Fr::from(3u64) }).unwrap(),
+            CCSNark::prove_universal(&universal, 1, SumCirc { values: [4, 5, 6] }).unwrap(),
+            CCSNark::prove_universal(&universal, 0, SquareCirc { x: Fr::from(5u64) }).unwrap(),
+        ];
+        let (instance, witness) = fold_all(ipk, &proofs);
+        assert_eq!(instance.w.len(), 4);
+        assert_eq!(instance.err.len(), 4 + 2 + 1);
+        assert_eq!(
+            CCSAccumulationScheme::decide_with_report(&idk, (&instance, &witness)),
+            Ok(())
+        );
+
+        // the transcript absorbs the digest of the index and of the selected member
+        let domain = |member: usize| {
+            let input = universal.embed_input(member, &[Fr::one(), Fr::zero()]);
+            transcript_domain(universal.digest(), &ipk.members, &input).unwrap()
+        };
+        assert_eq!(domain(0)[..2], universal.digest().to_field_elements());
+        assert_eq!(domain(0)[2..], universal.member_digest(0).to_field_elements());
+        assert_eq!(domain(1)[2..], universal.member_digest(1).to_field_elements());
+
+        // so a verifier that does not absorb them derives other challenges
+        let (acc_instance, acc_witness) = CCSAccumulationScheme::zero_accumulator(ipk);
+        let proof = &proofs[1];
+        let ((new_instance, new_witness), acc_proof) = CCSAccumulationScheme::prove(
+            ipk,
+            (&acc_instance, &acc_witness),
+            (&proof.instance, &proof.witness),
+        )
+        .unwrap();
+        let verify = |ivk: &CCSVerifierKey<Fr>| {
+            CCSAccumulationScheme::verify(
+                ivk,
+                &acc_proof,
+                (&acc_instance, &acc_witness),
+                (&new_instance, &new_witness),
+                &proof.instance,
+            )
+            .unwrap()
+        };
+        let mut ivk = ipk.verifier_key();
+        assert!(verify(&ivk));
+        ivk.members.clear();
+        assert!(!verify(&ivk));
+    }
+
+    #[test]
+    pub fn test_universal_selectors_one_hot() {
+        let universal = universal_index();
+        let ipk = universal.key();
+        let idk = ipk.decider_key();
+
+        // with both selectors set, the zero witness satisfies both gated members and the
+        // selector rows, and only the one-hot row fails
+        let mut input = universal.embed_input(0, &[Fr::one(), Fr::zero()]);
+        input[2] = Fr::one();
+        let witness = vec![Fr::zero(); 4];
+        let Some(report) = ipk.ccs().check_assignment(&input, &witness) else {
+            panic!["two selectors passed the check"]
+        };
+        assert_eq!(report.rows, vec![6]);
+        let proof = CCSNark::prove(ipk, input, witness).unwrap();
+        assert_eq!(
+            CCSNark::verify(&idk, &proof.instance.input, &proof),
+            Err(VerificationError::UnsatisfiedConstraint { row: 6 })
+        );
+
+        // the fold has no member digest to absorb
+        let (acc_instance, acc_witness) = CCSAccumulationScheme::zero_accumulator(ipk);
+        let Err(SynthesisError::Unsatisfiable) = CCSAccumulationScheme::prove(
+            ipk,
+            (&acc_instance, &acc_witness),
+            (&proof.instance, &proof.witness),
+        ) else {
+            panic!["folded an input selecting two members"]
+        };
+
+        // and the verifier rejects an honest fold claimed for an input selecting two
+        let honest = CCSNark::prove_universal(&universal, 0, SquareCirc { x: Fr::from(3u64) })
+            .unwrap();
+        let ((new_instance, new_witness), acc_proof) = CCSAccumulationScheme::prove(
+            ipk,
+            (&acc_instance, &acc_witness),
+            (&honest.instance, &honest.witness),
+        )
+        .unwrap();
+        let mut two_selectors = honest.instance.clone();
+        two_selectors.input[2] = Fr::one();
+        assert!(!CCSAccumulationScheme::verify(
+            &ipk.verifier_key(),
+            &acc_proof,
+            (&acc_instance, &acc_witness),
+            (&new_instance, &new_witness),
+            &two_selectors,
+        )
+        .unwrap());
+    }
+}
@@ -0,0 +1,1205 @@
+use ark_crypto_primitives::crh::CRHSchemeGadget;
+use ark_crypto_primitives::merkle_tree::constraints::PathVar;
+use ark_crypto_primitives::merkle_tree::Path;
+use ark_crypto_primitives::prf::blake2s::constraints::Blake2sGadget;
+use ark_crypto_primitives::prf::PRFGadget;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_r1cs_std::uint8::UInt8;
+use ark_r1cs_std::{R1CSVar, ToBitsGadget, ToBytesGadget};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use ark_std::borrow::Borrow;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+use crate::bd_as::r1cs_nark::constraints::{
+    HashCRHGadget, HashParametersVar, InstanceVar, MerkleHashConfigVar,
+};
+use crate::bd_as::r1cs_nark::{CodeParameters, IndexVerifierKey, MerkleHashConfig};
+use crate::bd_as::{
+    AccumulatorInstance, AccumulatorWitness, CommittedAccumulator, MergeProof, Proof,
+};
+
+/// Merkle opening of a single codeword position
+pub type OpeningVar<F> = PathVar<MerkleHashConfig<F>, F, MerkleHashConfigVar<F>>;
+
+/// in-circuit accumulator instance. As for `InstanceVar` the circuit digest is fixed by
+/// the verifier key and not allocated.
+#[derive(Clone)]
+pub struct AccumulatorInstanceVar<F: PrimeField> {
+    pub(crate) x: Vec<FpVar<F>>,
+    pub(crate) w: Vec<FpVar<F>>,
+    pub(crate) err: Vec<FpVar<F>>,
+    pub(crate) c: FpVar<F>,
+}
+
+impl<F: PrimeField> AccumulatorInstanceVar<F> {
+    /// the folded public input
+    pub fn public_input(&self) -> &[FpVar<F>] {
+        &self.x
+    }
+}
+
+impl<F: PrimeField> AllocVar<AccumulatorInstance<F>, F> for AccumulatorInstanceVar<F> {
+    fn new_variable<T: Borrow<AccumulatorInstance<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|instance| {
+            let instance = instance.borrow();
+            Ok(AccumulatorInstanceVar {
+                x: Vec::new_variable(ark_relations::ns!(cs, "x"), || Ok(instance.x.clone()), mode)?,
+                w: Vec::new_variable(ark_relations::ns!(cs, "w"), || Ok(instance.w.clone()), mode)?,
+                err: Vec::new_variable(
+                    ark_relations::ns!(cs, "err"),
+                    || Ok(instance.err.clone()),
+                    mode,
+                )?,
+                c: FpVar::new_variable(ark_relations::ns!(cs, "c"), || Ok(instance.c), mode)?,
+            })
+        })
+    }
+}
+
+/// in-circuit accumulator witness, the Merkle roots of the encoded w and err
+#[derive(Clone)]
+pub struct AccumulatorWitnessVar<F: PrimeField> {
+    pub(crate) blinded_w: FpVar<F>,
+    pub(crate) blinded_err: FpVar<F>,
+}
+
+impl<F: PrimeField + Absorb> AllocVar<AccumulatorWitness<F>, F> for AccumulatorWitnessVar<F> {
+    fn new_variable<T: Borrow<AccumulatorWitness<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|witness| {
+            let witness = witness.borrow();
+            Ok(AccumulatorWitnessVar {
+                blinded_w: FpVar::new_variable(
+                    ark_relations::ns!(cs, "blinded_w"),
+                    || Ok(witness.blinded_w),
+                    mode,
+                )?,
+                blinded_err: FpVar::new_variable(
+                    ark_relations::ns!(cs, "blinded_err"),
+                    || Ok(witness.blinded_err),
+                    mode,
+                )?,
+            })
+        })
+    }
+}
+
+/// in-circuit accumulator given by its public input, its constant and the roots of the
+/// encoded w and err. This is what a recursive circuit carries from step to step; the
+/// vectors themselves are only seen through the openings of an accumulation proof.
+#[derive(Clone)]
+pub struct CommittedAccumulatorVar<F: PrimeField> {
+    pub(crate) x: Vec<FpVar<F>>,
+    pub(crate) c: FpVar<F>,
+    pub(crate) blinded_w: FpVar<F>,
+    pub(crate) blinded_err: FpVar<F>,
+}
+
+impl<F: PrimeField> CommittedAccumulatorVar<F> {
+    pub fn from_parts(
+        instance: &AccumulatorInstanceVar<F>,
+        witness: &AccumulatorWitnessVar<F>,
+    ) -> Self {
+        CommittedAccumulatorVar {
+            x: instance.x.clone(),
+            c: instance.c.clone(),
+            blinded_w: witness.blinded_w.clone(),
+            blinded_err: witness.blinded_err.clone(),
+        }
+    }
+
+    /// the folded public input
+    pub fn public_input(&self) -> &[FpVar<F>] {
+        &self.x
+    }
+
+    /// the accumulator as field elements: x, c, blinded_w, blinded_err
+    pub fn to_field_vars(&self) -> Vec<FpVar<F>> {
+        let mut vars = self.x.clone();
+        vars.push(self.c.clone());
+        vars.push(self.blinded_w.clone());
+        vars.push(self.blinded_err.clone());
+        vars
+    }
+}
+
+impl<F: PrimeField> CondSelectGadget<F> for CommittedAccumulatorVar<F> {
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        if true_value.x.len() != false_value.x.len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        Ok(CommittedAccumulatorVar {
+            x: true_value
+                .x
+                .iter()
+                .zip(&false_value.x)
+                .map(|(t, f)| FpVar::conditionally_select(cond, t, f))
+                .collect::<Result<_, _>>()?,
+            c: FpVar::conditionally_select(cond, &true_value.c, &false_value.c)?,
+            blinded_w: FpVar::conditionally_select(
+                cond,
+                &true_value.blinded_w,
+                &false_value.blinded_w,
+            )?,
+            blinded_err: FpVar::conditionally_select(
+                cond,
+                &true_value.blinded_err,
+                &false_value.blinded_err,
+            )?,
+        })
+    }
+}
+
+impl<F: PrimeField> AllocVar<CommittedAccumulator<F>, F> for CommittedAccumulatorVar<F> {
+    fn new_variable<T: Borrow<CommittedAccumulator<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|accumulator| {
+            let accumulator = accumulator.borrow();
+            Ok(CommittedAccumulatorVar {
+                x: Vec::new_variable(
+                    ark_relations::ns!(cs, "x"),
+                    || Ok(accumulator.x.clone()),
+                    mode,
+                )?,
+                c: FpVar::new_variable(ark_relations::ns!(cs, "c"), || Ok(accumulator.c), mode)?,
+                blinded_w: FpVar::new_variable(
+                    ark_relations::ns!(cs, "blinded_w"),
+                    || Ok(accumulator.blinded_w),
+                    mode,
+                )?,
+                blinded_err: FpVar::new_variable(
+                    ark_relations::ns!(cs, "blinded_err"),
+                    || Ok(accumulator.blinded_err),
+                    mode,
+                )?,
+            })
+        })
+    }
+}
+
+/// in-circuit accumulation proof without the cross term, i.e. the openings and the
+/// opened values. Together with the roots of a `CommittedAccumulatorVar` this is all
+/// `AccumulationVerifierGadget::verify_committed` needs, so its size does not depend on
+/// the number of constraints of the accumulated circuit.
+#[derive(Clone)]
+pub struct CommittedProofVar<F: PrimeField + Absorb> {
+    pub(crate) acc_openings: Vec<OpeningVar<F>>,
+    pub(crate) new_acc_openings: Vec<OpeningVar<F>>,
+    pub(crate) input_openings: Vec<OpeningVar<F>>,
+    pub(crate) err_openings: Vec<OpeningVar<F>>,
+    pub(crate) new_err_openings: Vec<OpeningVar<F>>,
+    pub(crate) t_openings: Vec<OpeningVar<F>>,
+    pub(crate) input_values: Vec<FpVar<F>>,
+    pub(crate) acc_values: Vec<FpVar<F>>,
+    pub(crate) err_values: Vec<FpVar<F>>,
+    pub(crate) t_values: Vec<FpVar<F>>,
+    pub(crate) blinded_t: FpVar<F>,
+}
+
+impl<F: PrimeField + Absorb> AllocVar<Proof<F>, F> for CommittedProofVar<F> {
+    fn new_variable<T: Borrow<Proof<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|proof| {
+            let proof = proof.borrow();
+            let new_values = |values: &[F]| {
+                Vec::new_variable(ark_relations::ns!(cs, "values"), || Ok(values.to_vec()), mode)
+            };
+            Ok(CommittedProofVar {
+                acc_openings: new_openings(cs.clone(), &proof.acc_openings, mode)?,
+                new_acc_openings: new_openings(cs.clone(), &proof.new_acc_openings, mode)?,
+                input_openings: new_openings(cs.clone(), &proof.input_openings, mode)?,
+                err_openings: new_openings(cs.clone(), &proof.err_openings, mode)?,
+                new_err_openings: new_openings(cs.clone(), &proof.new_err_openings, mode)?,
+                t_openings: new_openings(cs.clone(), &proof.t_openings, mode)?,
+                input_values: new_values(&proof.input_values)?,
+                acc_values: new_values(&proof.acc_values)?,
+                err_values: new_values(&proof.err_values)?,
+                t_values: new_values(&proof.t_values)?,
+                blinded_t: FpVar::new_variable(
+                    ark_relations::ns!(cs, "blinded_t"),
+                    || Ok(proof.blinded_t),
+                    mode,
+                )?,
+            })
+        })
+    }
+}
+
+/// in-circuit accumulation proof
+#[derive(Clone)]
+pub struct ProofVar<F: PrimeField + Absorb> {
+    pub(crate) committed: CommittedProofVar<F>,
+    pub(crate) t: Vec<FpVar<F>>,
+}
+
+impl<F: PrimeField + Absorb> AllocVar<Proof<F>, F> for ProofVar<F> {
+    fn new_variable<T: Borrow<Proof<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|proof| {
+            let proof = proof.borrow();
+            Ok(ProofVar {
+                committed: CommittedProofVar::new_variable(cs.clone(), || Ok(proof), mode)?,
+                t: Vec::new_variable(ark_relations::ns!(cs, "t"), || Ok(proof.t.clone()), mode)?,
+            })
+        })
+    }
+}
+
+/// in-circuit merge proof without the cross term, see `CommittedProofVar`
+#[derive(Clone)]
+pub struct CommittedMergeProofVar<F: PrimeField + Absorb> {
+    pub(crate) acc_openings: Vec<OpeningVar<F>>,
+    pub(crate) other_openings: Vec<OpeningVar<F>>,
+    pub(crate) new_acc_openings: Vec<OpeningVar<F>>,
+    pub(crate) err_openings: Vec<OpeningVar<F>>,
+    pub(crate) other_err_openings: Vec<OpeningVar<F>>,
+    pub(crate) new_err_openings: Vec<OpeningVar<F>>,
+    pub(crate) t_openings: Vec<OpeningVar<F>>,
+    pub(crate) acc_values: Vec<FpVar<F>>,
+    pub(crate) other_values: Vec<FpVar<F>>,
+    pub(crate) err_values: Vec<FpVar<F>>,
+    pub(crate) other_err_values: Vec<FpVar<F>>,
+    pub(crate) t_values: Vec<FpVar<F>>,
+    pub(crate) blinded_t: FpVar<F>,
+}
+
+impl<F: PrimeField + Absorb> AllocVar<MergeProof<F>, F> for CommittedMergeProofVar<F> {
+    fn new_variable<T: Borrow<MergeProof<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|proof| {
+            let proof = proof.borrow();
+            let new_values = |values: &[F]| {
+                Vec::new_variable(ark_relations::ns!(cs, "values"), || Ok(values.to_vec()), mode)
+            };
+            Ok(CommittedMergeProofVar {
+                acc_openings: new_openings(cs.clone(), &proof.acc_openings, mode)?,
+                other_openings: new_openings(cs.clone(), &proof.other_openings, mode)?,
+                new_acc_openings: new_openings(cs.clone(), &proof.new_acc_openings, mode)?,
+                err_openings: new_openings(cs.clone(), &proof.err_openings, mode)?,
+                other_err_openings: new_openings(cs.clone(), &proof.other_err_openings, mode)?,
+                new_err_openings: new_openings(cs.clone(), &proof.new_err_openings, mode)?,
+                t_openings: new_openings(cs.clone(), &proof.t_openings, mode)?,
+                acc_values: new_values(&proof.acc_values)?,
+                other_values: new_values(&proof.other_values)?,
+                err_values: new_values(&proof.err_values)?,
+                other_err_values: new_values(&proof.other_err_values)?,
+                t_values: new_values(&proof.t_values)?,
+                blinded_t: FpVar::new_variable(
+                    ark_relations::ns!(cs, "blinded_t"),
+                    || Ok(proof.blinded_t),
+                    mode,
+                )?,
+            })
+        })
+    }
+}
+
+fn new_openings<F: PrimeField + Absorb>(
+    cs: ConstraintSystemRef<F>,
+    openings: &[Path<MerkleHashConfig<F>>],
+    mode: AllocationMode,
+) -> Result<Vec<OpeningVar<F>>, SynthesisError> {
+    openings
+        .iter()
+        .map(|opening| {
+            OpeningVar::new_variable(ark_relations::ns!(cs, "opening"), || Ok(opening), mode)
+        })
+        .collect()
+}
+
+/// R1CS version of `BDASAccumulationScheme::verify`
+pub struct AccumulationVerifierGadget<F: PrimeField + Absorb> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> AccumulationVerifierGadget<F> {
+    /// returns a boolean that is true iff the native verifier accepts the same proof,
+    /// accumulators and input. The key is baked into the circuit, so the sizes of the
+    /// allocated vectors are checked while synthesizing. The codeword length of the key
+    /// has to be a power of two for the queried positions to be derived in-circuit.
+    pub fn verify(
+        verifier_key: &IndexVerifierKey<F>,
+        proof: &ProofVar<F>,
+        old_accumulator: (&AccumulatorInstanceVar<F>, &AccumulatorWitnessVar<F>),
+        new_accumulator: (&AccumulatorInstanceVar<F>, &AccumulatorWitnessVar<F>),
+        input_instance: &InstanceVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let index_info = &verifier_key.index_info;
+        let (acc_instance, acc_witness) = old_accumulator;
+        let (new_acc_instance, new_acc_witness) = new_accumulator;
+
+        if proof.t.len() != index_info.num_constraints
+            || input_instance.input.len() != index_info.num_instance_variables
+        {
+            return Ok(Boolean::FALSE);
+        }
+
+        let cs = proof.committed.blinded_t.cs();
+        let hash_params = HashParametersVar::new_constant(
+            ark_relations::ns!(cs, "hash_params"),
+            &verifier_key.hash_params,
+        )?;
+        let digest = verifier_key.digest.to_field_elements().map(FpVar::constant);
+        let old = CommittedAccumulatorVar::from_parts(acc_instance, acc_witness);
+        let new = CommittedAccumulatorVar::from_parts(new_acc_instance, new_acc_witness);
+
+        let Some((accepted, r, indexes)) = Self::check_committed(
+            &verifier_key.code_params,
+            &hash_params,
+            &digest,
+            &proof.committed,
+            &old,
+            &new,
+            input_instance,
+        )?
+        else {
+            return Ok(Boolean::FALSE);
+        };
+
+        // the opened values have to be the codewords of the vectors at the queried points,
+        // and the codewords of the new w and err the folded ones
+        let committed = &proof.committed;
+        let mut checks = vec![accepted];
+        for (query, index) in indexes.iter().enumerate() {
+            let point = code_point(index)?;
+            let new_w_value = &committed.acc_values[query] + &r * &committed.input_values[query];
+            let new_err_value = &committed.err_values[query] + &r * &committed.t_values[query];
+            let codes = [
+                (&acc_instance.w, &committed.acc_values[query]),
+                (&acc_instance.err, &committed.err_values[query]),
+                (&proof.t, &committed.t_values[query]),
+                (&new_acc_instance.w, &new_w_value),
+                (&new_acc_instance.err, &new_err_value),
+            ];
+            for (vector, value) in codes {
+                checks.push(evaluate_code(vector, &point).is_eq(value)?);
+            }
+        }
+
+        Boolean::kary_and(&checks)
+    }
+
+    /// verifies a fold of `input_instance` into `old_accumulator` given only the roots of
+    /// the accumulated vectors. Every check of `verify` is made except that the opened
+    /// values are taken from the proof instead of being recomputed from w, err and t, so
+    /// the soundness of the folded vectors rests on the decider, as for the native
+    /// verifier. The code and hash parameters are those of the accumulated circuit, and
+    /// `digest` is its digest as two field elements (see `CircuitDigest`), which the
+    /// recursive circuits take as a witness since they cannot contain their own digest.
+    pub fn verify_committed(
+        code_params: &CodeParameters,
+        hash_params: &HashParametersVar<F>,
+        digest: &[FpVar<F>],
+        proof: &CommittedProofVar<F>,
+        old_accumulator: &CommittedAccumulatorVar<F>,
+        new_accumulator: &CommittedAccumulatorVar<F>,
+        input_instance: &InstanceVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        match Self::check_committed(
+            code_params,
+            hash_params,
+            digest,
+            proof,
+            old_accumulator,
+            new_accumulator,
+            input_instance,
+        )? {
+            Some((accepted, _, _)) => Ok(accepted),
+            None => Ok(Boolean::FALSE),
+        }
+    }
+
+    /// R1CS version of `BDASAccumulationScheme::verify_merge` given only the roots of the
+    /// merged accumulators, taking the opened values from the proof and the digest of the
+    /// circuit as `verify_committed` does
+    pub fn verify_merge_committed(
+        code_params: &CodeParameters,
+        hash_params: &HashParametersVar<F>,
+        digest: &[FpVar<F>],
+        proof: &CommittedMergeProofVar<F>,
+        accumulator: &CommittedAccumulatorVar<F>,
+        other: &CommittedAccumulatorVar<F>,
+        merged: &CommittedAccumulatorVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let num_queries = code_params.num_queries;
+
+        if !code_params.code_length.is_power_of_two() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let index_bits = code_params.code_length.trailing_zeros() as usize;
+
+        if [
+            proof.acc_openings.len(),
+            proof.other_openings.len(),
+            proof.new_acc_openings.len(),
+            proof.err_openings.len(),
+            proof.other_err_openings.len(),
+            proof.new_err_openings.len(),
+            proof.t_openings.len(),
+            proof.acc_values.len(),
+            proof.other_values.len(),
+            proof.err_values.len(),
+            proof.other_err_values.len(),
+            proof.t_values.len(),
+        ]
+        .iter()
+        .any(|len| *len != num_queries)
+            || other.x.len() != accumulator.x.len()
+            || merged.x.len() != accumulator.x.len()
+        {
+            return Ok(Boolean::FALSE);
+        }
+
+        let mut statement = digest.to_vec();
+        statement.extend(accumulator.to_field_vars());
+        statement.extend(other.to_field_vars());
+        let (r, opening_indexes) = derive_challenges(
+            hash_params,
+            num_queries,
+            &[
+                HashCRHGadget::evaluate(hash_params, &statement)?,
+                other.blinded_w.clone(),
+                accumulator.blinded_w.clone(),
+                proof.blinded_t.clone(),
+            ],
+            index_bits,
+        )?;
+
+        let mut checks = vec![];
+        for (query, index) in opening_indexes.iter().enumerate() {
+            let w_value = &proof.acc_values[query];
+            let other_w_value = &proof.other_values[query];
+            let err_value = &proof.err_values[query];
+            let other_err_value = &proof.other_err_values[query];
+            let t_value = &proof.t_values[query];
+            let new_w_value = w_value + &r * other_w_value;
+            let new_err_value = err_value + &r * (t_value + &r * other_err_value);
+
+            let openings = [
+                (&proof.acc_openings, &accumulator.blinded_w, w_value),
+                (&proof.other_openings, &other.blinded_w, other_w_value),
+                (&proof.new_acc_openings, &merged.blinded_w, &new_w_value),
+                (&proof.err_openings, &accumulator.blinded_err, err_value),
+                (&proof.other_err_openings, &other.blinded_err, other_err_value),
+                (&proof.new_err_openings, &merged.blinded_err, &new_err_value),
+                (&proof.t_openings, &proof.blinded_t, t_value),
+            ];
+            for (opening, root, value) in openings {
+                checks.push(verify_opening(hash_params, &opening[query], root, index, value)?);
+            }
+        }
+
+        checks.push(merged.c.is_eq(&(&accumulator.c + &r * &other.c))?);
+        for ((new_x, x), other_x) in merged.x.iter().zip(&accumulator.x).zip(&other.x) {
+            checks.push(new_x.is_eq(&(x + &r * other_x))?);
+        }
+
+        Boolean::kary_and(&checks)
+    }
+
+    /// the checks of `verify_committed`, along with the folding challenge and the
+    /// little-endian bits of the queried positions. Returns `None` when the sizes of the
+    /// allocated vectors are inconsistent.
+    #[allow(clippy::type_complexity)]
+    fn check_committed(
+        code_params: &CodeParameters,
+        hash_params: &HashParametersVar<F>,
+        digest: &[FpVar<F>],
+        proof: &CommittedProofVar<F>,
+        old_accumulator: &CommittedAccumulatorVar<F>,
+        new_accumulator: &CommittedAccumulatorVar<F>,
+        input_instance: &InstanceVar<F>,
+    ) -> Result<Option<(Boolean<F>, FpVar<F>, Vec<Vec<Boolean<F>>>)>, SynthesisError> {
+        let num_queries = code_params.num_queries;
+
+        if !code_params.code_length.is_power_of_two() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let index_bits = code_params.code_length.trailing_zeros() as usize;
+
+        if [
+            proof.input_openings.len(),
+            proof.acc_openings.len(),
+            proof.new_acc_openings.len(),
+            proof.t_openings.len(),
+            proof.err_openings.len(),
+            proof.new_err_openings.len(),
+            proof.input_values.len(),
+            proof.acc_values.len(),
+            proof.err_values.len(),
+            proof.t_values.len(),
+        ]
+        .iter()
+        .any(|len| *len != num_queries)
+            || old_accumulator.x.len() != input_instance.input.len()
+            || new_accumulator.x.len() != input_instance.input.len()
+        {
+            return Ok(None);
+        }
+
+        let mut statement = digest.to_vec();
+        statement.extend_from_slice(&input_instance.input);
+        statement.extend(old_accumulator.to_field_vars());
+        let roots = [
+            HashCRHGadget::evaluate(hash_params, &statement)?,
+            input_instance.blinded_witness.clone(),
+            old_accumulator.blinded_w.clone(),
+            proof.blinded_t.clone(),
+        ];
+        let (r, opening_indexes) = derive_challenges(hash_params, num_queries, &roots, index_bits)?;
+
+        let mut checks = vec![];
+        for (query, index) in opening_indexes.iter().enumerate() {
+            let z_value = &proof.input_values[query];
+            let w_value = &proof.acc_values[query];
+            let t_value = &proof.t_values[query];
+            let err_value = &proof.err_values[query];
+            let new_w_value = w_value + &r * z_value;
+            let new_err_value = err_value + &r * t_value;
+
+            let openings = [
+                (&proof.input_openings, &input_instance.blinded_witness, z_value),
+                (&proof.acc_openings, &old_accumulator.blinded_w, w_value),
+                (&proof.new_acc_openings, &new_accumulator.blinded_w, &new_w_value),
+                (&proof.t_openings, &proof.blinded_t, t_value),
+                (&proof.err_openings, &old_accumulator.blinded_err, err_value),
+                (&proof.new_err_openings, &new_accumulator.blinded_err, &new_err_value),
+            ];
+            for (opening, root, value) in openings {
+                checks.push(verify_opening(hash_params, &opening[query], root, index, value)?);
+            }
+        }
+
+        checks.push(new_accumulator.c.is_eq(&(&old_accumulator.c + &r))?);
+
+        // the public input is folded in the clear
+        for ((new_x, x), input) in new_accumulator
+            .x
+            .iter()
+            .zip(&old_accumulator.x)
+            .zip(&input_instance.input)
+        {
+            checks.push(new_x.is_eq(&(x + &r * input))?);
+        }
+
+        Ok(Some((Boolean::kary_and(&checks)?, r, opening_indexes)))
+    }
+}
+
+/// checks that `opening` opens `root` to `value` at the position with little-endian
+/// bits `index`
+fn verify_opening<F: PrimeField + Absorb>(
+    hash_params: &HashParametersVar<F>,
+    opening: &OpeningVar<F>,
+    root: &FpVar<F>,
+    index: &[Boolean<F>],
+    value: &FpVar<F>,
+) -> Result<Boolean<F>, SynthesisError> {
+    // an opening of a tree of another height cannot be at the queried position
+    if opening.get_leaf_position().len() != index.len() {
+        return Ok(Boolean::FALSE);
+    }
+    let mut opening = opening.clone();
+    opening.set_leaf_position(index.to_vec());
+    opening.verify_membership(hash_params, hash_params, root, ark_std::slice::from_ref(value))
+}
+
+/// in-circuit `get_randomness` and `get_random_indices`: the folding challenge and the
+/// little-endian bits of the queried positions, from the transcript over `roots`
+#[allow(clippy::type_complexity)]
+fn derive_challenges<F: PrimeField + Absorb>(
+    hash_params: &HashParametersVar<F>,
+    num_queries: usize,
+    roots: &[FpVar<F>],
+    index_bits: usize,
+) -> Result<(FpVar<F>, Vec<Vec<Boolean<F>>>), SynthesisError> {
+    let parameters = match hash_params {
+        HashParametersVar::Poseidon(_) => {
+            return derive_blake2s_challenges(num_queries, roots, index_bits)
+        }
+        HashParametersVar::Poseidon2(parameters) => parameters,
+    };
+    let challenge = parameters.hash(roots)?;
+    let mut r = challenge.clone();
+    let mut indices = vec![];
+    for _ in 0..num_queries {
+        r = parameters.hash(ark_std::slice::from_ref(&r))?;
+        // the lowest 64 bits reduced modulo the code length are its lowest `index_bits`
+        let mut bits = r.to_bits_le()?;
+        bits.truncate(index_bits);
+        indices.push(bits);
+    }
+
+    Ok((challenge, indices))
+}
+
+#[allow(clippy::type_complexity)]
+fn derive_blake2s_challenges<F: PrimeField>(
+    num_queries: usize,
+    roots: &[FpVar<F>],
+    index_bits: usize,
+) -> Result<(FpVar<F>, Vec<Vec<Boolean<F>>>), SynthesisError> {
+    let seed = field_vars_to_fixed_bytes(&roots[..1])?;
+    let mut inp = field_vars_to_fixed_bytes(&roots[1..2])?;
+    for root in &roots[2..] {
+        let key = field_vars_to_fixed_bytes(ark_std::slice::from_ref(root))?;
+        inp = <Blake2sGadget as PRFGadget<_, F>>::evaluate(&key, &inp)?.0;
+    }
+    let mut r = hash_to_field_vars(&seed, &inp)?;
+    let challenge = r[0].clone();
+
+    inp = field_vars_to_fixed_bytes(&r)?;
+
+    let mut indices = vec![];
+    for _ in 0..num_queries {
+        r = hash_to_field_vars(&seed, &inp)?;
+        inp = field_vars_to_fixed_bytes(&r)?;
+        // the index is the big-endian u64 in the first 8 bytes reduced modulo the code
+        // length, i.e. its lowest `index_bits` bits
+        let mut bits = vec![];
+        for byte in inp[..8].iter().rev() {
+            bits.extend(byte.to_bits_le()?);
+        }
+        bits.truncate(index_bits);
+        indices.push(bits);
+    }
+
+    Ok((challenge, indices))
+}
+
+fn hash_to_field_vars<F: PrimeField>(
+    seed: &[UInt8<F>],
+    input: &[UInt8<F>],
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    let output = <Blake2sGadget as PRFGadget<_, F>>::evaluate(seed, input)?;
+    bytes_to_field_vars(&output.0)
+}
+
+/// in-circuit `field_vec_to_fixed_bytes`
+fn field_vars_to_fixed_bytes<F: PrimeField>(
+    field_elems: &[FpVar<F>],
+) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    let elements_needed = 256usize.div_ceil(F::MODULUS_BIT_SIZE as usize);
+
+    let mut bytes = vec![];
+    for elem in field_elems.iter().take(elements_needed) {
+        bytes.extend(elem.to_bytes()?);
+    }
+    bytes.resize(32, UInt8::constant(0));
+    Ok(bytes)
+}
+
+/// in-circuit `bytes_to_field_vec`, every chunk is read big-endian and reduced modulo p
+fn bytes_to_field_vars<F: PrimeField>(bytes: &[UInt8<F>]) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    let bytes_per_elem = (F::MODULUS_BIT_SIZE as usize).div_ceil(8);
+
+    let mut result = vec![];
+    for chunk in bytes.chunks(bytes_per_elem) {
+        let mut elem = FpVar::zero();
+        let mut coeff = F::one();
+        for byte in chunk.iter().rev() {
+            for bit in byte.to_bits_le()? {
+                elem += FpVar::from(bit) * coeff;
+                coeff.double_in_place();
+            }
+        }
+        result.push(elem);
+    }
+    Ok(result)
+}
+
+/// g^(i + 1) for the position i with little-endian bits `index`, the point position i
+/// of a Reed-Solomon codeword is evaluated at
+fn code_point<F: PrimeField>(index: &[Boolean<F>]) -> Result<FpVar<F>, SynthesisError> {
+    let mut point = FpVar::constant(F::GENERATOR);
+    let mut power = F::GENERATOR;
+    for bit in index {
+        point *= FpVar::conditionally_select(bit, &FpVar::constant(power), &FpVar::one())?;
+        power.square_in_place();
+    }
+    Ok(point)
+}
+
+/// the codeword of `values` at `point`
+fn evaluate_code<F: PrimeField>(values: &[FpVar<F>], point: &FpVar<F>) -> FpVar<F> {
+    values
+        .iter()
+        .rev()
+        .fold(FpVar::zero(), |acc, value| acc * point + value)
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::r1cs_nark::poseidon2::poseidon2_parameters;
+    use crate::bd_as::r1cs_nark::{
+        CircuitDigest, HashParameters, IndexProverKey, Instance, Witness,
+    };
+    use crate::bd_as::{AccumulatorProver, BDASAccumulationScheme, CommittedVector};
+    use crate::AccumulationScheme;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_ff::One;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// runs the gadget on freshly allocated witnesses and returns its verdict
+    fn verify_in_circuit(
+        prover_key: &IndexProverKey<Fr>,
+        proof: &Proof<Fr>,
+        old_accumulator: &(AccumulatorInstance<Fr>, AccumulatorWitness<Fr>),
+        new_accumulator: &(AccumulatorInstance<Fr>, AccumulatorWitness<Fr>),
+        input_instance: &Instance<Fr>,
+    ) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let proof_var = ProofVar::new_witness(cs.clone(), || Ok(proof)).unwrap();
+        let old_instance_var =
+            AccumulatorInstanceVar::new_witness(cs.clone(), || Ok(&old_accumulator.0)).unwrap();
+        let old_witness_var =
+            AccumulatorWitnessVar::new_witness(cs.clone(), || Ok(&old_accumulator.1)).unwrap();
+        let new_instance_var =
+            AccumulatorInstanceVar::new_witness(cs.clone(), || Ok(&new_accumulator.0)).unwrap();
+        let new_witness_var =
+            AccumulatorWitnessVar::new_witness(cs.clone(), || Ok(&new_accumulator.1)).unwrap();
+        let input_var = InstanceVar::new_witness(cs.clone(), || Ok(input_instance)).unwrap();
+
+        let accepted = AccumulationVerifierGadget::verify(
+            &prover_key.verifier_key(),
+            &proof_var,
+            (&old_instance_var, &old_witness_var),
+            (&new_instance_var, &new_witness_var),
+            &input_var,
+        )
+        .unwrap();
+        // the verdict is computed, not enforced, so the constraints always hold
+        assert!(cs.is_satisfied().unwrap());
+        accepted.value().unwrap()
+    }
+
+    fn verify_natively(
+        prover_key: &IndexProverKey<Fr>,
+        proof: &Proof<Fr>,
+        old_accumulator: &(AccumulatorInstance<Fr>, AccumulatorWitness<Fr>),
+        new_accumulator: &(AccumulatorInstance<Fr>, AccumulatorWitness<Fr>),
+        input_instance: &Instance<Fr>,
+    ) -> bool {
+        BDASAccumulationScheme::<Fr>::verify(
+            &prover_key.verifier_key(),
+            proof,
+            (&old_accumulator.0, &old_accumulator.1),
+            (&new_accumulator.0, &new_accumulator.1),
+            input_instance,
+        )
+        .unwrap()
+    }
+
+    /// x * x = x2, x2 * x = y over (1, y, x, x2)
+    fn cube_input(prover_key: &IndexProverKey<Fr>, x: u64) -> (Instance<Fr>, Witness<Fr>) {
+        let witness = vec![Fr::from(x), Fr::from(x * x)];
+        let instance = Instance {
+            circuit_digest: prover_key.digest(),
+            input: vec![Fr::one(), Fr::from(x * x * x)],
+            blinded_witness: CommittedVector::commit(
+                &witness,
+                &prover_key.code_params,
+                &prover_key.hash_params,
+            )
+            .root(),
+        };
+        (instance, Witness { witness })
+    }
+
+    #[test]
+    pub fn test_gadget_matches_native_verifier() {
+        let a = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 3)]];
+        let b = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 2)]];
+        let c = vec![vec![(Fr::one(), 3)], vec![(Fr::one(), 1)]];
+        let ipk = IndexProverKey::from_matrices(2, 2, a, b, c).unwrap();
+
+        let zero_accumulator = (
+            AccumulatorInstance::zero(ipk.clone()),
+            AccumulatorWitness::zero(ipk.clone()),
+        );
+        let (first_instance, first_witness) = cube_input(&ipk, 3);
+        let (first_accumulator, first_proof) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&zero_accumulator.0, &zero_accumulator.1),
+            (&first_instance, &first_witness),
+        )
+        .unwrap();
+        let (second_instance, second_witness) = cube_input(&ipk, 5);
+        let (second_accumulator, second_proof) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&first_accumulator.0, &first_accumulator.1),
+            (&second_instance, &second_witness),
+        )
+        .unwrap();
+
+        let mut cases = vec![
+            (
+                first_proof.clone(),
+                zero_accumulator.clone(),
+                first_accumulator.clone(),
+                first_instance.clone(),
+                true,
+            ),
+            (
+                second_proof.clone(),
+                first_accumulator.clone(),
+                second_accumulator.clone(),
+                second_instance.clone(),
+                true,
+            ),
+        ];
+
+        let mut proof = second_proof.clone();
+        proof.input_values[0] += Fr::one();
+        cases.push((
+            proof,
+            first_accumulator.clone(),
+            second_accumulator.clone(),
+            second_instance.clone(),
+            false,
+        ));
+
+        let mut proof = second_proof.clone();
+        proof.t[0] += Fr::one();
+        cases.push((
+            proof,
+            first_accumulator.clone(),
+            second_accumulator.clone(),
+            second_instance.clone(),
+            false,
+        ));
+
+        // an opening of another position
+        let mut proof = second_proof.clone();
+        proof.new_acc_openings.swap(0, 1);
+        cases.push((
+            proof,
+            first_accumulator.clone(),
+            second_accumulator.clone(),
+            second_instance.clone(),
+            false,
+        ));
+
+        // all paths of the zero accumulator's tree are the same, so the position a path
+        // claims does not matter
+        let mut proof = first_proof.clone();
+        proof.acc_openings.swap(0, 1);
+        cases.push((
+            proof,
+            zero_accumulator.clone(),
+            first_accumulator.clone(),
+            first_instance.clone(),
+            true,
+        ));
+
+        let mut new_accumulator = second_accumulator.clone();
+        new_accumulator.0.c += Fr::one();
+        cases.push((
+            second_proof.clone(),
+            first_accumulator.clone(),
+            new_accumulator,
+            second_instance.clone(),
+            false,
+        ));
+
+        let mut new_accumulator = second_accumulator.clone();
+        new_accumulator.0.x[1] += Fr::one();
+        cases.push((
+            second_proof.clone(),
+            first_accumulator.clone(),
+            new_accumulator,
+            second_instance.clone(),
+            false,
+        ));
+
+        // a new w or err other than the folded one, under the roots of the folded ones
+        let mut new_accumulator = second_accumulator.clone();
+        new_accumulator.0.w[0] += Fr::one();
+        cases.push((
+            second_proof.clone(),
+            first_accumulator.clone(),
+            new_accumulator,
+            second_instance.clone(),
+            false,
+        ));
+
+        let mut new_accumulator = second_accumulator.clone();
+        new_accumulator.0.err[0] += Fr::one();
+        cases.push((
+            second_proof.clone(),
+            first_accumulator.clone(),
+            new_accumulator,
+            second_instance.clone(),
+            false,
+        ));
+
+        // folding the wrong input
+        cases.push((
+            second_proof,
+            first_accumulator,
+            second_accumulator,
+            first_instance,
+            false,
+        ));
+
+        for (case, (proof, old_accumulator, new_accumulator, input_instance, expected)) in
+            cases.into_iter().enumerate()
+        {
+            let native =
+                verify_natively(&ipk, &proof, &old_accumulator, &new_accumulator, &input_instance);
+            let in_circuit = verify_in_circuit(
+                &ipk,
+                &proof,
+                &old_accumulator,
+                &new_accumulator,
+                &input_instance,
+            );
+            assert_eq!(native, expected, "case {case}");
+            assert_eq!(in_circuit, expected, "case {case}");
+        }
+    }
+    /// runs `verify_committed` on freshly allocated witnesses and returns its verdict
+    fn verify_committed_in_circuit(
+        prover_key: &IndexProverKey<Fr>,
+        proof: &Proof<Fr>,
+        old_accumulator: &(AccumulatorInstance<Fr>, AccumulatorWitness<Fr>),
+        new_accumulator: &(AccumulatorInstance<Fr>, AccumulatorWitness<Fr>),
+        input_instance: &Instance<Fr>,
+    ) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let hash_params =
+            HashParametersVar::new_constant(cs.clone(), &prover_key.hash_params).unwrap();
+        let proof_var = CommittedProofVar::new_witness(cs.clone(), || Ok(proof)).unwrap();
+        let old_var = CommittedAccumulatorVar::new_witness(cs.clone(), || {
+            Ok(CommittedAccumulator::new(&old_accumulator.0, &old_accumulator.1))
+        })
+        .unwrap();
+        let new_var = CommittedAccumulatorVar::new_witness(cs.clone(), || {
+            Ok(CommittedAccumulator::new(&new_accumulator.0, &new_accumulator.1))
+        })
+        .unwrap();
+        let input_var = InstanceVar::new_witness(cs.clone(), || Ok(input_instance)).unwrap();
+        let digest =
+            Vec::new_witness(cs.clone(), || Ok(prover_key.digest.to_field_elements().to_vec()))
+                .unwrap();
+
+        let accepted = AccumulationVerifierGadget::verify_committed(
+            &CodeParameters::default(),
+            &hash_params,
+            &digest,
+            &proof_var,
+            &old_var,
+            &new_var,
+            &input_var,
+        )
+        .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        accepted.value().unwrap()
+    }
+
+    #[test]
+    pub fn test_committed_gadget() {
+        let a = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 3)]];
+        let b = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 2)]];
+        let c = vec![vec![(Fr::one(), 3)], vec![(Fr::one(), 1)]];
+        let ipk = IndexProverKey::from_matrices(2, 2, a, b, c).unwrap();
+
+        let zero_accumulator = (
+            AccumulatorInstance::zero(ipk.clone()),
+            AccumulatorWitness::zero(ipk.clone()),
+        );
+        let (instance, witness) = cube_input(&ipk, 3);
+        let (accumulator, proof) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&zero_accumulator.0, &zero_accumulator.1),
+            (&instance, &witness),
+        )
+        .unwrap();
+
+        assert_eq!(
+            CommittedAccumulator::zero(2, &ipk.code_params, &ipk.hash_params),
+            CommittedAccumulator::new(&zero_accumulator.0, &zero_accumulator.1)
+        );
+
+        let verify = |proof: &Proof<Fr>, new_accumulator| {
+            verify_committed_in_circuit(&ipk, proof, &zero_accumulator, new_accumulator, &instance)
+        };
+        assert!(verify(&proof, &accumulator));
+
+        // the cross term itself is not looked at, only its opened values
+        let mut tampered = proof.clone();
+        tampered.t[0] += Fr::one();
+        assert!(verify(&tampered, &accumulator));
+        assert!(!verify_in_circuit(&ipk, &tampered, &zero_accumulator, &accumulator, &instance));
+
+        let mut tampered = proof.clone();
+        tampered.t_values[0] += Fr::one();
+        assert!(!verify(&tampered, &accumulator));
+
+        let mut tampered = proof.clone();
+        tampered.acc_values[1] += Fr::one();
+        assert!(!verify(&tampered, &accumulator));
+
+        let mut new_accumulator = accumulator.clone();
+        new_accumulator.1.blinded_err += Fr::one();
+        assert!(!verify(&proof, &new_accumulator));
+
+        // the challenge is bound to the digest of the key
+        let mut other_key = ipk.clone();
+        other_key.digest = CircuitDigest([1; 32]);
+        assert!(!verify_committed_in_circuit(
+            &other_key,
+            &proof,
+            &zero_accumulator,
+            &accumulator,
+            &instance
+        ));
+    }
+    #[test]
+    pub fn test_poseidon2_gadgets() {
+        let a = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 3)]];
+        let b = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 2)]];
+        let c = vec![vec![(Fr::one(), 3)], vec![(Fr::one(), 1)]];
+        let ipk = IndexProverKey::from_matrices(2, 2, a, b, c)
+            .unwrap()
+            .with_hash_params(HashParameters::Poseidon2(poseidon2_parameters()));
+
+        let zero_accumulator = (
+            AccumulatorInstance::zero(ipk.clone()),
+            AccumulatorWitness::zero(ipk.clone()),
+        );
+        let (instance, witness) = cube_input(&ipk, 3);
+        let (accumulator, proof) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&zero_accumulator.0, &zero_accumulator.1),
+            (&instance, &witness),
+        )
+        .unwrap();
+        assert!(BDASAccumulationScheme::decide(
+            &ipk.decider_key(),
+            (&accumulator.0, &accumulator.1)
+        )
+        .unwrap());
+
+        assert!(verify_in_circuit(&ipk, &proof, &zero_accumulator, &accumulator, &instance));
+        assert!(verify_committed_in_circuit(
+            &ipk,
+            &proof,
+            &zero_accumulator,
+            &accumulator,
+            &instance
+        ));
+
+        let mut tampered = proof.clone();
+        tampered.t_values[0] += Fr::one();
+        assert!(!verify_committed_in_circuit(
+            &ipk,
+            &tampered,
+            &zero_accumulator,
+            &accumulator,
+            &instance
+        ));
+    }
+
+    #[test]
+    pub fn test_merge_gadget() {
+        let a = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 3)]];
+        let b = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 2)]];
+        let c = vec![vec![(Fr::one(), 3)], vec![(Fr::one(), 1)]];
+        let ipk = IndexProverKey::from_matrices(2, 2, a, b, c).unwrap();
+
+        let mut left = AccumulatorProver::new(ipk.clone());
+        let mut right = AccumulatorProver::new(ipk.clone());
+        let (instance, witness) = cube_input(&ipk, 3);
+        left.prove((&instance, &witness), None).unwrap();
+        let (instance, witness) = cube_input(&ipk, 5);
+        right.prove((&instance, &witness), None).unwrap();
+
+        let (other_instance, other_witness) = right.accumulator();
+        let other = CommittedAccumulator::new(other_instance, other_witness);
+        let (acc_instance, acc_witness) = left.accumulator();
+        let accumulator = CommittedAccumulator::new(acc_instance, acc_witness);
+        let ((merged_instance, merged_witness), proof) =
+            left.merge((other_instance, other_witness)).unwrap();
+        let merged = CommittedAccumulator::new(&merged_instance, &merged_witness);
+
+        let verify_with = |proof: &MergeProof<Fr>, merged, digest: CircuitDigest| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let hash_params =
+                HashParametersVar::new_constant(cs.clone(), &ipk.hash_params).unwrap();
+            let digest =
+                Vec::new_witness(cs.clone(), || Ok(digest.to_field_elements().to_vec())).unwrap();
+            let proof_var = CommittedMergeProofVar::new_witness(cs.clone(), || Ok(proof)).unwrap();
+            let [acc_var, other_var, merged_var] = [&accumulator, &other, merged].map(|acc| {
+                CommittedAccumulatorVar::new_witness(cs.clone(), || Ok(acc)).unwrap()
+            });
+            let accepted = AccumulationVerifierGadget::verify_merge_committed(
+                &CodeParameters::default(),
+                &hash_params,
+                &digest,
+                &proof_var,
+                &acc_var,
+                &other_var,
+                &merged_var,
+            )
+            .unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            accepted.value().unwrap()
+        };
+        let verify = |proof, merged| verify_with(proof, merged, ipk.digest);
+        assert!(verify(&proof, &merged));
+        assert!(!verify_with(&proof, &merged, CircuitDigest([1; 32])));
+
+        let mut tampered = proof.clone();
+        tampered.other_err_values[0] += Fr::one();
+        assert!(!verify(&tampered, &merged));
+
+        let mut tampered = merged.clone();
+        tampered.x[0] += Fr::one();
+        assert!(!verify(&proof, &tampered));
+    }
+}
@@ -1,21 +1,43 @@
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_crypto_primitives::merkle_tree::{Path, MerkleTree};
-use crate::bd_as::r1cs_nark::{MerkleHashConfig, poseidon_parameters, IndexProverKey};
+use crate::bd_as::r1cs_nark::{
+    CircuitDigest, CodeParameters, HashCRH, HashParameters, HashTwoToOneCRH, IndexInfo,
+    IndexProverKey, MerkleHashConfig,
+};
+use ark_crypto_primitives::crh::{CRHScheme, TwoToOneCRHScheme};
+use crate::bd_as::{add_vectors, scalar_mult, RSCode};
 use ark_ff::PrimeField;
 use ark_crypto_primitives::sponge::Absorb;
 
+/// relaxed R1CS instance (c, x) together with the folded witness w and error err, so
+/// that A(x, w) ∘ B(x, w) = c C(x, w) + err. Only w and err are committed to, the folded
+/// public input x is checked in the clear.
 #[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct AccumulatorInstance<F: PrimeField> {
+    pub(crate) circuit_digest: CircuitDigest,
+    pub(crate) x: Vec<F>,
     pub(crate) w: Vec<F>,
     pub(crate) err: Vec<F>,
     pub(crate) c: F,
 }
 
 impl<F: PrimeField> AccumulatorInstance<F> {
-    pub(crate) fn zero(ipk: IndexProverKey<F>) -> Self {
+    /// the folded public input
+    pub fn public_input(&self) -> &[F] {
+        &self.x
+    }
+
+    pub fn zero(ipk: IndexProverKey<F>) -> Self {
+        Self::zero_for(ipk.digest, &ipk.index_info)
+    }
+
+    /// the zero accumulator of any relation with the shape `index_info`
+    pub(crate) fn zero_for(circuit_digest: CircuitDigest, index_info: &IndexInfo) -> Self {
         AccumulatorInstance{
-            w: vec![F::zero(); ipk.index_info.num_variables],
-            err: vec![F::zero(); ipk.index_info.num_variables],
+            circuit_digest,
+            x: vec![F::zero(); index_info.num_instance_variables],
+            w: vec![F::zero(); index_info.num_witness_variables()],
+            err: vec![F::zero(); index_info.num_constraints],
             c: F::zero()
         }
     }
@@ -29,31 +51,15 @@ pub struct AccumulatorWitness<F: PrimeField + Absorb> {
 }
 
 impl<F: PrimeField + Absorb> AccumulatorWitness<F> {
-    pub(crate) fn zero(ipk: IndexProverKey<F>) -> Self {
-        let hash_params = poseidon_parameters::<F>();
-
-        let mut zero_mod = vec![[F::zero()]; 512];
-        while !zero_mod.len().is_power_of_two() {
-            zero_mod.push([F::zero()]);
-        }
-
-        let w_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(
-                &hash_params, 
-                &hash_params, 
-                zero_mod.clone()
-            ).unwrap();
-
-        let err_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(
-                &hash_params, 
-                &hash_params, 
-                zero_mod.clone()
-            ).unwrap();
+    pub fn zero(ipk: IndexProverKey<F>) -> Self {
+        Self::zero_for(&ipk.code_params, &ipk.hash_params)
+    }
 
+    pub(crate) fn zero_for(code_params: &CodeParameters, hash_params: &HashParameters<F>) -> Self {
+        let zero_root = CommittedVector::zero_root(code_params, hash_params);
         AccumulatorWitness{
-            blinded_w: w_tree.root(),
-            blinded_err: err_tree.root(),
+            blinded_w: zero_root,
+            blinded_err: zero_root,
         }
     }
 }
@@ -66,6 +72,206 @@ pub struct Proof<F: PrimeField + Absorb> {
     pub(crate) err_openings: Vec<Path<MerkleHashConfig<F>>>,
     pub(crate) new_err_openings: Vec<Path<MerkleHashConfig<F>>>,
     pub(crate) t_openings: Vec<Path<MerkleHashConfig<F>>>,
+    /// values of the input witness codeword at the queried indices, opened by
+    /// `input_openings`
+    pub(crate) input_values: Vec<F>,
+    /// values of the old w, old err and t codewords at the queried indices. The native
+    /// verifier recomputes them, a verifier holding only the commitments (like the IVC
+    /// circuit) takes them from here.
+    pub(crate) acc_values: Vec<F>,
+    pub(crate) err_values: Vec<F>,
+    pub(crate) t_values: Vec<F>,
+    pub(crate) blinded_t: F,
+    pub(crate) t: Vec<F>,
+}
+
+impl<F: PrimeField + Absorb> Proof<F> {
+    /// a proof of the shape `code_params` asks for with every opening and value zero. It
+    /// is never accepted; recursive circuits are run on it where there is no fold to
+    /// verify yet.
+    pub(crate) fn dummy(code_params: &CodeParameters) -> Self {
+        let depth = code_params.code_length.next_power_of_two().trailing_zeros() as usize;
+        let openings = vec![
+            Path {
+                leaf_sibling_hash: F::zero(),
+                auth_path: vec![F::zero(); depth.saturating_sub(1)],
+                leaf_index: 0,
+            };
+            code_params.num_queries
+        ];
+        let values = vec![F::zero(); code_params.num_queries];
+        Proof {
+            acc_openings: openings.clone(),
+            new_acc_openings: openings.clone(),
+            input_openings: openings.clone(),
+            err_openings: openings.clone(),
+            new_err_openings: openings.clone(),
+            t_openings: openings,
+            input_values: values.clone(),
+            acc_values: values.clone(),
+            err_values: values.clone(),
+            t_values: values,
+            blinded_t: F::zero(),
+            t: vec![],
+        }
+    }
+}
+
+/// cross term of a fold with a power of r above one, sent along with `Proof` for
+/// relations of degree above two (see `ccs`): the term in the clear, the Merkle root of
+/// its encoding and the openings at the queried positions
+#[derive(Clone)]
+pub struct CrossTerm<F: PrimeField + Absorb> {
+    pub(crate) t: Vec<F>,
+    pub(crate) blinded_t: F,
+    pub(crate) openings: Vec<Path<MerkleHashConfig<F>>>,
+}
+
+/// proof that an accumulator is the fold of two accumulators, see
+/// `BDASAccumulationScheme::merge`. The cross term of two relaxed instances takes the place
+/// of t and the error of the merged accumulator is err + r·t + r²·other_err.
+#[derive(Clone)]
+pub struct MergeProof<F: PrimeField + Absorb> {
+    pub(crate) acc_openings: Vec<Path<MerkleHashConfig<F>>>,
+    pub(crate) other_openings: Vec<Path<MerkleHashConfig<F>>>,
+    pub(crate) new_acc_openings: Vec<Path<MerkleHashConfig<F>>>,
+    pub(crate) err_openings: Vec<Path<MerkleHashConfig<F>>>,
+    pub(crate) other_err_openings: Vec<Path<MerkleHashConfig<F>>>,
+    pub(crate) new_err_openings: Vec<Path<MerkleHashConfig<F>>>,
+    pub(crate) t_openings: Vec<Path<MerkleHashConfig<F>>>,
+    /// values of the w, err and t codewords of both accumulators at the queried indices
+    pub(crate) acc_values: Vec<F>,
+    pub(crate) other_values: Vec<F>,
+    pub(crate) err_values: Vec<F>,
+    pub(crate) other_err_values: Vec<F>,
+    pub(crate) t_values: Vec<F>,
     pub(crate) blinded_t: F,
     pub(crate) t: Vec<F>,
-}
\ No newline at end of file
+}
+
+impl<F: PrimeField + Absorb> MergeProof<F> {
+    /// a merge proof of the shape `code_params` asks for with every opening and value
+    /// zero, see `Proof::dummy`
+    pub(crate) fn dummy(code_params: &CodeParameters) -> Self {
+        let Proof {
+            acc_openings,
+            acc_values,
+            ..
+        } = Proof::dummy(code_params);
+        MergeProof {
+            other_openings: acc_openings.clone(),
+            new_acc_openings: acc_openings.clone(),
+            err_openings: acc_openings.clone(),
+            other_err_openings: acc_openings.clone(),
+            new_err_openings: acc_openings.clone(),
+            t_openings: acc_openings.clone(),
+            acc_openings,
+            other_values: acc_values.clone(),
+            err_values: acc_values.clone(),
+            other_err_values: acc_values.clone(),
+            t_values: acc_values.clone(),
+            acc_values,
+            blinded_t: F::zero(),
+            t: vec![],
+        }
+    }
+}
+
+/// an accumulator as seen by a verifier that holds only the commitments: the folded
+/// public input, the constant c and the Merkle roots of the encoded w and err
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommittedAccumulator<F: PrimeField> {
+    pub(crate) x: Vec<F>,
+    pub(crate) c: F,
+    pub(crate) blinded_w: F,
+    pub(crate) blinded_err: F,
+}
+
+impl<F: PrimeField + Absorb> CommittedAccumulator<F> {
+    pub fn new(instance: &AccumulatorInstance<F>, witness: &AccumulatorWitness<F>) -> Self {
+        CommittedAccumulator {
+            x: instance.x.clone(),
+            c: instance.c,
+            blinded_w: witness.blinded_w,
+            blinded_err: witness.blinded_err,
+        }
+    }
+
+    /// the zero accumulator of a circuit with `num_instance_variables` inputs
+    pub fn zero(
+        num_instance_variables: usize,
+        code_params: &CodeParameters,
+        hash_params: &HashParameters<F>,
+    ) -> Self {
+        let zero_root = CommittedVector::zero_root(code_params, hash_params);
+        CommittedAccumulator {
+            x: vec![F::zero(); num_instance_variables],
+            c: F::zero(),
+            blinded_w: zero_root,
+            blinded_err: zero_root,
+        }
+    }
+
+    /// the accumulator as field elements: x, c, blinded_w, blinded_err
+    pub fn to_field_elements(&self) -> Vec<F> {
+        let mut elements = self.x.clone();
+        elements.extend([self.c, self.blinded_w, self.blinded_err]);
+        elements
+    }
+}
+
+/// Reed-Solomon codeword of a vector together with the Merkle tree over it. The
+/// prover keeps these around so that openings can be generated without re-encoding.
+#[derive(Clone)]
+pub struct CommittedVector<F: PrimeField + Absorb> {
+    pub(crate) code: Vec<F>,
+    pub(crate) tree: MerkleTree<MerkleHashConfig<F>>,
+}
+
+impl<F: PrimeField + Absorb> CommittedVector<F> {
+    /// encodes `values` into a codeword of the length `code_params` asks for and builds
+    /// the Merkle tree over it
+    pub fn commit(
+        values: &[F],
+        code_params: &CodeParameters,
+        hash_params: &HashParameters<F>,
+    ) -> Self {
+        let code = RSCode::encode(values.to_vec(), code_params.code_length as u64).code;
+        Self::from_code(code, hash_params)
+    }
+
+    pub(crate) fn from_code(code: Vec<F>, hash_params: &HashParameters<F>) -> Self {
+        let mut leaves: Vec<[F; 1]> = code.iter().map(|i| [*i]).collect();
+        while !leaves.len().is_power_of_two() {
+            leaves.push([F::zero()]);
+        }
+
+        let tree =
+            MerkleTree::<MerkleHashConfig<F>>::new(hash_params, hash_params, leaves).unwrap();
+
+        CommittedVector { code, tree }
+    }
+
+    /// commitment to `self + r * other`, computed from the codewords
+    pub(crate) fn combine(&self, r: &F, other: &Self, hash_params: &HashParameters<F>) -> Self {
+        Self::from_code(
+            add_vectors(&self.code, &scalar_mult(r, &other.code)),
+            hash_params,
+        )
+    }
+
+    /// Merkle root of the codeword
+    pub fn root(&self) -> F {
+        self.tree.root()
+    }
+
+    /// root of the commitment to a zero vector, whose codeword is zero. Every level of
+    /// its tree repeats a single node, so the root takes one hash per level.
+    pub(crate) fn zero_root(code_params: &CodeParameters, hash_params: &HashParameters<F>) -> F {
+        let mut root = HashCRH::evaluate(hash_params, [F::zero()]).unwrap();
+        for _ in 0..code_params.code_length.next_power_of_two().trailing_zeros() {
+            root = HashTwoToOneCRH::compress(hash_params, root, root).unwrap();
+        }
+        root
+    }
+}
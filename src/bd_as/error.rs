@@ -0,0 +1,96 @@
+use ark_relations::r1cs::SynthesisError;
+use ark_std::fmt;
+
+use crate::bd_as::r1cs_nark::UnsatisfiedReport;
+
+/// reason an accumulator was rejected by `BDASAccumulationScheme::decide_with_report`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecideError {
+    /// the accumulator was produced for a different circuit than the decider key
+    KeyMismatch,
+    /// x does not have the number of instance variables of the index
+    InputLength { expected: usize, found: usize },
+    /// w does not have the number of witness variables of the index
+    WitnessLength { expected: usize, found: usize },
+    /// err does not have one entry per constraint
+    ErrLength { expected: usize, found: usize },
+    /// the Merkle root of the encoded w is not `blinded_w`
+    WitnessCommitment,
+    /// the Merkle root of the encoded err is not `blinded_err`
+    ErrCommitment,
+    /// A(x, w) ∘ B(x, w) != err + c·C(x, w) at the reported rows
+    UnsatisfiedConstraints(UnsatisfiedReport),
+}
+
+impl fmt::Display for DecideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecideError::KeyMismatch => {
+                write!(f, "the accumulator was produced for a different circuit")
+            }
+            DecideError::InputLength { expected, found } => write!(
+                f,
+                "the accumulated input has {} variables but the index expects {}",
+                found, expected
+            ),
+            DecideError::WitnessLength { expected, found } => write!(
+                f,
+                "the accumulated witness has {} variables but the index expects {}",
+                found, expected
+            ),
+            DecideError::ErrLength { expected, found } => write!(
+                f,
+                "the error vector has {} entries but the index has {} constraints",
+                found, expected
+            ),
+            DecideError::WitnessCommitment => {
+                write!(f, "the accumulated witness does not match its Merkle commitment")
+            }
+            DecideError::ErrCommitment => {
+                write!(f, "the error vector does not match its Merkle commitment")
+            }
+            DecideError::UnsatisfiedConstraints(report) => {
+                write!(f, "relaxed R1CS: {}", report)
+            }
+        }
+    }
+}
+
+impl ark_std::error::Error for DecideError {}
+
+/// reason `AccumulatorProver` did not fold an input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProverError {
+    /// the input does not satisfy the circuit at the reported rows, see
+    /// `AccumulatorProver::check_inputs`
+    Unsatisfied(UnsatisfiedReport),
+    /// the fold itself failed, see `BDASAccumulationScheme::prove`
+    Synthesis(SynthesisError),
+}
+
+impl From<SynthesisError> for ProverError {
+    fn from(error: SynthesisError) -> Self {
+        ProverError::Synthesis(error)
+    }
+}
+
+/// for the provers of recursive circuits, which report the errors of their circuits
+impl From<ProverError> for SynthesisError {
+    fn from(error: ProverError) -> Self {
+        match error {
+            ProverError::Unsatisfied(_) => SynthesisError::Unsatisfiable,
+            ProverError::Synthesis(error) => error,
+        }
+    }
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProverError::Unsatisfied(report) => write!(f, "the input is unsatisfied: {}", report),
+            ProverError::Synthesis(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl ark_std::error::Error for ProverError {}
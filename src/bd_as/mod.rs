@@ -1,7 +1,12 @@
+pub mod air;
+pub mod ccs;
+pub mod constraints;
+pub mod plonkish;
 pub mod r1cs_nark;
 
 use crate::AccumulationScheme;
-use ark_crypto_primitives::merkle_tree::MerkleTree;
+use ark_crypto_primitives::crh::CRHScheme;
+use ark_crypto_primitives::merkle_tree::Path;
 use ark_crypto_primitives::prf::blake2s::Blake2s;
 use ark_crypto_primitives::prf::PRF;
 use ark_crypto_primitives::sponge::Absorb;
@@ -10,8 +15,9 @@ use ark_relations::r1cs::SynthesisError;
 use ark_std::cfg_into_iter;
 use ark_std::marker::PhantomData;
 use r1cs_nark::{
-    matrix_vec_mul, poseidon_parameters, CommitmentFullAssignment, FullAssignment, IndexProverKey,
-    IndexVerifierKey, MerkleHashConfig
+    matrix_vec_mul, CircuitDigest, CodeParameters, HashCRH, HashParameters, IndexDeciderKey,
+    IndexProverKey, IndexVerifierKey, Instance, MerkleHashConfig, R1CSNark, UnsatisfiedReport,
+    Witness, MAX_REPORTED_ROWS
 };
 
 
@@ -19,6 +25,18 @@ mod data_structures;
 pub use data_structures::*;
 mod reed_solomon;
 pub use reed_solomon::*;
+mod prover;
+pub use prover::*;
+mod error;
+pub use error::*;
+
+/// length of the Reed-Solomon codewords committed to by the Merkle trees, for circuits
+/// small enough that their vectors fit (see `CodeParameters::fitted_to`)
+pub(crate) const CODE_LENGTH: usize = 512;
+/// smallest ratio of the code length to the length of the committed vectors
+pub(crate) const INVERSE_RATE: usize = 2;
+/// number of codeword positions the accumulation verifier spot-checks
+pub(crate) const NUM_QUERIES: usize = 16;
 
 #[derive(Clone)]
 pub struct BDASAccumulationScheme<F: PrimeField + Absorb> {
@@ -27,7 +45,7 @@ pub struct BDASAccumulationScheme<F: PrimeField + Absorb> {
 
 fn bytes_to_field_vec<F: PrimeField>(bytes: [u8; 32]) -> Vec<F> {
     let bits_per_elem = F::MODULUS_BIT_SIZE as usize;
-    let bytes_per_elem = (bits_per_elem + 7) / 8; // Convert bits to bytes, rounding up
+    let bytes_per_elem = bits_per_elem.div_ceil(8); // Convert bits to bytes, rounding up
     let mut result = vec![];
 
     // Iterate over chunks of the byte array and convert each chunk to a field element
@@ -44,9 +62,8 @@ fn field_vec_to_fixed_bytes<F: PrimeField>(field_elems: Vec<F>) -> [u8; 32] {
     let mut byte_vec = Vec::new();
 
     // Calculate minimum number of elements needed
-    let bits_needed = 256; // 32 bytes * 8 bits/byte
-    let elements_needed =
-        (bits_needed + F::MODULUS_BIT_SIZE as usize - 1) / F::MODULUS_BIT_SIZE as usize;
+    let bits_needed: usize = 256; // 32 bytes * 8 bits/byte
+    let elements_needed = bits_needed.div_ceil(F::MODULUS_BIT_SIZE as usize);
 
     // Take only the minimum required elements
     let field_elems_truncated = field_elems.into_iter().take(elements_needed);
@@ -65,20 +82,73 @@ fn field_vec_to_fixed_bytes<F: PrimeField>(field_elems: Vec<F>) -> [u8; 32] {
     result
 }
 
-fn get_randomness<F: PrimeField>(input_witness: Vec<F>, accumulator_witness: Vec<F>) -> Vec<F> {
-    let seed: [u8; 32] = field_vec_to_fixed_bytes(input_witness);
-    let inp: [u8; 32] = field_vec_to_fixed_bytes(accumulator_witness);
-    bytes_to_field_vec(Blake2s::evaluate(&seed, &inp).unwrap())
+/// the statement a folding challenge is bound to, hashed into the element that leads its
+/// transcript: the digest of the key followed by `public`, the public inputs and
+/// committed accumulators being folded. Without it the challenge would only depend on
+/// the roots, and x, c and err could be picked after it.
+fn statement<F: PrimeField + Absorb>(
+    hash_params: &HashParameters<F>,
+    digest: CircuitDigest,
+    public: impl IntoIterator<Item = F>,
+) -> Result<F, SynthesisError> {
+    let mut elements = digest.to_field_elements().to_vec();
+    elements.extend(public);
+    HashCRH::evaluate(hash_params, elements).map_err(|_| SynthesisError::Unsatisfiable)
 }
 
+/// Fiat-Shamir transcript over the Merkle roots sent before a challenge. The first two
+/// roots key and seed Blake2s, every further root is absorbed by rehashing the seed
+/// keyed with it, so the challenge depends on all of them.
+fn transcript<F: PrimeField>(roots: &[F]) -> ([u8; 32], [u8; 32]) {
+    let seed: [u8; 32] = field_vec_to_fixed_bytes(vec![roots[0]]);
+    let mut inp: [u8; 32] = field_vec_to_fixed_bytes(vec![roots[1]]);
+    for root in &roots[2..] {
+        inp = Blake2s::evaluate(&field_vec_to_fixed_bytes(vec![*root]), &inp).unwrap();
+    }
+    (seed, inp)
+}
+
+/// folding challenge over `roots`, with Blake2s next to Poseidon commitments and with
+/// Poseidon2 itself next to Poseidon2 commitments
+fn get_randomness<F: PrimeField>(hash_params: &HashParameters<F>, roots: &[F]) -> Vec<F> {
+    match hash_params {
+        HashParameters::Poseidon(_) => {
+            let (seed, inp) = transcript(roots);
+            bytes_to_field_vec(Blake2s::evaluate(&seed, &inp).unwrap())
+        }
+        HashParameters::Poseidon2(parameters) => vec![parameters.hash(roots)],
+    }
+}
+
+/// queried positions over `roots`. With Poseidon2 the folding challenge is rehashed once
+/// per query and the position is its lowest 64 bits reduced modulo `max_index`.
 fn get_random_indices<F: PrimeField>(
+    hash_params: &HashParameters<F>,
+    number_indices: usize, 
+    roots: &[F],
+    max_index: usize,
+) -> Vec<usize> {
+    let parameters = match hash_params {
+        HashParameters::Poseidon(_) => {
+            return blake2s_random_indices(number_indices, roots, max_index)
+        }
+        HashParameters::Poseidon2(parameters) => parameters,
+    };
+    let mut r = parameters.hash(roots);
+    (0..number_indices)
+        .map(|_| {
+            r = parameters.hash(&[r]);
+            r.into_bigint().as_ref()[0] as usize % max_index
+        })
+        .collect()
+}
+
+fn blake2s_random_indices<F: PrimeField>(
     number_indices: usize, 
-    input_witness: Vec<F>, 
-    accumulator_witness: Vec<F>,
+    roots: &[F],
     max_index: usize,
 ) -> Vec<usize> {
-    let seed: [u8; 32] = field_vec_to_fixed_bytes(input_witness);
-    let mut inp: [u8; 32] = field_vec_to_fixed_bytes(accumulator_witness);
+    let (seed, mut inp) = transcript(roots);
     let mut r:Vec<F> = bytes_to_field_vec(Blake2s::evaluate(&seed, &inp).unwrap());
 
     let mut indices: Vec<usize> = vec![];
@@ -100,11 +170,11 @@ impl<F: PrimeField + Absorb> AccumulationScheme<F> for BDASAccumulationScheme<F>
     type AccumulatorInstance = AccumulatorInstance<F>;
     type AccumulatorWitness = AccumulatorWitness<F>;
     type Proof = Proof<F>;
-    type InputInstance = FullAssignment<F>;
-    type InputWitness = CommitmentFullAssignment<F>;
+    type InputInstance = Instance<F>;
+    type InputWitness = Witness<F>;
     type ProverKey = IndexProverKey<F>;
     type VerifierKey = IndexVerifierKey<F>;
-    type DeciderKey = IndexVerifierKey<F>;
+    type DeciderKey = IndexDeciderKey<F>;
 
     fn prove<'a>(
         prover_key: &'a Self::ProverKey,
@@ -117,155 +187,478 @@ impl<F: PrimeField + Absorb> AccumulationScheme<F> for BDASAccumulationScheme<F>
         ),
         SynthesisError,
     > {
-        // Using Fiat-Shamir to compute randomness of the linear combination
-        let r: F = get_randomness(
-            vec![input.1.blinded_assignment],
-            vec![old_accumulator.1.blinded_w],
-        )[0];
+        let (w_commitment, err_commitment) = commit_accumulator(
+            &prover_key.code_params,
+            &prover_key.hash_params,
+            old_accumulator,
+        )?;
+
+        let (new_accumulator, proof, _, _) = Self::fold(
+            prover_key,
+            old_accumulator,
+            input,
+            &w_commitment,
+            &err_commitment,
+            None,
+        )?;
+
+        Ok((new_accumulator, proof))
+    }
 
-        let (input_instance, input_witness) = input;
-        let mut input_assignment = input_instance.input.clone();
-        input_assignment.extend(input_instance.witness.clone());
+    fn verify<'a>(
+        verifier_key: &'a Self::VerifierKey,
+        proof: &Self::Proof,
+        old_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
+        new_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
+        input_instance: &'a Self::InputInstance,
+    ) -> Result<bool, SynthesisError> {
+        Self::verify_with_cross_terms(
+            verifier_key,
+            &[],
+            (proof, &[]),
+            old_accumulator,
+            new_accumulator,
+            input_instance,
+        )
+    }
 
-        let num_input_variables = input_instance.input.len();
-        let num_witness_variables = input_instance.witness.len();
-        let num_variables = num_witness_variables + num_input_variables;
+    fn decide<'a>(
+        decider_key: &'a Self::DeciderKey,
+        accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
+    ) -> Result<bool, SynthesisError> {
+        Ok(Self::decide_with_report(decider_key, accumulator).is_ok())
+    }
+}
 
-        assert_eq!(prover_key.index_info.num_variables, num_variables);
+impl<F: PrimeField + Absorb> BDASAccumulationScheme<F> {
+    /// `verify` for folds of relations of degree above two, with the cross terms of r²,
+    /// r³, … in `higher`. The error folding identity is spot-checked with all of them,
+    /// and `domain` is absorbed like in `fold_cross_terms`.
+    pub(crate) fn verify_with_cross_terms(
+        verifier_key: &IndexVerifierKey<F>,
+        domain: &[F],
+        proofs: (&Proof<F>, &[CrossTerm<F>]),
+        old_accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        new_accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        input_instance: &Instance<F>,
+    ) -> Result<bool, SynthesisError> {
+        let (proof, higher) = proofs;
+        let input_openings = &proof.input_openings;
+        let acc_openings = &proof.acc_openings;
+        let new_acc_openings = &proof.new_acc_openings;
+        let t_openings = &proof.t_openings;
+        let err_openings = &proof.err_openings;
+        let new_err_openings = &proof.new_err_openings;
 
-        let (acc_instance, acc_witness) = old_accumulator;
-        let num_acc_variables = acc_instance.w.len();
+        let code_params = &verifier_key.code_params;
+        let num_queries = code_params.num_queries;
+
+        if [
+            input_openings.len(),
+            acc_openings.len(),
+            new_acc_openings.len(),
+            t_openings.len(),
+            err_openings.len(),
+            new_err_openings.len(),
+            proof.input_values.len(),
+            proof.acc_values.len(),
+            proof.err_values.len(),
+            proof.t_values.len(),
+        ]
+        .iter()
+        .any(|len| *len != num_queries)
+        {
+            return Ok(false);
+        }
 
-        assert_eq!(prover_key.index_info.num_variables, num_acc_variables);
-        assert_eq!(prover_key.index_info.num_variables, acc_instance.err.len());
+        if proof.t.len() != verifier_key.index_info.num_constraints
+            || higher.iter().any(|term| {
+                term.t.len() != verifier_key.index_info.num_constraints
+                    || term.openings.len() != num_queries
+            })
+        {
+            return Ok(false);
+        }
 
-        let mut az = matrix_vec_mul(&prover_key.a, &input_instance.input, &input_instance.witness);
-        let mut bz = matrix_vec_mul(&prover_key.b, &input_instance.input, &input_instance.witness);
-        let mut cz = matrix_vec_mul(&prover_key.c, &input_instance.input, &input_instance.witness);
+        let hash_params = &verifier_key.hash_params;
+        let code_length = code_params.code_length;
 
-        let mut aw = matrix_vec_mul(&prover_key.a, &acc_instance.w, &vec![]);
-        let mut bw = matrix_vec_mul(&prover_key.b, &acc_instance.w, &vec![]);
-        let mut cw = matrix_vec_mul(&prover_key.c, &acc_instance.w, &vec![]);
+        let (acc_instance, acc_witness) = old_accumulator;
+        let (new_acc_instance, new_acc_witness) = new_accumulator;
 
-        while az.len() < num_acc_variables  {
-            az.push(F::zero());
-            bz.push(F::zero());
-            cz.push(F::zero());
-            aw.push(F::zero());
-            bw.push(F::zero());
-            cw.push(F::zero());
+        if !verifier_key.is_compatible(&acc_instance.circuit_digest)
+            || !verifier_key.is_compatible(&new_acc_instance.circuit_digest)
+            || !verifier_key.is_compatible(&input_instance.circuit_digest)
+        {
+            return Ok(false);
         }
+        let t = &proof.t;
 
-        let azbw = had_product(&az, &bw);
-        let awbz = had_product(&aw, &bz);
+        let new_w_code = RSCode::encode(new_acc_instance.w.clone(), code_length as u64).code;
+        let new_err_code = RSCode::encode(new_acc_instance.err.clone(), code_length as u64).code;
+        let err_code = RSCode::encode(acc_instance.err.clone(), code_length as u64).code;
+        let w_code = RSCode::encode(acc_instance.w.clone(), code_length as u64).code;
+        let t_code = RSCode::encode(t.clone(), code_length as u64).code;
+        let higher_codes: Vec<Vec<F>> = higher
+            .iter()
+            .map(|term| RSCode::encode(term.t.clone(), code_length as u64).code)
+            .collect();
+
+        // the challenges are derived after the cross terms are committed to
+        let statement = statement(
+            hash_params,
+            verifier_key.digest,
+            input_instance
+                .input
+                .iter()
+                .copied()
+                .chain(CommittedAccumulator::new(acc_instance, acc_witness).to_field_elements()),
+        )?;
+        let roots: Vec<F> = [statement]
+            .into_iter()
+            .chain(domain.iter().copied())
+            .chain([input_instance.blinded_witness, acc_witness.blinded_w, proof.blinded_t])
+            .chain(higher.iter().map(|term| term.blinded_t))
+            .collect();
+        let opening_indexes = get_random_indices(hash_params, num_queries, &roots, code_length);
+
+        // the openings are checked at the queried positions, whatever position the
+        // paths claim, and have to be paths of a tree over the whole codeword
+        let depth = code_length.next_power_of_two().trailing_zeros() as usize;
+        for (query, index) in opening_indexes.iter().enumerate() {
+            if proof.acc_values[query] != w_code[*index]
+                || proof.err_values[query] != err_code[*index]
+                || proof.t_values[query] != t_code[*index]
+            {
+                return Ok(false);
+            }
 
-        let t = sub_vectors(
-            &add_vectors(&azbw, &awbz),
-            &add_vectors(&cw, &scalar_mult(&acc_instance.c, &cz)),
-        );
+            let openings = [
+                (
+                    &input_openings[query],
+                    &input_instance.blinded_witness,
+                    proof.input_values[query],
+                ),
+                (&acc_openings[query], &acc_witness.blinded_w, w_code[*index]),
+                (&new_acc_openings[query], &new_acc_witness.blinded_w, new_w_code[*index]),
+                (&t_openings[query], &proof.blinded_t, t_code[*index]),
+                (&err_openings[query], &acc_witness.blinded_err, err_code[*index]),
+                (&new_err_openings[query], &new_acc_witness.blinded_err, new_err_code[*index]),
+            ];
+            for (opening, root, value) in openings {
+                if !opens_at(hash_params, opening, root, value, *index, depth) {
+                    return Ok(false);
+                }
+            }
+            for (term, code) in higher.iter().zip(&higher_codes) {
+                let opening = &term.openings[query];
+                if !opens_at(hash_params, opening, &term.blinded_t, code[*index], *index, depth) {
+                    return Ok(false);
+                }
+            }
+        }
 
-        let t_code = RSCode::encode(t.clone(), 512).code;
+        let r: F = get_randomness(hash_params, &roots)[0];
 
-        let mut t_modified: Vec<[F; 1]> = vec![];
-        for &i in t_code.iter() {
-            t_modified.push([i.clone()]);
+        if acc_instance.c + r != new_acc_instance.c {
+            return Ok(false);
         }
-        while !t_modified.len().is_power_of_two() {
-            t_modified.push([F::zero()]);
+
+        // the public input is folded in the clear
+        if input_instance.input.len() != verifier_key.index_info.num_instance_variables
+            || acc_instance.x.len() != input_instance.input.len()
+            || new_acc_instance.x != add_vectors(&acc_instance.x, &scalar_mult(&r, &input_instance.input))
+        {
+            return Ok(false);
         }
 
-        let w_code = RSCode::encode(acc_instance.w.clone(), 512).code;
+        for (i, z_value) in opening_indexes.into_iter().zip(&proof.input_values) {
+            if new_w_code[i] != w_code[i] + r * *z_value {
+                return Ok(false);
+            }
 
-        let mut w_modified: Vec<[F; 1]> = vec![];
-        for &i in w_code.iter() {
-            w_modified.push([i.clone()]);
-        }
-        while !w_modified.len().is_power_of_two() {
-            w_modified.push([F::zero()]);
+            // err + r·t + r²·t_2 + … at the queried position
+            let mut folded_err = err_code[i] + r * t_code[i];
+            let mut r_power = r;
+            for code in &higher_codes {
+                r_power *= r;
+                folded_err += r_power * code[i];
+            }
+            if new_err_code[i] != folded_err {
+                return Ok(false);
+            }
         }
 
-        let z_code = RSCode::encode(input_assignment.clone(), 512).code;
+        Ok(true)
+    }
+
+
+    /// same checks as `decide`, but reports the first one that fails: the shape of the
+    /// accumulator, the commitment of w, the commitment of err and finally the rows of
+    /// A(x, w) ∘ B(x, w) = err + c·C(x, w) that do not hold
+    pub fn decide_with_report(
+        decider_key: &IndexDeciderKey<F>,
+        accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+    ) -> Result<(), DecideError> {
+        let (instance, witness) = accumulator;
+        let index_info = &decider_key.index_info;
+
+        if !decider_key.is_compatible(&instance.circuit_digest) {
+            return Err(DecideError::KeyMismatch);
+        }
+        if instance.x.len() != index_info.num_instance_variables {
+            return Err(DecideError::InputLength {
+                expected: index_info.num_instance_variables,
+                found: instance.x.len(),
+            });
+        }
+        if instance.w.len() != index_info.num_witness_variables() {
+            return Err(DecideError::WitnessLength {
+                expected: index_info.num_witness_variables(),
+                found: instance.w.len(),
+            });
+        }
+        if instance.err.len() != index_info.num_constraints {
+            return Err(DecideError::ErrLength {
+                expected: index_info.num_constraints,
+                found: instance.err.len(),
+            });
+        }
 
-        let mut z_modified: Vec<[F; 1]> = vec![];
-        for &i in z_code.iter() {
-            z_modified.push([i.clone()]);
+        let (code_params, hash_params) = (&decider_key.code_params, &decider_key.hash_params);
+        let w_commitment = CommittedVector::commit(&instance.w, code_params, hash_params);
+        if w_commitment.root() != witness.blinded_w {
+            return Err(DecideError::WitnessCommitment);
         }
-        while !z_modified.len().is_power_of_two() {
-            z_modified.push([F::zero()]);
+        let err_commitment = CommittedVector::commit(&instance.err, code_params, hash_params);
+        if err_commitment.root() != witness.blinded_err {
+            return Err(DecideError::ErrCommitment);
         }
 
-        let err_code = RSCode::encode(acc_instance.err.clone(), 512).code;
+        let aw = matrix_vec_mul(&decider_key.a, &instance.x, &instance.w);
+        let bw = matrix_vec_mul(&decider_key.b, &instance.x, &instance.w);
+        let cw = matrix_vec_mul(&decider_key.c, &instance.x, &instance.w);
+
+        let lhs = had_product(&aw, &bw);
+        let rhs = add_vectors(&instance.err, &scalar_mult(&instance.c, &cw));
 
-        let mut err_modified: Vec<[F; 1]> = vec![];
-        for &i in err_code.iter() {
-            err_modified.push([i.clone()]);
+        let failing_rows: Vec<usize> = (0..lhs.len())
+            .filter(|row| lhs[*row] != rhs[*row])
+            .collect();
+        if failing_rows.is_empty() {
+            return Ok(());
         }
-        while !err_modified.len().is_power_of_two() {
-            err_modified.push([F::zero()]);
+        Err(DecideError::UnsatisfiedConstraints(UnsatisfiedReport {
+            num_unsatisfied: failing_rows.len(),
+            rows: failing_rows.into_iter().take(MAX_REPORTED_ROWS).collect(),
+            namespace: vec![],
+        }))
+    }
+
+    /// checks that `input` satisfies the circuit of `prover_key` before it is folded.
+    /// Folding an unsatisfying input succeeds and only fails at `decide`, this reports
+    /// the failing rows right away as `ProverError::Unsatisfied`. An input that does not
+    /// follow the instance/witness split of the key fails like `fold` does, with
+    /// `SynthesisError::Unsatisfiable`, before any constraint is evaluated.
+    pub fn check_input(
+        prover_key: &IndexProverKey<F>,
+        input: (&Instance<F>, &Witness<F>),
+    ) -> Result<(), ProverError> {
+        let (input_instance, input_witness) = input;
+        if !prover_key
+            .index_info
+            .matches_split(input_instance.input.len(), input_witness.witness.len())
+        {
+            return Err(ProverError::Synthesis(SynthesisError::Unsatisfiable));
         }
+        let report =
+            R1CSNark::check_assignment(prover_key, &input_instance.input, &input_witness.witness);
+        match report {
+            Some(report) => Err(ProverError::Unsatisfied(report)),
+            None => Ok(()),
+        }
+    }
 
-        let hash_params = poseidon_parameters::<F>();
+    /// folds `input` into `old_accumulator` given the prover's commitments to the old
+    /// accumulator, and returns the commitments to the new one along with it. The input
+    /// commitment is rebuilt from the witness unless `input_commitment` is given. Fails
+    /// with `SynthesisError::MalformedVerifyingKey` if the accumulator or the input
+    /// belong to another circuit than `prover_key`, and with `SynthesisError::Unsatisfiable`
+    /// if they do not follow its instance/witness split.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn fold(
+        prover_key: &IndexProverKey<F>,
+        old_accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        input: (&Instance<F>, &Witness<F>),
+        w_commitment: &CommittedVector<F>,
+        err_commitment: &CommittedVector<F>,
+        input_commitment: Option<&CommittedVector<F>>,
+    ) -> Result<
+        (
+            (AccumulatorInstance<F>, AccumulatorWitness<F>),
+            Proof<F>,
+            CommittedVector<F>,
+            CommittedVector<F>,
+        ),
+        SynthesisError,
+    > {
+        if !prover_key.is_compatible(&old_accumulator.0.circuit_digest)
+            || !prover_key.is_compatible(&input.0.circuit_digest)
+        {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
 
-        let t_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, t_modified).unwrap();
+        let (input_instance, input_witness) = input;
 
-        let w_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, w_modified).unwrap();
+        let (acc_instance, _) = old_accumulator;
 
-        let z_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, z_modified.clone()).unwrap();
+        // the input and the accumulator have to follow the instance/witness split of the index
+        let index_info = &prover_key.index_info;
+        if !index_info.matches_split(input_instance.input.len(), input_witness.witness.len())
+            || !index_info.matches_split(acc_instance.x.len(), acc_instance.w.len())
+            || acc_instance.err.len() != index_info.num_constraints
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
 
-        let err_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, err_modified)
-                .unwrap();
+        let az = matrix_vec_mul(&prover_key.a, &input_instance.input, &input_witness.witness);
+        let bz = matrix_vec_mul(&prover_key.b, &input_instance.input, &input_witness.witness);
+        let cz = matrix_vec_mul(&prover_key.c, &input_instance.input, &input_witness.witness);
 
-        assert_eq!(err_tree.root(), acc_witness.blinded_err);
-        assert_eq!(w_tree.root(), acc_witness.blinded_w);
-        assert_eq!(z_tree.root(), input_witness.blinded_assignment);
+        let aw = matrix_vec_mul(&prover_key.a, &acc_instance.x, &acc_instance.w);
+        let bw = matrix_vec_mul(&prover_key.b, &acc_instance.x, &acc_instance.w);
+        let cw = matrix_vec_mul(&prover_key.c, &acc_instance.x, &acc_instance.w);
 
-        let blinded_t = t_tree.root();
+        let azbw = had_product(&az, &bw);
+        let awbz = had_product(&aw, &bz);
 
-        let new_w = add_vectors(&acc_instance.w, &scalar_mult(&r, &input_assignment));
+        let t = sub_vectors(
+            &add_vectors(&azbw, &awbz),
+            &add_vectors(&cw, &scalar_mult(&acc_instance.c, &cz)),
+        );
 
-        let new_err = add_vectors(&acc_instance.err, &scalar_mult(&r, &t));
+        let (new_accumulator, proof, _, new_w_commitment, new_err_commitment) =
+            Self::fold_cross_terms(
+                (&prover_key.hash_params, &prover_key.code_params, prover_key.digest),
+                &[],
+                old_accumulator,
+                input,
+                (t, vec![]),
+                (w_commitment, err_commitment),
+                input_commitment,
+            )?;
+        Ok((new_accumulator, proof, new_w_commitment, new_err_commitment))
+    }
 
-        let new_acc_instance = AccumulatorInstance {
-            w: new_w.clone(),
-            err: new_err.clone(),
-            c: acc_instance.c + r,
-        };
+    /// the part of `fold` that does not depend on the relation: given the cross terms
+    /// of the accumulator and the input, folds w, x, err and c with the challenge and
+    /// opens the codewords at the queried positions. `t` is the coefficient of r and
+    /// `higher` those of r², r³, … for relations of degree above two, which are
+    /// committed to and opened the same way as `t`. Relations other than R1CS (see
+    /// `ccs`) only differ in how the cross terms are computed. Fails with
+    /// `SynthesisError::Unsatisfiable` if the input witness is not the one committed to
+    /// by the input instance. `domain` is absorbed into the transcript between the
+    /// statement and the roots, see `ccs::UniversalIndex`.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn fold_cross_terms(
+        key: (&HashParameters<F>, &CodeParameters, CircuitDigest),
+        domain: &[F],
+        old_accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        input: (&Instance<F>, &Witness<F>),
+        cross_terms: (Vec<F>, Vec<Vec<F>>),
+        commitments: (&CommittedVector<F>, &CommittedVector<F>),
+        input_commitment: Option<&CommittedVector<F>>,
+    ) -> Result<
+        (
+            (AccumulatorInstance<F>, AccumulatorWitness<F>),
+            Proof<F>,
+            Vec<CrossTerm<F>>,
+            CommittedVector<F>,
+            CommittedVector<F>,
+        ),
+        SynthesisError,
+    > {
+        let (hash_params, code_params, digest) = key;
+        let (t, higher) = cross_terms;
+        let (w_commitment, err_commitment) = commitments;
+        let (input_instance, input_witness) = input;
+        let (acc_instance, acc_witness) = old_accumulator;
 
-        let new_err_code = RSCode::encode(new_err.clone(), 512).code;
+        // the cross terms are committed to before the challenge, which is derived from
+        // their roots along with the roots of the input and the accumulated witness and
+        // the statement of the fold
+        let t_commitment = CommittedVector::commit(&t, code_params, hash_params);
+        let higher_commitments: Vec<CommittedVector<F>> = higher
+            .iter()
+            .map(|term| CommittedVector::commit(term, code_params, hash_params))
+            .collect();
+        let statement = statement(
+            hash_params,
+            digest,
+            input_instance
+                .input
+                .iter()
+                .copied()
+                .chain(CommittedAccumulator::new(acc_instance, acc_witness).to_field_elements()),
+        )?;
+        let roots: Vec<F> = [statement]
+            .into_iter()
+            .chain(domain.iter().copied())
+            .chain([input_instance.blinded_witness, acc_witness.blinded_w, t_commitment.root()])
+            .chain(higher_commitments.iter().map(|commitment| commitment.root()))
+            .collect();
 
-        let mut new_err_modified: Vec<[F; 1]> = vec![];
-        for &i in new_err_code.iter() {
-            new_err_modified.push([i]);
-        }
-        while !new_err_modified.len().is_power_of_two() {
-            new_err_modified.push([F::zero()]);
-        }
+        // Using Fiat-Shamir to compute randomness of the linear combination
+        let r: F = get_randomness(hash_params, &roots)[0];
 
-        let new_w_code = RSCode::encode(new_w.clone(), 512).code;
+        let z_commitment = match input_commitment {
+            Some(z_commitment) => z_commitment.clone(),
+            None => CommittedVector::commit(&input_witness.witness, code_params, hash_params),
+        };
 
-        let mut new_w_modified: Vec<[F; 1]> = vec![];
-        for &i in new_w_code.iter() {
-            new_w_modified.push([i.clone()]);
+        if z_commitment.root() != input_instance.blinded_witness {
+            return Err(SynthesisError::Unsatisfiable);
         }
-        while !new_w_modified.len().is_power_of_two() {
-            new_w_modified.push([F::zero()]);
+
+        let blinded_t = t_commitment.root();
+
+        let new_x = add_vectors(&acc_instance.x, &scalar_mult(&r, &input_instance.input));
+        let new_w = add_vectors(&acc_instance.w, &scalar_mult(&r, &input_witness.witness));
+
+        // err + r·t + r²·t_2 + …, on the vectors and on their codewords
+        let mut new_err = add_vectors(&acc_instance.err, &scalar_mult(&r, &t));
+        let mut r_power = r;
+        for term in &higher {
+            r_power *= r;
+            new_err = add_vectors(&new_err, &scalar_mult(&r_power, term));
         }
 
-        let new_w_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, new_w_modified)
-                .unwrap();
+        let new_acc_instance = AccumulatorInstance {
+            circuit_digest: digest,
+            x: new_x,
+            w: new_w,
+            err: new_err,
+            c: acc_instance.c + r,
+        };
 
-        let new_err_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, new_err_modified)
-                .unwrap();
+        // the encoding is linear, so the new codewords are the same combination of the
+        // old ones and do not need to be re-encoded
+        let new_w_commitment = w_commitment.combine(&r, &z_commitment, hash_params);
+        let new_err_commitment = if higher_commitments.is_empty() {
+            err_commitment.combine(&r, &t_commitment, hash_params)
+        } else {
+            let mut code = add_vectors(&err_commitment.code, &scalar_mult(&r, &t_commitment.code));
+            let mut r_power = r;
+            for commitment in &higher_commitments {
+                r_power *= r;
+                code = add_vectors(&code, &scalar_mult(&r_power, &commitment.code));
+            }
+            CommittedVector::from_code(code, hash_params)
+        };
 
         let new_acc_witness = AccumulatorWitness {
-            blinded_err: new_err_tree.root(),
-            blinded_w: new_w_tree.root(),
+            blinded_err: new_err_commitment.root(),
+            blinded_w: new_w_commitment.root(),
         };
 
         let mut acc_openings = vec![];
@@ -274,267 +667,394 @@ impl<F: PrimeField + Absorb> AccumulationScheme<F> for BDASAccumulationScheme<F>
         let mut err_openings = vec![];
         let mut new_err_openings = vec![];
         let mut t_openings = vec![];
-
-        let _indices = get_random_indices(
-            16, 
-            vec![input.1.blinded_assignment],
-            vec![old_accumulator.1.blinded_w],
-            512
+        let mut input_values = vec![];
+        let mut acc_values = vec![];
+        let mut err_values = vec![];
+        let mut t_values = vec![];
+
+        let indices = get_random_indices(
+            hash_params,
+            code_params.num_queries,
+            &roots,
+            code_params.code_length,
         );
 
-        for i in _indices {
-            acc_openings.push(w_tree.generate_proof(i).unwrap());
-            new_acc_openings.push(new_w_tree.generate_proof(i).unwrap());
-            input_openings.push(z_tree.generate_proof(i).unwrap());
-            err_openings.push(err_tree.generate_proof(i).unwrap());
-            new_err_openings.push(new_err_tree.generate_proof(i).unwrap());
-            t_openings.push(t_tree.generate_proof(i).unwrap());
+        let higher_terms = higher
+            .into_iter()
+            .zip(&higher_commitments)
+            .map(|(term, commitment)| CrossTerm {
+                t: term,
+                blinded_t: commitment.root(),
+                openings: indices
+                    .iter()
+                    .map(|i| commitment.tree.generate_proof(*i).unwrap())
+                    .collect(),
+            })
+            .collect();
+
+        for i in indices {
+            acc_openings.push(w_commitment.tree.generate_proof(i).unwrap());
+            new_acc_openings.push(new_w_commitment.tree.generate_proof(i).unwrap());
+            input_openings.push(z_commitment.tree.generate_proof(i).unwrap());
+            err_openings.push(err_commitment.tree.generate_proof(i).unwrap());
+            new_err_openings.push(new_err_commitment.tree.generate_proof(i).unwrap());
+            t_openings.push(t_commitment.tree.generate_proof(i).unwrap());
+            input_values.push(z_commitment.code[i]);
+            acc_values.push(w_commitment.code[i]);
+            err_values.push(err_commitment.code[i]);
+            t_values.push(t_commitment.code[i]);
         }
 
         let proof = Proof {
-            acc_openings: acc_openings,
-            new_acc_openings: new_acc_openings,
-            input_openings: input_openings,
-            err_openings: err_openings,
-            new_err_openings: new_err_openings,
-            t_openings: t_openings,
-            blinded_t: blinded_t,
-            t: t,
+            acc_openings,
+            new_acc_openings,
+            input_openings,
+            err_openings,
+            new_err_openings,
+            t_openings,
+            input_values,
+            acc_values,
+            err_values,
+            t_values,
+            blinded_t,
+            t,
         };
 
-        Ok(((new_acc_instance, new_acc_witness), proof))
+        Ok((
+            (new_acc_instance, new_acc_witness),
+            proof,
+            higher_terms,
+            new_w_commitment,
+            new_err_commitment,
+        ))
     }
 
-    fn verify<'a>(
-        verifier_key: &'a Self::VerifierKey,
-        proof: &Self::Proof,
-        old_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
-        new_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
-        input: (&'a Self::InputInstance, &'a Self::InputWitness),
-    ) -> Result<bool, SynthesisError> {
-        let input_openings = &proof.input_openings;
-        let acc_openings = &proof.acc_openings;
-        let new_acc_openings = &proof.new_acc_openings;
-        let t_openings = &proof.t_openings;
-        let err_openings = &proof.err_openings;
-        let new_err_openings = &proof.new_err_openings;
+    /// folds the accumulator `other` into `accumulator`, so that PCD nodes with several
+    /// predecessors can combine their accumulators. With r derived from the two
+    /// accumulators, the digest of the key and the root of t, the merged accumulator is
+    /// (x + r·other_x, w + r·other_w, err + r·t + r²·other_err, c + r·other_c) where
+    /// t = A(w) ∘ B(other_w) + A(other_w) ∘ B(w) − c·C(other_w) − other_c·C(w).
+    #[allow(clippy::type_complexity)]
+    pub fn merge(
+        prover_key: &IndexProverKey<F>,
+        accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        other: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+    ) -> Result<((AccumulatorInstance<F>, AccumulatorWitness<F>), MergeProof<F>), SynthesisError>
+    {
+        let (code_params, hash_params) = (&prover_key.code_params, &prover_key.hash_params);
+        let w_commitment = CommittedVector::commit(&accumulator.0.w, code_params, hash_params);
+        let err_commitment = CommittedVector::commit(&accumulator.0.err, code_params, hash_params);
+
+        let (merged, proof, _, _) = Self::merge_committed(
+            prover_key,
+            accumulator,
+            other,
+            (&w_commitment, &err_commitment),
+        )?;
+        Ok((merged, proof))
+    }
 
-        if input_openings.len() != acc_openings.len() {
-            return Ok(false);
-        } else if input_openings.len() != new_acc_openings.len() {
+    /// checks a merge proof of `BDASAccumulationScheme::merge` the way `verify` checks a
+    /// fold: the openings at the queried positions and the folding identities there
+    pub fn verify_merge(
+        verifier_key: &IndexVerifierKey<F>,
+        proof: &MergeProof<F>,
+        accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        other: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        merged: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+    ) -> Result<bool, SynthesisError> {
+        let code_params = &verifier_key.code_params;
+        let num_queries = code_params.num_queries;
+        let code_length = code_params.code_length;
+        let index_info = &verifier_key.index_info;
+
+        let (acc_instance, acc_witness) = accumulator;
+        let (other_instance, other_witness) = other;
+        let (new_instance, new_witness) = merged;
+
+        if [
+            proof.acc_openings.len(),
+            proof.other_openings.len(),
+            proof.new_acc_openings.len(),
+            proof.err_openings.len(),
+            proof.other_err_openings.len(),
+            proof.new_err_openings.len(),
+            proof.t_openings.len(),
+            proof.acc_values.len(),
+            proof.other_values.len(),
+            proof.err_values.len(),
+            proof.other_err_values.len(),
+            proof.t_values.len(),
+        ]
+        .iter()
+        .any(|len| *len != num_queries)
+            || proof.t.len() != index_info.num_constraints
+        {
             return Ok(false);
         }
 
-        let hash_params = poseidon_parameters::<F>();
-
-        let (input_instance, input_witness) = input;
-        let (acc_instance, acc_witness) = old_accumulator;
-        let (new_acc_instance, new_acc_witness) = new_accumulator;
-        let t = &proof.t;
-
-        let mut counter = 0;
-        let mut input_assignment = input_instance.input.clone();
-        input_assignment.extend(input_instance.witness.clone());
-
-        let new_w_code = RSCode::encode(new_acc_instance.w.clone(), 512).code;
-        let new_err_code = RSCode::encode(new_acc_instance.err.clone(), 512).code;
-        let err_code = RSCode::encode(acc_instance.err.clone(), 512).code;
-        let z_code = RSCode::encode(input_assignment.clone(), 512).code;
-        let w_code = RSCode::encode(acc_instance.w.clone(), 512).code;
-        let t_code = RSCode::encode(t.clone(), 512).code;
-
-        let opening_indexes = get_random_indices(
-            16,
-            vec![input.1.blinded_assignment],
-            vec![old_accumulator.1.blinded_w],
-            512
-        );
-
-        for opening in input_openings {
-            if !opening.verify(
-                &hash_params,
-                &hash_params,
-                &input_witness.blinded_assignment,
-                [z_code[opening_indexes[counter]]],
-            ).unwrap() {
-                return Ok(false);
-            }
-            counter += 1;
-        }
-
-        counter = 0;
-        for opening in acc_openings {
-            if !opening.verify(
-                &hash_params,
-                &hash_params,
-                &acc_witness.blinded_w,
-                [w_code[opening_indexes[counter]]],
-            ).unwrap() {
-                return Ok(false);
-            }
-            counter += 1;
-        }
-
-        counter = 0;
-        for opening in new_acc_openings {
-            if !opening.verify(
-                &hash_params,
-                &hash_params,
-                &new_acc_witness.blinded_w,
-                [new_w_code[opening_indexes[counter]]],
-            ).unwrap() {
-                return Ok(false);
-            }
-            counter += 1;
+        if [acc_instance, other_instance, new_instance]
+            .iter()
+            .any(|instance| {
+                !verifier_key.is_compatible(&instance.circuit_digest)
+                    || instance.x.len() != index_info.num_instance_variables
+            })
+        {
+            return Ok(false);
         }
 
-        counter = 0;
-        for opening in t_openings {
-            if !opening.verify(
-                &hash_params, 
-                &hash_params, 
-                &proof.blinded_t, 
-                [t_code[opening_indexes[counter]]]
-            ).unwrap() {
+        let encode = |values: &Vec<F>| RSCode::encode(values.clone(), code_length as u64).code;
+        let w_code = encode(&acc_instance.w);
+        let other_w_code = encode(&other_instance.w);
+        let new_w_code = encode(&new_instance.w);
+        let err_code = encode(&acc_instance.err);
+        let other_err_code = encode(&other_instance.err);
+        let new_err_code = encode(&new_instance.err);
+        let t_code = encode(&proof.t);
+
+        let hash_params = &verifier_key.hash_params;
+        let mut public = CommittedAccumulator::new(acc_instance, acc_witness).to_field_elements();
+        public.extend(CommittedAccumulator::new(other_instance, other_witness).to_field_elements());
+        let statement = statement(hash_params, verifier_key.digest, public)?;
+        let roots = [statement, other_witness.blinded_w, acc_witness.blinded_w, proof.blinded_t];
+        let r: F = get_randomness(hash_params, &roots)[0];
+        let opening_indexes = get_random_indices(hash_params, num_queries, &roots, code_length);
+
+        let depth = code_length.next_power_of_two().trailing_zeros() as usize;
+        for (query, index) in opening_indexes.into_iter().enumerate() {
+            if proof.acc_values[query] != w_code[index]
+                || proof.other_values[query] != other_w_code[index]
+                || proof.err_values[query] != err_code[index]
+                || proof.other_err_values[query] != other_err_code[index]
+                || proof.t_values[query] != t_code[index]
+            {
                 return Ok(false);
             }
-            counter += 1;
-        }
 
-        counter = 0;
-        for opening in err_openings {
-            if !opening.verify(
-                &hash_params,
-                &hash_params,
-                &acc_witness.blinded_err,
-                [err_code[opening_indexes[counter]]],
-            ).unwrap() {
-                return Ok(false);
+            let openings = [
+                (&proof.acc_openings, &acc_witness.blinded_w, w_code[index]),
+                (&proof.other_openings, &other_witness.blinded_w, other_w_code[index]),
+                (&proof.new_acc_openings, &new_witness.blinded_w, new_w_code[index]),
+                (&proof.err_openings, &acc_witness.blinded_err, err_code[index]),
+                (&proof.other_err_openings, &other_witness.blinded_err, other_err_code[index]),
+                (&proof.new_err_openings, &new_witness.blinded_err, new_err_code[index]),
+                (&proof.t_openings, &proof.blinded_t, t_code[index]),
+            ];
+            for (opening, root, value) in openings {
+                if !opens_at(hash_params, &opening[query], root, value, index, depth) {
+                    return Ok(false);
+                }
             }
-            counter += 1;
-        }
 
-        counter = 0;
-        for opening in new_err_openings {
-            if !opening.verify(
-                &hash_params,
-                &hash_params,
-                &new_acc_witness.blinded_err,
-                [new_err_code[opening_indexes[counter]]],
-            ).unwrap() {
+            if new_w_code[index] != w_code[index] + r * other_w_code[index]
+                || new_err_code[index]
+                    != err_code[index] + r * (t_code[index] + r * other_err_code[index])
+            {
                 return Ok(false);
             }
-            counter += 1;
         }
 
-        let r: F = get_randomness(
-            vec![input.1.blinded_assignment],
-            vec![old_accumulator.1.blinded_w],
-        )[0];
+        Ok(new_instance.c == acc_instance.c + r * other_instance.c
+            && new_instance.x == add_vectors(&acc_instance.x, &scalar_mult(&r, &other_instance.x)))
+    }
 
-        if acc_instance.c + r != new_acc_instance.c {
-            return Ok(false);
+    /// `merge` given the prover's commitments to w and err of `accumulator`, also
+    /// returning the commitments to the merged accumulator
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn merge_committed(
+        prover_key: &IndexProverKey<F>,
+        accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        other: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+        commitments: (&CommittedVector<F>, &CommittedVector<F>),
+    ) -> Result<
+        (
+            (AccumulatorInstance<F>, AccumulatorWitness<F>),
+            MergeProof<F>,
+            CommittedVector<F>,
+            CommittedVector<F>,
+        ),
+        SynthesisError,
+    > {
+        let (acc_instance, acc_witness) = accumulator;
+        let (other_instance, other_witness) = other;
+        let (w_commitment, err_commitment) = commitments;
+
+        if !prover_key.is_compatible(&acc_instance.circuit_digest)
+            || !prover_key.is_compatible(&other_instance.circuit_digest)
+        {
+            return Err(SynthesisError::MalformedVerifyingKey);
         }
 
-        for i in opening_indexes {
-            if new_w_code[i] != w_code[i] + r * z_code[i] {
-                return Ok(false);
-            }
-
-            if new_err_code[i] != err_code[i] + r * t_code[i] {
-                return Ok(false);
+        let index_info = &prover_key.index_info;
+        for instance in [acc_instance, other_instance] {
+            if !index_info.matches_split(instance.x.len(), instance.w.len())
+                || instance.err.len() != index_info.num_constraints
+            {
+                return Err(SynthesisError::Unsatisfiable);
             }
         }
 
-        Ok(true)
-    }
+        let aw = matrix_vec_mul(&prover_key.a, &acc_instance.x, &acc_instance.w);
+        let bw = matrix_vec_mul(&prover_key.b, &acc_instance.x, &acc_instance.w);
+        let cw = matrix_vec_mul(&prover_key.c, &acc_instance.x, &acc_instance.w);
 
-    fn decide<'a>(
-        decider_key: &'a Self::DeciderKey,
-        accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness),
-    ) -> Result<bool, SynthesisError> {
-        let (instance, witness) = accumulator;
+        let other_aw = matrix_vec_mul(&prover_key.a, &other_instance.x, &other_instance.w);
+        let other_bw = matrix_vec_mul(&prover_key.b, &other_instance.x, &other_instance.w);
+        let other_cw = matrix_vec_mul(&prover_key.c, &other_instance.x, &other_instance.w);
 
-        let mut aw = matrix_vec_mul(&decider_key.a, &instance.w, &vec![]);
-        let mut bw = matrix_vec_mul(&decider_key.b, &instance.w, &vec![]);
-        let mut cw = matrix_vec_mul(&decider_key.c, &instance.w, &vec![]);
+        let t = sub_vectors(
+            &add_vectors(&had_product(&aw, &other_bw), &had_product(&other_aw, &bw)),
+            &add_vectors(
+                &scalar_mult(&acc_instance.c, &other_cw),
+                &scalar_mult(&other_instance.c, &cw),
+            ),
+        );
 
-        while aw.len() < decider_key.index_info.num_variables {
-            aw.push(F::zero());
-            bw.push(F::zero());
-            cw.push(F::zero());
-        }
+        // commit to t before deriving r, so that t cannot be chosen after it
+        let (code_params, hash_params) = (&prover_key.code_params, &prover_key.hash_params);
+        let t_commitment = CommittedVector::commit(&t, code_params, hash_params);
+        let mut public = CommittedAccumulator::new(acc_instance, acc_witness).to_field_elements();
+        public.extend(CommittedAccumulator::new(other_instance, other_witness).to_field_elements());
+        let statement = statement(hash_params, prover_key.digest, public)?;
+        let roots = [
+            statement,
+            other_witness.blinded_w,
+            acc_witness.blinded_w,
+            t_commitment.root(),
+        ];
+        let r: F = get_randomness(hash_params, &roots)[0];
+
+        let other_w_commitment =
+            CommittedVector::commit(&other_instance.w, code_params, hash_params);
+        let other_err_commitment =
+            CommittedVector::commit(&other_instance.err, code_params, hash_params);
+
+        let r_squared = r * r;
+        let new_instance = AccumulatorInstance {
+            circuit_digest: prover_key.digest,
+            x: add_vectors(&acc_instance.x, &scalar_mult(&r, &other_instance.x)),
+            w: add_vectors(&acc_instance.w, &scalar_mult(&r, &other_instance.w)),
+            err: add_vectors(
+                &add_vectors(&acc_instance.err, &scalar_mult(&r, &t)),
+                &scalar_mult(&r_squared, &other_instance.err),
+            ),
+            c: acc_instance.c + r * other_instance.c,
+        };
 
-        let w_code = RSCode::encode(instance.w.clone(), 512).code;
-        let err_code = RSCode::encode(instance.err.clone(), 512).code;
+        let new_w_commitment = w_commitment.combine(&r, &other_w_commitment, hash_params);
+        let new_err_commitment = CommittedVector::from_code(
+            add_vectors(
+                &add_vectors(&err_commitment.code, &scalar_mult(&r, &t_commitment.code)),
+                &scalar_mult(&r_squared, &other_err_commitment.code),
+            ),
+            hash_params,
+        );
 
-        let mut w_modified: Vec<[F; 1]> = vec![];
-        for i in w_code.iter() {
-            w_modified.push([i.clone()]);
-        }
-        while !w_modified.len().is_power_of_two() {
-            w_modified.push([F::zero()]);
-        }
+        let new_witness = AccumulatorWitness {
+            blinded_w: new_w_commitment.root(),
+            blinded_err: new_err_commitment.root(),
+        };
 
-        let mut err_modified: Vec<[F; 1]> = vec![];
-        for i in err_code.iter() {
-            err_modified.push([i.clone()]);
+        let mut proof = MergeProof::dummy(&prover_key.code_params);
+        let indices = get_random_indices(
+            hash_params,
+            prover_key.code_params.num_queries,
+            &roots,
+            prover_key.code_params.code_length,
+        );
+        let committed = [
+            (&mut proof.acc_openings, &mut proof.acc_values, w_commitment),
+            (&mut proof.other_openings, &mut proof.other_values, &other_w_commitment),
+            (&mut proof.err_openings, &mut proof.err_values, err_commitment),
+            (&mut proof.other_err_openings, &mut proof.other_err_values, &other_err_commitment),
+            (&mut proof.t_openings, &mut proof.t_values, &t_commitment),
+        ];
+        for (openings, values, commitment) in committed {
+            *openings = indices
+                .iter()
+                .map(|i| commitment.tree.generate_proof(*i).unwrap())
+                .collect();
+            *values = indices.iter().map(|i| commitment.code[*i]).collect();
         }
-        while !err_modified.len().is_power_of_two() {
-            err_modified.push([F::zero()]);
+        for (openings, commitment) in [
+            (&mut proof.new_acc_openings, &new_w_commitment),
+            (&mut proof.new_err_openings, &new_err_commitment),
+        ] {
+            *openings = indices
+                .iter()
+                .map(|i| commitment.tree.generate_proof(*i).unwrap())
+                .collect();
         }
+        proof.blinded_t = t_commitment.root();
+        proof.t = t;
+
+        Ok((
+            (new_instance, new_witness),
+            proof,
+            new_w_commitment,
+            new_err_commitment,
+        ))
+    }
+}
 
-        let hash_params = poseidon_parameters::<F>();
-
-        let w_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, w_modified).unwrap();
-
-        let err_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, err_modified)
-                .unwrap();
-
-        assert_eq!(w_tree.root(), witness.blinded_w);
-        assert_eq!(err_tree.root(), witness.blinded_err);
-
-        let lhs = had_product(&aw, &bw);
+/// commits to w and err of `accumulator`, failing with `SynthesisError::Unsatisfiable`
+/// if its witness holds other roots
+pub(crate) fn commit_accumulator<F: PrimeField + Absorb>(
+    code_params: &CodeParameters,
+    hash_params: &HashParameters<F>,
+    accumulator: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+) -> Result<(CommittedVector<F>, CommittedVector<F>), SynthesisError> {
+    let (instance, witness) = accumulator;
+    let w_commitment = CommittedVector::commit(&instance.w, code_params, hash_params);
+    let err_commitment = CommittedVector::commit(&instance.err, code_params, hash_params);
+    if w_commitment.root() != witness.blinded_w || err_commitment.root() != witness.blinded_err {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    Ok((w_commitment, err_commitment))
+}
 
-        let rhs = add_vectors(&instance.err, &scalar_mult(&instance.c, &cw));
-        
-        if lhs == rhs {
-            return Ok(true)
-        }
-        Ok(false)
+/// whether `opening` opens `root` to `value` at `index` in a tree of the given depth,
+/// whatever position the path itself claims
+fn opens_at<F: PrimeField + Absorb>(
+    hash_params: &HashParameters<F>,
+    opening: &Path<MerkleHashConfig<F>>,
+    root: &F,
+    value: F,
+    index: usize,
+    depth: usize,
+) -> bool {
+    if opening.auth_path.len() + 1 != depth {
+        return false;
     }
+    let opening = Path {
+        leaf_index: index,
+        ..opening.clone()
+    };
+    opening.verify(hash_params, hash_params, root, [value]).unwrap()
 }
 
-fn add_vectors<F: Field>(vec_a: &Vec<F>, vec_b: &Vec<F>) -> Vec<F> {
-    let sum = cfg_into_iter!(vec_a)
+pub(crate) fn add_vectors<F: Field>(vec_a: &Vec<F>, vec_b: &Vec<F>) -> Vec<F> {
+    cfg_into_iter!(vec_a)
         .zip(vec_b)
         .map(|(a, b)| (*a) + (*b))
-        .collect();
-
-    sum
+        .collect()
 }
 
 fn sub_vectors<F: Field>(vec_a: &Vec<F>, vec_b: &Vec<F>) -> Vec<F> {
-    let diff = cfg_into_iter!(vec_a)
+    cfg_into_iter!(vec_a)
         .zip(vec_b)
         .map(|(a, b)| (*a) - (*b))
-        .collect();
-
-    diff
+        .collect()
 }
 
 fn had_product<F: Field>(vec_a: &Vec<F>, vec_b: &Vec<F>) -> Vec<F> {
-    let had_prod = cfg_into_iter!(vec_a)
+    cfg_into_iter!(vec_a)
         .zip(vec_b)
         .map(|(a, b)| (*a) * (*b))
-        .collect();
-
-    had_prod
+        .collect()
 }
 
-fn scalar_mult<F: Field>(c: &F, vec_a: &Vec<F>) -> Vec<F> {
+pub(crate) fn scalar_mult<F: Field>(c: &F, vec_a: &Vec<F>) -> Vec<F> {
     let result: Vec<F> = cfg_into_iter!(vec_a).map(|a| (*a) * (*c)).collect();
     result
 }
@@ -550,10 +1070,13 @@ pub mod test {
         },
         TwoToOneCRHScheme, TwoToOneCRHSchemeGadget,
     };
-    use ark_relations::r1cs::ConstraintSynthesizer;
+    use ark_relations::lc;
+    use ark_relations::r1cs::{ConstraintSynthesizer, Variable};
     use ark_ed_on_bls12_381::Fr;
     use ark_ff::One;
     use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use r1cs_nark::poseidon2::poseidon2_parameters;
     use r1cs_nark::{
         poseidon_parameters, R1CSNark
     };
@@ -600,8 +1123,8 @@ pub mod test {
         println!("inp_2: {:?}", inp_wit_2);
         let inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
             &poseidon_parameters(),
-            inp_wit_1.clone(),
-            inp_wit_2.clone(),
+            inp_wit_1,
+            inp_wit_2,
         )
         .unwrap();
         let hash_circ = HashVerifyCirc {
@@ -611,19 +1134,37 @@ pub mod test {
         };
         let pp = R1CSNark::<Fr>::setup();
 
-        let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
+        let Ok((ipk, _)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
             panic!("prover key not generated")
         };
+        let idk = ipk.decider_key();
 
         let null_acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
         let null_acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
 
         let null_accumulator_succeeded = BDASAccumulationScheme::decide(
-            &ivk, 
+            &idk, 
             (&null_acc_instance, &null_acc_witness)
         ).unwrap();
 
-        assert_eq!(null_accumulator_succeeded, true);
+        assert!(null_accumulator_succeeded);
+    }
+
+    #[test]
+    pub fn test_zero_root_matches_tree() {
+        for hash_params in [
+            HashParameters::Poseidon(poseidon_parameters()),
+            HashParameters::Poseidon2(poseidon2_parameters()),
+        ] {
+            for code_length in [CODE_LENGTH, 4 * CODE_LENGTH] {
+                let code_params = CodeParameters { code_length, ..CodeParameters::default() };
+                let committed = CommittedVector::<Fr>::commit(&[], &code_params, &hash_params);
+                assert_eq!(
+                    CommittedVector::zero_root(&code_params, &hash_params),
+                    committed.root()
+                );
+            }
+        }
     }
 
     #[test]
@@ -635,8 +1176,8 @@ pub mod test {
         println!("inp_2: {:?}", inp_wit_2);
         let inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
             &poseidon_parameters(),
-            inp_wit_1.clone(),
-            inp_wit_2.clone(),
+            inp_wit_1,
+            inp_wit_2,
         )
         .unwrap();
         let hash_circ = HashVerifyCirc {
@@ -649,6 +1190,7 @@ pub mod test {
         let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
             panic!("prover key not generated")
         };
+        let idk = ipk.decider_key();
 
         let null_acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
         let null_acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
@@ -658,11 +1200,11 @@ pub mod test {
             panic!["proof not generated"]
         };
 
-        if !R1CSNark::<Fr>::verify(
-            &ivk, 
+        if R1CSNark::<Fr>::verify(
+            &idk, 
             &proof.instance.input, 
             &proof
-        ) {
+        ).is_err() {
             panic!["R1CS proof not verified"];
         }
 
@@ -679,7 +1221,7 @@ pub mod test {
             &acc_proof,
             (&null_acc_instance, &null_acc_witness), 
             (&new_acc_instance, &new_acc_witness), 
-            (&proof.instance, &proof.witness)
+            &proof.instance
         ).unwrap();
 
         if !verification_result {
@@ -687,10 +1229,878 @@ pub mod test {
         }
 
         let decider_succeeded = BDASAccumulationScheme::decide(
-            &ivk, 
+            &idk, 
             (&new_acc_instance, &new_acc_witness)
         ).unwrap();
 
-        assert_eq!(decider_succeeded, true);
+        assert!(decider_succeeded);
+    }
+
+    #[test]
+    pub fn test_stateful_prover() {
+        let mut inp_wit_1 = Fr::from(3u64);
+        let mut inp_wit_2 = Fr::from(7u64);
+        let hash_circ = |inp_wit_1: Fr, inp_wit_2: Fr| {
+            let inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
+                &poseidon_parameters(),
+                inp_wit_1,
+                inp_wit_2,
+            )
+            .unwrap();
+            HashVerifyCirc {
+                inp_wit_1,
+                inp_wit_2,
+                inp_hash,
+            }
+        };
+        let pp = R1CSNark::<Fr>::setup();
+
+        let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, hash_circ(inp_wit_1, inp_wit_2)) else {
+            panic!("prover key not generated")
+        };
+        let idk = ipk.decider_key();
+
+        let mut prover = AccumulatorProver::new(ipk.clone());
+        let mut rng = ark_std::test_rng();
+
+        for _ in 0..3 {
+            let circ = hash_circ(inp_wit_1, inp_wit_2);
+            let next_hash = circ.inp_hash;
+            let proof = R1CSNark::<Fr>::prove(&ipk, circ, Some(&mut rng)).unwrap();
+
+            let (old_acc_instance, old_acc_witness) = {
+                let (instance, witness) = prover.accumulator();
+                (instance.clone(), witness.clone())
+            };
+
+            let Ok(((expected_instance, expected_witness), _)) = BDASAccumulationScheme::<Fr>::prove(
+                &ipk,
+                (&old_acc_instance, &old_acc_witness),
+                (&proof.instance, &proof.witness)
+            ) else {
+                panic!["accumulation proof not generated"]
+            };
+
+            let ((new_acc_instance, new_acc_witness), acc_proof) =
+                prover.accumulate(&proof).unwrap();
+
+            assert!(new_acc_instance.x == expected_instance.x);
+            assert!(new_acc_instance.w == expected_instance.w);
+            assert!(new_acc_instance.err == expected_instance.err);
+            assert!(new_acc_witness.blinded_w == expected_witness.blinded_w);
+            assert!(new_acc_witness.blinded_err == expected_witness.blinded_err);
+
+            assert!(BDASAccumulationScheme::<Fr>::verify(
+                &ivk,
+                &acc_proof,
+                (&old_acc_instance, &old_acc_witness),
+                (&new_acc_instance, &new_acc_witness),
+                &proof.instance
+            )
+            .unwrap());
+
+            inp_wit_1 = inp_wit_2;
+            inp_wit_2 = next_hash;
+        }
+
+        let (instance, witness) = prover.accumulator();
+        assert!(BDASAccumulationScheme::decide(&idk, (instance, witness)).unwrap());
+    }
+
+    /// x * x = y enforced many times over three variables
+    #[derive(Clone)]
+    pub struct ConstraintHeavyCirc {
+        x: Fr,
+        num_constraints: usize,
+    }
+    impl ConstraintSynthesizer<Fr> for ConstraintHeavyCirc {
+        fn generate_constraints(
+            self,
+            cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+        ) -> ark_relations::r1cs::Result<()> {
+            let y = cs.new_input_variable(|| Ok(self.x * self.x))?;
+            let x = cs.new_witness_variable(|| Ok(self.x))?;
+            for _ in 0..self.num_constraints {
+                cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + y)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// a single constraint summing many witnesses into the input
+    #[derive(Clone)]
+    pub struct VariableHeavyCirc {
+        values: Vec<Fr>,
+    }
+    impl ConstraintSynthesizer<Fr> for VariableHeavyCirc {
+        fn generate_constraints(
+            self,
+            cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+        ) -> ark_relations::r1cs::Result<()> {
+            let sum = cs.new_input_variable(|| Ok(self.values.iter().sum()))?;
+            let mut sum_lc = lc!();
+            for value in self.values {
+                sum_lc = sum_lc + cs.new_witness_variable(|| Ok(value))?;
+            }
+            cs.enforce_constraint(sum_lc, lc!() + Variable::One, lc!() + sum)?;
+            Ok(())
+        }
+    }
+
+    fn fold_and_decide<C: ConstraintSynthesizer<Fr> + Clone>(circuits: Vec<C>) -> bool {
+        let pp = R1CSNark::<Fr>::setup();
+        let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, circuits[0].clone()) else {
+            panic!("prover key not generated")
+        };
+        let idk = ipk.decider_key();
+
+        let mut acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let mut acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+        assert_eq!(acc_instance.err.len(), ipk.index_info.num_constraints);
+
+        let mut rng = ark_std::test_rng();
+        for circuit in circuits {
+            let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+            let Ok(((new_acc_instance, new_acc_witness), acc_proof)) = BDASAccumulationScheme::<Fr>::prove(
+                &ipk,
+                (&acc_instance, &acc_witness),
+                (&proof.instance, &proof.witness)
+            ) else {
+                panic!["accumulation proof not generated"]
+            };
+            assert_eq!(acc_proof.t.len(), ipk.index_info.num_constraints);
+
+            if !BDASAccumulationScheme::<Fr>::verify(
+                &ivk,
+                &acc_proof,
+                (&acc_instance, &acc_witness),
+                (&new_acc_instance, &new_acc_witness),
+                &proof.instance
+            ).unwrap() {
+                return false;
+            }
+            acc_instance = new_acc_instance;
+            acc_witness = new_acc_witness;
+        }
+
+        BDASAccumulationScheme::decide(&idk, (&acc_instance, &acc_witness)).unwrap()
+    }
+
+    #[test]
+    pub fn test_constraint_heavy_circuit() {
+        let circuits: Vec<_> = (2..5u64)
+            .map(|x| ConstraintHeavyCirc {
+                x: Fr::from(x),
+                num_constraints: 20,
+            })
+            .collect();
+        assert!(fold_and_decide(circuits));
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_variable_heavy_circuit() {
+        let circuits: Vec<_> = (0..3u64)
+            .map(|i| VariableHeavyCirc {
+                values: (0..20u64).map(|j| Fr::from(i * 20 + j)).collect(),
+            })
+            .collect();
+        assert!(fold_and_decide(circuits));
+    }
+
+    #[test]
+    pub fn test_poseidon2_commitments() {
+        let circuits: Vec<_> = (2..5u64)
+            .map(|x| ConstraintHeavyCirc {
+                x: Fr::from(x),
+                num_constraints: 4,
+            })
+            .collect();
+        let pp = R1CSNark::<Fr>::setup();
+        let Ok((ipk, _)) = R1CSNark::<Fr>::index(&pp, circuits[0].clone()) else {
+            panic!("prover key not generated")
+        };
+        let poseidon_idk = ipk.decider_key();
+        let ipk = ipk.with_hash_params(HashParameters::Poseidon2(poseidon2_parameters()));
+        let ivk = ipk.verifier_key();
+        let idk = ipk.decider_key();
+
+        let mut acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let mut acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+        let mut rng = ark_std::test_rng();
+        for circuit in circuits {
+            let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+            assert_eq!(R1CSNark::<Fr>::verify(&idk, &proof.instance.input, &proof), Ok(()));
+            let Ok(((new_acc_instance, new_acc_witness), acc_proof)) =
+                BDASAccumulationScheme::<Fr>::prove(
+                    &ipk,
+                    (&acc_instance, &acc_witness),
+                    (&proof.instance, &proof.witness),
+                )
+            else {
+                panic!["accumulation proof not generated"]
+            };
+
+            // the folding challenge is a Poseidon2 hash of the statement and the roots
+            let statement = statement(
+                &ipk.hash_params,
+                ipk.digest,
+                proof.instance.input.iter().copied().chain(
+                    CommittedAccumulator::new(&acc_instance, &acc_witness).to_field_elements(),
+                ),
+            )
+            .unwrap();
+            let roots = [
+                statement,
+                proof.instance.blinded_witness,
+                acc_witness.blinded_w,
+                acc_proof.blinded_t,
+            ];
+            let r = poseidon2_parameters::<Fr>().hash(&roots);
+            assert_eq!(new_acc_instance.x[1], acc_instance.x[1] + r * proof.instance.input[1]);
+
+            assert!(BDASAccumulationScheme::<Fr>::verify(
+                &ivk,
+                &acc_proof,
+                (&acc_instance, &acc_witness),
+                (&new_acc_instance, &new_acc_witness),
+                &proof.instance
+            )
+            .unwrap());
+            acc_instance = new_acc_instance;
+            acc_witness = new_acc_witness;
+        }
+
+        assert!(BDASAccumulationScheme::decide(&idk, (&acc_instance, &acc_witness)).unwrap());
+        // the roots are Poseidon2 ones, a Poseidon decider does not recompute them
+        assert!(
+            !BDASAccumulationScheme::decide(&poseidon_idk, (&acc_instance, &acc_witness)).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_public_input_folded_in_clear() {
+        let circuits: Vec<_> = (2..4u64)
+            .map(|x| ConstraintHeavyCirc {
+                x: Fr::from(x),
+                num_constraints: 4,
+            })
+            .collect();
+        let pp = R1CSNark::<Fr>::setup();
+        let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, circuits[0].clone()) else {
+            panic!("prover key not generated")
+        };
+        let idk = ipk.decider_key();
+
+        let mut acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let mut acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+        assert_eq!(acc_instance.public_input().len(), 2);
+
+        let mut rng = ark_std::test_rng();
+        for circuit in circuits {
+            let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+            let Ok(((new_acc_instance, new_acc_witness), acc_proof)) = BDASAccumulationScheme::<Fr>::prove(
+                &ipk,
+                (&acc_instance, &acc_witness),
+                (&proof.instance, &proof.witness)
+            ) else {
+                panic!["accumulation proof not generated"]
+            };
+
+            let statement = statement(
+                &ipk.hash_params,
+                ipk.digest,
+                proof.instance.input.iter().copied().chain(
+                    CommittedAccumulator::new(&acc_instance, &acc_witness).to_field_elements(),
+                ),
+            )
+            .unwrap();
+            let r: Fr = get_randomness(&ipk.hash_params, &[
+                statement,
+                proof.instance.blinded_witness,
+                acc_witness.blinded_w,
+                acc_proof.blinded_t,
+            ])[0];
+            let expected_x: Vec<Fr> = acc_instance
+                .x
+                .iter()
+                .zip(&proof.instance.input)
+                .map(|(x, input)| *x + r * input)
+                .collect();
+            assert!(new_acc_instance.public_input() == expected_x.as_slice());
+            assert_eq!(new_acc_instance.w.len(), proof.witness.witness.len());
+
+            // a wrong folded public input is caught without touching the commitments
+            let mut tampered_instance = new_acc_instance.clone();
+            tampered_instance.x[1] += Fr::one();
+            assert!(!BDASAccumulationScheme::<Fr>::verify(
+                &ivk,
+                &acc_proof,
+                (&acc_instance, &acc_witness),
+                (&tampered_instance, &new_acc_witness),
+                &proof.instance
+            ).unwrap());
+
+            assert!(BDASAccumulationScheme::<Fr>::verify(
+                &ivk,
+                &acc_proof,
+                (&acc_instance, &acc_witness),
+                (&new_acc_instance, &new_acc_witness),
+                &proof.instance
+            ).unwrap());
+            acc_instance = new_acc_instance;
+            acc_witness = new_acc_witness;
+        }
+
+        assert!(BDASAccumulationScheme::decide(&idk, (&acc_instance, &acc_witness)).unwrap());
+    }
+
+    #[test]
+    pub fn test_verifier_sees_only_instance() {
+        let pp = R1CSNark::<Fr>::setup();
+        let mut rng = ark_std::test_rng();
+        let mut instance_sizes = vec![];
+
+        for num_values in [4u64, 64] {
+            let circuit = VariableHeavyCirc {
+                values: (0..num_values).map(Fr::from).collect(),
+            };
+            let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, circuit.clone()) else {
+                panic!("prover key not generated")
+            };
+            let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+
+            let acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+            let acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+            let Ok(((new_acc_instance, new_acc_witness), mut acc_proof)) = BDASAccumulationScheme::<Fr>::prove(
+                &ipk,
+                (&acc_instance, &acc_witness),
+                (&proof.instance, &proof.witness)
+            ) else {
+                panic!["accumulation proof not generated"]
+            };
+
+            // the verifier only receives the serialized instance
+            let mut bytes = vec![];
+            proof.instance.serialize_compressed(&mut bytes).unwrap();
+            instance_sizes.push(bytes.len());
+            let instance = Instance::<Fr>::deserialize_compressed(&bytes[..]).unwrap();
+
+            assert!(BDASAccumulationScheme::<Fr>::verify(
+                &ivk,
+                &acc_proof,
+                (&acc_instance, &acc_witness),
+                (&new_acc_instance, &new_acc_witness),
+                &instance
+            ).unwrap());
+
+            acc_proof.input_values[0] += Fr::one();
+            assert!(!BDASAccumulationScheme::<Fr>::verify(
+                &ivk,
+                &acc_proof,
+                (&acc_instance, &acc_witness),
+                (&new_acc_instance, &new_acc_witness),
+                &instance
+            ).unwrap());
+        }
+
+        assert_eq!(instance_sizes[0], instance_sizes[1]);
+    }
+
+    #[test]
+    pub fn test_rejects_mismatched_keys() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circuit = |num_constraints| ConstraintHeavyCirc {
+            x: Fr::from(3u64),
+            num_constraints,
+        };
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, circuit(4)).unwrap();
+        let idk = ipk.decider_key();
+        let (other_ipk, other_ivk) = R1CSNark::<Fr>::index(&pp, circuit(5)).unwrap();
+        let other_idk = other_ipk.decider_key();
+
+        let mut rng = ark_std::test_rng();
+        let proof = R1CSNark::<Fr>::prove(&ipk, circuit(4), Some(&mut rng)).unwrap();
+        let other_proof = R1CSNark::<Fr>::prove(&other_ipk, circuit(5), Some(&mut rng)).unwrap();
+
+        let acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+
+        let Err(SynthesisError::MalformedVerifyingKey) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&other_proof.instance, &other_proof.witness)
+        ) else {
+            panic!["input of another circuit accumulated"]
+        };
+
+        let ((new_acc_instance, new_acc_witness), acc_proof) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&proof.instance, &proof.witness)
+        ).unwrap();
+
+        assert!(!BDASAccumulationScheme::<Fr>::verify(
+            &other_ivk,
+            &acc_proof,
+            (&acc_instance, &acc_witness),
+            (&new_acc_instance, &new_acc_witness),
+            &proof.instance
+        ).unwrap());
+        assert!(!BDASAccumulationScheme::decide(&other_idk, (&new_acc_instance, &new_acc_witness)).unwrap());
+        assert!(BDASAccumulationScheme::decide(&idk, (&new_acc_instance, &new_acc_witness)).unwrap());
+    }
+
+    #[test]
+    pub fn test_rejects_missing_openings() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circuit = ConstraintHeavyCirc {
+            x: Fr::from(3u64),
+            num_constraints: 4,
+        };
+        let (ipk, ivk) = R1CSNark::<Fr>::index(&pp, circuit.clone()).unwrap();
+
+        let mut rng = ark_std::test_rng();
+        let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+        let acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+        let ((new_acc_instance, new_acc_witness), mut acc_proof) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&proof.instance, &proof.witness)
+        ).unwrap();
+        assert_eq!(acc_proof.t_openings.len(), ivk.code_params.num_queries);
+
+        acc_proof.t_openings.pop();
+        assert!(!BDASAccumulationScheme::<Fr>::verify(
+            &ivk,
+            &acc_proof,
+            (&acc_instance, &acc_witness),
+            (&new_acc_instance, &new_acc_witness),
+            &proof.instance
+        ).unwrap());
+    }
+
+    #[test]
+    pub fn test_rejects_input_with_wrong_split() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circuit = ConstraintHeavyCirc {
+            x: Fr::from(3u64),
+            num_constraints: 4,
+        };
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, circuit.clone()).unwrap();
+
+        let mut rng = ark_std::test_rng();
+        let mut proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+        let output = proof.instance.input.pop().unwrap();
+        proof.witness.witness.insert(0, output);
+        proof.instance.blinded_witness = CommittedVector::commit(
+            &proof.witness.witness,
+            &ipk.code_params,
+            &ipk.hash_params,
+        )
+        .root();
+
+        let acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+        let Err(SynthesisError::Unsatisfiable) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&proof.instance, &proof.witness)
+        ) else {
+            panic!["input with a different instance/witness split accumulated"]
+        };
+    }
+
+    #[test]
+    pub fn test_rejects_forged_cross_term() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circuit = ConstraintHeavyCirc {
+            x: Fr::from(3u64),
+            num_constraints: 4,
+        };
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, circuit.clone()).unwrap();
+        let ipk = ipk.with_code_params(CodeParameters::with_num_queries(4));
+        let hash_params = &ipk.hash_params;
+        let code_params = ipk.code_params;
+
+        // an input that does not satisfy the circuit
+        let mut rng = ark_std::test_rng();
+        let mut proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+        proof.witness.witness[0] += Fr::one();
+        let z_commitment =
+            CommittedVector::commit(&proof.witness.witness, &code_params, hash_params);
+        proof.instance.blinded_witness = z_commitment.root();
+        let acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+
+        // a prover who learns r before committing to t picks the t that makes the folded
+        // accumulator satisfy the relaxed relation
+        let roots = [proof.instance.blinded_witness, acc_witness.blinded_w];
+        let r: Fr = get_randomness(hash_params, &roots)[0];
+        let x = add_vectors(&acc_instance.x, &scalar_mult(&r, &proof.instance.input));
+        let w = add_vectors(&acc_instance.w, &scalar_mult(&r, &proof.witness.witness));
+        let c = acc_instance.c + r;
+        let az = matrix_vec_mul(&ipk.a, &x, &w);
+        let bz = matrix_vec_mul(&ipk.b, &x, &w);
+        let cz = matrix_vec_mul(&ipk.c, &x, &w);
+        let err = sub_vectors(&had_product(&az, &bz), &scalar_mult(&c, &cz));
+        let t = scalar_mult(&r.inverse().unwrap(), &sub_vectors(&err, &acc_instance.err));
+
+        let w_commitment = CommittedVector::commit(&acc_instance.w, &code_params, hash_params);
+        let err_commitment = CommittedVector::commit(&acc_instance.err, &code_params, hash_params);
+        let t_commitment = CommittedVector::commit(&t, &code_params, hash_params);
+        let new_w_commitment = CommittedVector::commit(&w, &code_params, hash_params);
+        let new_err_commitment = CommittedVector::commit(&err, &code_params, hash_params);
+        let new_instance = AccumulatorInstance {
+            circuit_digest: ipk.digest(),
+            x,
+            w,
+            err,
+            c,
+        };
+        let new_witness = AccumulatorWitness {
+            blinded_w: new_w_commitment.root(),
+            blinded_err: new_err_commitment.root(),
+        };
+        assert!(BDASAccumulationScheme::decide(&ipk.decider_key(), (&new_instance, &new_witness))
+            .unwrap());
+
+        // every opening is honest, at the positions queried before t is committed to
+        let indices = get_random_indices(
+            hash_params,
+            code_params.num_queries,
+            &roots,
+            code_params.code_length,
+        );
+        let open = |commitment: &CommittedVector<Fr>| -> Vec<_> {
+            indices.iter().map(|i| commitment.tree.generate_proof(*i).unwrap()).collect()
+        };
+        let values = |commitment: &CommittedVector<Fr>| -> Vec<Fr> {
+            indices.iter().map(|i| commitment.code[*i]).collect()
+        };
+        let forged = Proof {
+            acc_openings: open(&w_commitment),
+            new_acc_openings: open(&new_w_commitment),
+            input_openings: open(&z_commitment),
+            err_openings: open(&err_commitment),
+            new_err_openings: open(&new_err_commitment),
+            t_openings: open(&t_commitment),
+            input_values: values(&z_commitment),
+            acc_values: values(&w_commitment),
+            err_values: values(&err_commitment),
+            t_values: values(&t_commitment),
+            blinded_t: t_commitment.root(),
+            t,
+        };
+        assert!(!BDASAccumulationScheme::<Fr>::verify(
+            &ipk.verifier_key(),
+            &forged,
+            (&acc_instance, &acc_witness),
+            (&new_instance, &new_witness),
+            &proof.instance,
+        )
+        .unwrap());
+    }
+
+    #[test]
+    pub fn test_challenge_binds_statement() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circuit = ConstraintHeavyCirc {
+            x: Fr::from(3u64),
+            num_constraints: 4,
+        };
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, circuit.clone()).unwrap();
+        let mut rng = ark_std::test_rng();
+        let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+        let acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+
+        // the same cross term and roots, folded under another digest or into an
+        // accumulator with another x, c or err root
+        let (code_params, hash_params) = (&ipk.code_params, &ipk.hash_params);
+        let w_commitment = CommittedVector::commit(&acc_instance.w, code_params, hash_params);
+        let err_commitment = CommittedVector::commit(&acc_instance.err, code_params, hash_params);
+        let t = vec![Fr::from(0u64); ipk.index_info.num_constraints];
+        let challenge = |digest, instance: &AccumulatorInstance<Fr>, witness| {
+            let ((new_instance, _), ..) = BDASAccumulationScheme::fold_cross_terms(
+                (hash_params, code_params, digest),
+                &[],
+                (instance, witness),
+                (&proof.instance, &proof.witness),
+                (t.clone(), vec![]),
+                (&w_commitment, &err_commitment),
+                None,
+            )
+            .unwrap();
+            new_instance.c - instance.c
+        };
+        let r = challenge(ipk.digest(), &acc_instance, &acc_witness);
+        assert_ne!(challenge(CircuitDigest([1; 32]), &acc_instance, &acc_witness), r);
+
+        let mut other_instance = acc_instance.clone();
+        other_instance.x[1] += Fr::one();
+        assert_ne!(challenge(ipk.digest(), &other_instance, &acc_witness), r);
+        let mut other_instance = acc_instance.clone();
+        other_instance.c += Fr::one();
+        assert_ne!(challenge(ipk.digest(), &other_instance, &acc_witness), r);
+        let mut other_witness = acc_witness.clone();
+        other_witness.blinded_err += Fr::one();
+        assert_ne!(challenge(ipk.digest(), &acc_instance, &other_witness), r);
+    }
+
+    #[test]
+    pub fn test_rejects_wrong_roots() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circuit = ConstraintHeavyCirc {
+            x: Fr::from(3u64),
+            num_constraints: 4,
+        };
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, circuit.clone()).unwrap();
+        let mut rng = ark_std::test_rng();
+        let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+        let acc_instance = AccumulatorInstance::<Fr>::zero(ipk.clone());
+        let acc_witness = AccumulatorWitness::<Fr>::zero(ipk.clone());
+
+        // an accumulator whose witness does not commit to w
+        let mut bad_witness = acc_witness.clone();
+        bad_witness.blinded_w += Fr::one();
+        let Err(SynthesisError::Unsatisfiable) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&acc_instance, &bad_witness),
+            (&proof.instance, &proof.witness)
+        ) else {
+            panic!["accumulator with a wrong root accumulated"]
+        };
+        let Err(SynthesisError::Unsatisfiable) =
+            AccumulatorProver::from_accumulator(ipk.clone(), (acc_instance.clone(), bad_witness))
+        else {
+            panic!["prover resumed from an accumulator with a wrong root"]
+        };
+
+        // an input whose instance does not commit to its witness
+        let mut bad_input = proof.instance.clone();
+        bad_input.blinded_witness += Fr::one();
+        let Err(SynthesisError::Unsatisfiable) = BDASAccumulationScheme::<Fr>::prove(
+            &ipk,
+            (&acc_instance, &acc_witness),
+            (&bad_input, &proof.witness)
+        ) else {
+            panic!["input with a wrong root accumulated"]
+        };
+        let mut prover = AccumulatorProver::new(ipk.clone());
+        let Err(ProverError::Synthesis(SynthesisError::Unsatisfiable)) =
+            prover.prove((&bad_input, &proof.witness), proof.commitment.as_ref())
+        else {
+            panic!["input with a wrong root accumulated by the stateful prover"]
+        };
+    }
+
+    #[test]
+    pub fn test_check_input_before_folding() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circuit = ConstraintHeavyCirc {
+            x: Fr::from(3u64),
+            num_constraints: 4,
+        };
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, circuit.clone()).unwrap();
+
+        let mut rng = ark_std::test_rng();
+        let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+        assert_eq!(
+            BDASAccumulationScheme::<Fr>::check_input(&ipk, (&proof.instance, &proof.witness)),
+            Ok(())
+        );
+
+        let mut bad_proof = proof.clone().without_commitment();
+        bad_proof.witness.witness[0] += Fr::one();
+        bad_proof.instance.blinded_witness = CommittedVector::commit(
+            &bad_proof.witness.witness,
+            &ipk.code_params,
+            &ipk.hash_params,
+        )
+        .root();
+        let Err(ProverError::Unsatisfied(report)) = BDASAccumulationScheme::<Fr>::check_input(
+            &ipk,
+            (&bad_proof.instance, &bad_proof.witness),
+        ) else {
+            panic!["unsatisfying input passed the check"]
+        };
+        assert_eq!(report.rows, vec![0, 1, 2, 3]);
+        assert_eq!(report.num_unsatisfied, 4);
+
+        // folding does not look at the constraints unless asked to
+        let mut prover = AccumulatorProver::new(ipk.clone());
+        assert!(prover.accumulate(&bad_proof).is_ok());
+
+        let mut checking_prover = AccumulatorProver::new(ipk).check_inputs(true);
+        assert!(checking_prover.accumulate(&proof).is_ok());
+        assert_eq!(
+            checking_prover.accumulate(&bad_proof).err(),
+            Some(ProverError::Unsatisfied(report))
+        );
+
+        // an input of the wrong length is rejected before the constraints are evaluated
+        let mut short_input = proof.instance.clone();
+        short_input.input.pop();
+        assert_eq!(
+            BDASAccumulationScheme::<Fr>::check_input(
+                checking_prover.prover_key(),
+                (&short_input, &proof.witness)
+            ),
+            Err(ProverError::Synthesis(SynthesisError::Unsatisfiable))
+        );
+        let mut long_witness = proof.witness.clone();
+        long_witness.witness.push(Fr::one());
+        assert_eq!(
+            checking_prover.prove((&proof.instance, &long_witness), None).err(),
+            Some(ProverError::Synthesis(SynthesisError::Unsatisfiable))
+        );
+        assert_eq!(
+            checking_prover.prove((&short_input, &proof.witness), None).err(),
+            Some(ProverError::Synthesis(SynthesisError::Unsatisfiable))
+        );
+    }
+
+    #[test]
+    pub fn test_decide_with_report() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circuit = ConstraintHeavyCirc {
+            x: Fr::from(3u64),
+            num_constraints: 4,
+        };
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, circuit.clone()).unwrap();
+        let idk = ipk.decider_key();
+
+        let mut rng = ark_std::test_rng();
+        let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+        let mut prover = AccumulatorProver::new(ipk.clone());
+        let ((acc_instance, acc_witness), _) = prover.accumulate(&proof).unwrap();
+        assert_eq!(
+            BDASAccumulationScheme::<Fr>::decide_with_report(&idk, (&acc_instance, &acc_witness)),
+            Ok(())
+        );
+
+        let mut bad_instance = acc_instance.clone();
+        bad_instance.w[0] += Fr::one();
+        assert_eq!(
+            BDASAccumulationScheme::<Fr>::decide_with_report(&idk, (&bad_instance, &acc_witness)),
+            Err(DecideError::WitnessCommitment)
+        );
+        // decide no longer panics on a commitment mismatch
+        assert!(
+            !BDASAccumulationScheme::<Fr>::decide(&idk, (&bad_instance, &acc_witness)).unwrap()
+        );
+
+        let mut bad_witness = acc_witness.clone();
+        bad_witness.blinded_err = acc_witness.blinded_w;
+        assert_eq!(
+            BDASAccumulationScheme::<Fr>::decide_with_report(&idk, (&acc_instance, &bad_witness)),
+            Err(DecideError::ErrCommitment)
+        );
+
+        let mut bad_instance = acc_instance.clone();
+        bad_instance.err.pop();
+        assert_eq!(
+            BDASAccumulationScheme::<Fr>::decide_with_report(&idk, (&bad_instance, &acc_witness)),
+            Err(DecideError::ErrLength {
+                expected: 4,
+                found: 3
+            })
+        );
+
+        // an unsatisfying input folded into the chain shows up in the rows it breaks
+        let mut bad_proof = proof.clone().without_commitment();
+        bad_proof.witness.witness[0] += Fr::one();
+        bad_proof.instance.blinded_witness = CommittedVector::commit(
+            &bad_proof.witness.witness,
+            &ipk.code_params,
+            &ipk.hash_params,
+        )
+        .root();
+        let ((acc_instance, acc_witness), _) = prover.accumulate(&bad_proof).unwrap();
+        let Err(DecideError::UnsatisfiedConstraints(report)) =
+            BDASAccumulationScheme::<Fr>::decide_with_report(&idk, (&acc_instance, &acc_witness))
+        else {
+            panic!["accumulator of an unsatisfying input decided"]
+        };
+        assert_eq!(report.rows, vec![0, 1, 2, 3]);
+    }
+    #[test]
+    pub fn test_merge_accumulators() {
+        let circuits: Vec<_> = (2..6u64)
+            .map(|x| ConstraintHeavyCirc {
+                x: Fr::from(x),
+                num_constraints: 4,
+            })
+            .collect();
+        let pp = R1CSNark::<Fr>::setup();
+        let (ipk, ivk) = R1CSNark::<Fr>::index(&pp, circuits[0].clone()).unwrap();
+        let idk = ipk.decider_key();
+
+        // two chains folding two inputs each
+        let mut rng = ark_std::test_rng();
+        let mut left = AccumulatorProver::new(ipk.clone());
+        let mut right = AccumulatorProver::new(ipk.clone());
+        for (i, circuit) in circuits.into_iter().enumerate() {
+            let proof = R1CSNark::<Fr>::prove(&ipk, circuit, Some(&mut rng)).unwrap();
+            let prover = if i % 2 == 0 { &mut left } else { &mut right };
+            prover.accumulate(&proof).unwrap();
+        }
+        let (other_instance, other_witness) = right.accumulator();
+        let (acc_instance, acc_witness) = left.accumulator();
+
+        let Ok(((new_instance, new_witness), merge_proof)) = BDASAccumulationScheme::<Fr>::merge(
+            &ipk,
+            (acc_instance, acc_witness),
+            (other_instance, other_witness),
+        ) else {
+            panic!["merge proof not generated"]
+        };
+        assert!(BDASAccumulationScheme::<Fr>::verify_merge(
+            &ivk,
+            &merge_proof,
+            (acc_instance, acc_witness),
+            (other_instance, other_witness),
+            (&new_instance, &new_witness),
+        )
+        .unwrap());
+        assert!(BDASAccumulationScheme::decide(&idk, (&new_instance, &new_witness)).unwrap());
+
+        // the stateful prover merges to the same accumulator
+        let mut prover = left.clone();
+        let ((instance, witness), _) = prover.merge((other_instance, other_witness)).unwrap();
+        assert!(instance.err == new_instance.err && witness.blinded_err == new_witness.blinded_err);
+
+        let mut tampered = merge_proof.clone();
+        tampered.t[0] += Fr::one();
+        assert!(!BDASAccumulationScheme::<Fr>::verify_merge(
+            &ivk,
+            &tampered,
+            (acc_instance, acc_witness),
+            (other_instance, other_witness),
+            (&new_instance, &new_witness),
+        )
+        .unwrap());
+
+        let mut tampered_instance = new_instance.clone();
+        tampered_instance.c += Fr::one();
+        assert!(!BDASAccumulationScheme::<Fr>::verify_merge(
+            &ivk,
+            &merge_proof,
+            (acc_instance, acc_witness),
+            (other_instance, other_witness),
+            (&tampered_instance, &new_witness),
+        )
+        .unwrap());
+
+        // merging in the opposite order needs its own proof
+        assert!(!BDASAccumulationScheme::<Fr>::verify_merge(
+            &ivk,
+            &merge_proof,
+            (other_instance, other_witness),
+            (acc_instance, acc_witness),
+            (&new_instance, &new_witness),
+        )
+        .unwrap());
+    }
+}
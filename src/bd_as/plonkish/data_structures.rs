@@ -0,0 +1,329 @@
+use ark_ff::PrimeField;
+use ark_relations::r1cs::Matrix;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::vec::Vec;
+
+use crate::bd_as::ccs::CCS;
+use crate::bd_as::r1cs_nark::{CircuitDigest, UnsatisfiedReport};
+
+use super::PlonkishError;
+
+/// number of wires (a, b, c) of a row
+pub(crate) const NUM_WIRES: usize = 3;
+
+/// selectors of a row, weighting q_L·a + q_R·b + q_O·c + q_M·a·b + q_C
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+pub struct Selectors<F: PrimeField> {
+    pub(crate) q_l: F,
+    pub(crate) q_r: F,
+    pub(crate) q_o: F,
+    pub(crate) q_m: F,
+    pub(crate) q_c: F,
+}
+
+impl<F: PrimeField> Selectors<F> {
+    pub fn new(q_l: F, q_r: F, q_o: F, q_m: F, q_c: F) -> Self {
+        Selectors {
+            q_l,
+            q_r,
+            q_o,
+            q_m,
+            q_c,
+        }
+    }
+}
+
+/// Plonkish relation over rows of three wires (a, b, c). Every row satisfies the gate
+/// q_L·a + q_R·b + q_O·c + q_M·a·b + q_C = 0, where row i < `num_public_inputs` also
+/// subtracts the public input x_i, and the wire positions form the cycles of
+/// `permutation`, each of which has to hold a single value. Position p is wire p / n of
+/// row p % n for n rows.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlonkishRelation<F: PrimeField> {
+    pub(crate) num_public_inputs: usize,
+    pub(crate) selectors: Vec<Selectors<F>>,
+    pub(crate) permutation: Vec<usize>,
+    /// digest of `to_ccs`, computed once by `new`
+    pub(crate) digest: CircuitDigest,
+}
+
+impl<F: PrimeField> PlonkishRelation<F> {
+    pub fn new(
+        num_public_inputs: usize,
+        selectors: Vec<Selectors<F>>,
+        permutation: Vec<usize>,
+    ) -> Result<Self, PlonkishError> {
+        if num_public_inputs > selectors.len() {
+            return Err(PlonkishError::TooManyPublicInputs {
+                num_public_inputs,
+                num_rows: selectors.len(),
+            });
+        }
+
+        let num_positions = NUM_WIRES * selectors.len();
+        let mut image = vec![false; num_positions];
+        if permutation.len() != num_positions {
+            return Err(PlonkishError::InvalidPermutation);
+        }
+        for position in &permutation {
+            if *position >= num_positions || image[*position] {
+                return Err(PlonkishError::InvalidPermutation);
+            }
+            image[*position] = true;
+        }
+
+        let mut relation = PlonkishRelation {
+            num_public_inputs,
+            selectors,
+            permutation,
+            digest: CircuitDigest([0; 32]),
+        };
+        relation.digest = relation.to_ccs().digest();
+        Ok(relation)
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.selectors.len()
+    }
+
+    /// the digest of `to_ccs`, which keys indexed from the relation carry
+    pub fn digest(&self) -> CircuitDigest {
+        self.digest
+    }
+
+    /// the relation as a CCS of degree two over z = (1, x, a-column, b-column, c-column):
+    /// the gates as G z + (Q_M A z) ∘ (B z) = 0 with the selectors in the matrices, one
+    /// row per gate, followed by one linear row z_p − z_σ(p) per copy along each cycle of
+    /// the permutation. Copy constraints are linear, so they fold like the gates.
+    pub fn to_ccs(&self) -> CCS<F> {
+        let num_rows = self.num_rows();
+        let num_instance_variables = 1 + self.num_public_inputs;
+        let cell = |position: usize| num_instance_variables + position;
+
+        let (mut gates, mut left, mut right): (Matrix<F>, Matrix<F>, Matrix<F>) =
+            (vec![], vec![], vec![]);
+        for (row, selectors) in self.selectors.iter().enumerate() {
+            let (a, b, c) = (cell(row), cell(num_rows + row), cell(2 * num_rows + row));
+            let mut linear: Vec<(F, usize)> = [
+                (selectors.q_c, 0),
+                (selectors.q_l, a),
+                (selectors.q_r, b),
+                (selectors.q_o, c),
+            ]
+            .into_iter()
+            .filter(|(coeff, _)| !coeff.is_zero())
+            .collect();
+            if row < self.num_public_inputs {
+                linear.push((-F::one(), 1 + row));
+            }
+            gates.push(linear);
+            if selectors.q_m.is_zero() {
+                left.push(vec![]);
+                right.push(vec![]);
+            } else {
+                left.push(vec![(selectors.q_m, a)]);
+                right.push(vec![(F::one(), b)]);
+            }
+        }
+
+        // walk every cycle once, equating each position with the next one
+        let mut visited = vec![false; self.permutation.len()];
+        for start in 0..self.permutation.len() {
+            let mut position = start;
+            while !visited[position] {
+                visited[position] = true;
+                let next = self.permutation[position];
+                if !visited[next] {
+                    gates.push(vec![(F::one(), cell(position)), (-F::one(), cell(next))]);
+                    left.push(vec![]);
+                    right.push(vec![]);
+                }
+                position = next;
+            }
+        }
+
+        CCS::new(
+            num_instance_variables,
+            NUM_WIRES * num_rows,
+            vec![gates, left, right],
+            vec![vec![0], vec![1, 2]],
+            vec![F::one(), F::one()],
+        )
+        .expect("the rows refer to wire positions of the relation")
+    }
+
+    /// the CCS assignment (input, witness) of `public_inputs` and the row-wise `wires`
+    pub fn assignment(
+        &self,
+        public_inputs: &[F],
+        wires: &[[F; NUM_WIRES]],
+    ) -> Result<(Vec<F>, Vec<F>), PlonkishError> {
+        if public_inputs.len() != self.num_public_inputs || wires.len() != self.num_rows() {
+            return Err(PlonkishError::AssignmentShape {
+                public_inputs: public_inputs.len(),
+                rows: wires.len(),
+            });
+        }
+
+        let input = ark_std::iter::once(F::one())
+            .chain(public_inputs.iter().copied())
+            .collect();
+        let witness = (0..NUM_WIRES)
+            .flat_map(|wire| wires.iter().map(move |row| row[wire]))
+            .collect();
+        Ok((input, witness))
+    }
+
+    /// checks the gates and the copy constraints, reporting the failing rows of
+    /// `to_ccs`: gates by their row, copies after them
+    pub fn check_assignment(
+        &self,
+        public_inputs: &[F],
+        wires: &[[F; NUM_WIRES]],
+    ) -> Result<Option<UnsatisfiedReport>, PlonkishError> {
+        let (input, witness) = self.assignment(public_inputs, wires)?;
+        Ok(self.to_ccs().check_assignment(&input, &witness))
+    }
+}
+
+/// gate of a `GateList`, reading the variables `wires` as (a, b, c)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gate<F: PrimeField> {
+    pub(crate) selectors: Selectors<F>,
+    pub(crate) wires: [usize; NUM_WIRES],
+}
+
+impl<F: PrimeField> Gate<F> {
+    pub fn custom(selectors: Selectors<F>, wires: [usize; NUM_WIRES]) -> Self {
+        Gate { selectors, wires }
+    }
+
+    /// a + b = c
+    pub fn add(a: usize, b: usize, c: usize) -> Self {
+        let selectors = Selectors::new(F::one(), F::one(), -F::one(), F::zero(), F::zero());
+        Self::custom(selectors, [a, b, c])
+    }
+
+    /// a · b = c
+    pub fn mul(a: usize, b: usize, c: usize) -> Self {
+        let selectors = Selectors::new(F::zero(), F::zero(), -F::one(), F::one(), F::zero());
+        Self::custom(selectors, [a, b, c])
+    }
+
+    /// a = value
+    pub fn constant(a: usize, value: F) -> Self {
+        let selectors = Selectors::new(F::one(), F::zero(), F::zero(), F::zero(), -value);
+        Self::custom(selectors, [a, a, a])
+    }
+}
+
+/// circuit as a list of gates over numbered variables, some of which are public. Each
+/// public input gets a row of its own ahead of the gates, and every variable read at
+/// several wire positions becomes a cycle of the permutation.
+#[derive(Clone, Debug, Default)]
+pub struct GateList<F: PrimeField> {
+    pub(crate) num_variables: usize,
+    pub(crate) public_inputs: Vec<usize>,
+    pub(crate) gates: Vec<Gate<F>>,
+}
+
+impl<F: PrimeField> GateList<F> {
+    pub fn new(num_variables: usize) -> Self {
+        GateList {
+            num_variables,
+            public_inputs: vec![],
+            gates: vec![],
+        }
+    }
+
+    /// makes `variable` the next public input
+    pub fn public_input(mut self, variable: usize) -> Self {
+        self.public_inputs.push(variable);
+        self
+    }
+
+    pub fn gate(mut self, gate: Gate<F>) -> Self {
+        self.gates.push(gate);
+        self
+    }
+
+    /// the variables of every row, public input rows first
+    fn rows(&self) -> Vec<[usize; NUM_WIRES]> {
+        self.public_inputs
+            .iter()
+            .map(|variable| [*variable; NUM_WIRES])
+            .chain(self.gates.iter().map(|gate| gate.wires))
+            .collect()
+    }
+
+    /// checks that the public inputs and the gates only read variables of the gate list
+    fn check_variables(&self) -> Result<(), PlonkishError> {
+        for (public_input, variable) in self.public_inputs.iter().enumerate() {
+            if *variable >= self.num_variables {
+                return Err(PlonkishError::UnknownPublicInput {
+                    public_input,
+                    variable: *variable,
+                });
+            }
+        }
+        for (gate, wires) in self.gates.iter().map(|gate| gate.wires).enumerate() {
+            if let Some(variable) = wires.iter().find(|variable| **variable >= self.num_variables) {
+                return Err(PlonkishError::UnknownVariable {
+                    gate,
+                    variable: *variable,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// the Plonkish relation of the gate list
+    pub fn compile(&self) -> Result<PlonkishRelation<F>, PlonkishError> {
+        self.check_variables()?;
+        let rows = self.rows();
+
+        let public_selectors = Selectors::new(F::one(), F::zero(), F::zero(), F::zero(), F::zero());
+        let selectors = ark_std::iter::repeat_n(public_selectors, self.public_inputs.len())
+            .chain(self.gates.iter().map(|gate| gate.selectors))
+            .collect();
+
+        // link the positions of each variable into a cycle, in position order
+        let num_rows = rows.len();
+        let mut positions = vec![vec![]; self.num_variables];
+        for wire in 0..NUM_WIRES {
+            for (row, variables) in rows.iter().enumerate() {
+                positions[variables[wire]].push(wire * num_rows + row);
+            }
+        }
+        let mut permutation = vec![0; NUM_WIRES * num_rows];
+        for cycle in positions {
+            for (i, position) in cycle.iter().enumerate() {
+                permutation[*position] = cycle[(i + 1) % cycle.len()];
+            }
+        }
+
+        PlonkishRelation::new(self.public_inputs.len(), selectors, permutation)
+    }
+
+    /// the public inputs and the row-wise wires of `compile` for the variable `values`,
+    /// which has to give a value to every variable of the gate list
+    pub fn assignment(
+        &self,
+        values: &[F],
+    ) -> Result<(Vec<F>, Vec<[F; NUM_WIRES]>), PlonkishError> {
+        self.check_variables()?;
+        if values.len() < self.num_variables {
+            return Err(PlonkishError::ValuesLength {
+                expected: self.num_variables,
+                found: values.len(),
+            });
+        }
+        let public_inputs = self.public_inputs.iter().map(|variable| values[*variable]).collect();
+        let wires = self
+            .rows()
+            .into_iter()
+            .map(|variables| variables.map(|variable| values[variable]))
+            .collect();
+        Ok((public_inputs, wires))
+    }
+}
@@ -0,0 +1,67 @@
+use ark_std::fmt;
+
+/// reason a Plonkish relation, a gate list or an assignment to them was rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlonkishError {
+    /// the permutation does not map the wire positions one-to-one onto themselves
+    InvalidPermutation,
+    /// every public input takes a row of its own
+    TooManyPublicInputs { num_public_inputs: usize, num_rows: usize },
+    /// `gate` reads a variable that the gate list does not have
+    UnknownVariable { gate: usize, variable: usize },
+    /// `public_input` is a variable that the gate list does not have
+    UnknownPublicInput { public_input: usize, variable: usize },
+    /// the assignment does not have the shape of the relation
+    AssignmentShape { public_inputs: usize, rows: usize },
+    /// a gate list assignment gives values to fewer variables than the gate list has
+    ValuesLength { expected: usize, found: usize },
+    /// the key was indexed from another relation
+    KeyMismatch,
+}
+
+impl fmt::Display for PlonkishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlonkishError::InvalidPermutation => {
+                write!(f, "the wiring is not a permutation of the wire positions")
+            }
+            PlonkishError::TooManyPublicInputs {
+                num_public_inputs,
+                num_rows,
+            } => write!(
+                f,
+                "{} public inputs do not fit in {} rows",
+                num_public_inputs, num_rows
+            ),
+            PlonkishError::UnknownVariable { gate, variable } => write!(
+                f,
+                "gate {} reads variable {} which does not exist",
+                gate, variable
+            ),
+            PlonkishError::UnknownPublicInput {
+                public_input,
+                variable,
+            } => write!(
+                f,
+                "public input {} is variable {} which does not exist",
+                public_input, variable
+            ),
+            PlonkishError::AssignmentShape {
+                public_inputs,
+                rows,
+            } => write!(
+                f,
+                "an assignment of {} public inputs and {} rows does not fit the relation",
+                public_inputs, rows
+            ),
+            PlonkishError::ValuesLength { expected, found } => write!(
+                f,
+                "expected values for {} variables, found {}",
+                expected, found
+            ),
+            PlonkishError::KeyMismatch => write!(f, "the key does not match the relation"),
+        }
+    }
+}
+
+impl ark_std::error::Error for PlonkishError {}
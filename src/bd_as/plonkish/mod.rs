@@ -0,0 +1,216 @@
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+
+use crate::bd_as::ccs::{CCSAccumulationScheme, CCSNark, CCSProverKey, CCSVerifierKey};
+use crate::bd_as::r1cs_nark::{Proof as NarkProof, PublicParameters};
+
+mod data_structures;
+mod error;
+pub use data_structures::*;
+pub use error::*;
+
+/// NARK for Plonkish relations, proving the CCS of `PlonkishRelation::to_ccs`. The
+/// instance is the public inputs and the Merkle root of the encoded wires.
+pub struct PlonkishNark<F: PrimeField + Absorb> {
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> PlonkishNark<F> {
+    /// generates index prover key and verifier key
+    pub fn index(
+        pp: &PublicParameters,
+        relation: &PlonkishRelation<F>,
+    ) -> (CCSProverKey<F>, CCSVerifierKey<F>) {
+        CCSNark::index(pp, relation.to_ccs())
+    }
+
+    /// generates a proof for the public inputs and the row-wise wires of `relation`.
+    /// Satisfaction is not checked here, see `PlonkishRelation::check_assignment`.
+    pub fn prove(
+        ipk: &CCSProverKey<F>,
+        relation: &PlonkishRelation<F>,
+        public_inputs: &[F],
+        wires: &[[F; NUM_WIRES]],
+    ) -> Result<NarkProof<F>, PlonkishError> {
+        if !ipk.is_compatible(&relation.digest()) {
+            return Err(PlonkishError::KeyMismatch);
+        }
+        let (input, witness) = relation.assignment(public_inputs, wires)?;
+        Ok(CCSNark::prove(ipk, input, witness).expect("the assignment has the shape of the key"))
+    }
+}
+
+/// accumulation scheme for Plonkish relations. Gates and copy constraints are of degree
+/// two, so folds are the ones of `BDASAccumulationScheme`, with the wires encoded by the
+/// same Reed-Solomon code and committed to by the same Merkle trees.
+pub type PlonkishAccumulationScheme<F> = CCSAccumulationScheme<F>;
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::ccs::test::fold_all;
+    use crate::bd_as::r1cs_nark::{CodeParameters, R1CSNark};
+    use crate::bd_as::DecideError;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_ff::{One, Zero};
+
+    /// x³ + x + 5 = out over the variables (x, x², x³, x³ + x, out), out public
+    fn cubic_gates() -> GateList<Fr> {
+        let plus_five =
+            Selectors::new(Fr::one(), Fr::zero(), -Fr::one(), Fr::zero(), Fr::from(5u64));
+        GateList::new(5)
+            .public_input(4)
+            .gate(Gate::mul(0, 0, 1))
+            .gate(Gate::mul(1, 0, 2))
+            .gate(Gate::add(2, 0, 3))
+            .gate(Gate::custom(plus_five, [3, 3, 4]))
+    }
+
+    fn cubic_values(x: u64) -> Vec<Fr> {
+        let x = Fr::from(x);
+        let values = vec![x, x * x, x * x * x, x * x * x + x];
+        let out = values[3] + Fr::from(5u64);
+        [values, vec![out]].concat()
+    }
+
+    #[test]
+    pub fn test_plonkish_gate_list() {
+        let pp = R1CSNark::<Fr>::setup();
+        let gates = cubic_gates();
+        let relation = gates.compile().unwrap();
+        assert_eq!(relation.num_rows(), 5);
+        assert_eq!(relation.to_ccs().degree(), 2);
+
+        let (ipk, _) = PlonkishNark::index(&pp, &relation);
+        let ipk = ipk.with_code_params(CodeParameters::with_num_queries(4));
+        let idk = ipk.decider_key();
+        assert_eq!(ipk.digest(), relation.digest());
+        let proofs: Vec<_> = (2..5u64)
+            .map(|x| {
+                let (public_inputs, wires) = gates.assignment(&cubic_values(x)).unwrap();
+                assert_eq!(relation.check_assignment(&public_inputs, &wires), Ok(None));
+                PlonkishNark::prove(&ipk, &relation, &public_inputs, &wires).unwrap()
+            })
+            .collect();
+        assert_eq!(proofs[1].instance.input, vec![Fr::one(), Fr::from(35u64)]);
+        for proof in &proofs {
+            assert_eq!(CCSNark::verify(&idk, &proof.instance.input, proof), Ok(()));
+        }
+
+        let (instance, witness) = fold_all(&ipk, &proofs);
+        assert_eq!(
+            PlonkishAccumulationScheme::decide_with_report(&idk, (&instance, &witness)),
+            Ok(())
+        );
+
+        // a wrong public output breaks the last gate
+        let (mut public_inputs, wires) = gates.assignment(&cubic_values(3)).unwrap();
+        public_inputs[0] += Fr::one();
+        let Ok(Some(report)) = relation.check_assignment(&public_inputs, &wires) else {
+            panic!["wrong output passed the check"]
+        };
+        assert_eq!(report.rows, vec![0]);
+    }
+
+    #[test]
+    pub fn test_plonkish_copy_constraints() {
+        let pp = R1CSNark::<Fr>::setup();
+        let gates = cubic_gates();
+        let relation = gates.compile().unwrap();
+        let (ipk, _) = PlonkishNark::index(&pp, &relation);
+        let ipk = ipk.with_code_params(CodeParameters::with_num_queries(4));
+        let idk = ipk.decider_key();
+
+        // the b wire of the public input row is in no gate, only in the cycle of out
+        let (public_inputs, mut wires) = gates.assignment(&cubic_values(3)).unwrap();
+        wires[0][1] += Fr::one();
+        let Ok(Some(report)) = relation.check_assignment(&public_inputs, &wires) else {
+            panic!["broken copy passed the check"]
+        };
+        assert_eq!(report.num_unsatisfied, 2);
+        assert!(report.rows.iter().all(|row| *row >= relation.num_rows()));
+
+        let good = gates.assignment(&cubic_values(2)).unwrap();
+        let proofs = [
+            PlonkishNark::prove(&ipk, &relation, &good.0, &good.1).unwrap(),
+            PlonkishNark::prove(&ipk, &relation, &public_inputs, &wires).unwrap(),
+        ];
+        let (instance, witness) = fold_all(&ipk, &proofs);
+        let Err(DecideError::UnsatisfiedConstraints(decided)) =
+            PlonkishAccumulationScheme::decide_with_report(&idk, (&instance, &witness))
+        else {
+            panic!["accumulated a broken copy"]
+        };
+        assert_eq!(decided.rows, report.rows);
+    }
+
+    #[test]
+    pub fn test_plonkish_rejects_malformed() {
+        let pp = R1CSNark::<Fr>::setup();
+        let row = Selectors::new(Fr::one(), Fr::zero(), Fr::zero(), Fr::zero(), Fr::zero());
+
+        assert_eq!(
+            PlonkishRelation::new(2, vec![row], vec![0, 1, 2]).err(),
+            Some(PlonkishError::TooManyPublicInputs {
+                num_public_inputs: 2,
+                num_rows: 1
+            })
+        );
+        assert_eq!(
+            PlonkishRelation::new(0, vec![row], vec![0, 0, 2]).err(),
+            Some(PlonkishError::InvalidPermutation)
+        );
+        assert_eq!(
+            PlonkishRelation::new(0, vec![row], vec![1, 2]).err(),
+            Some(PlonkishError::InvalidPermutation)
+        );
+        assert_eq!(
+            cubic_gates().gate(Gate::add(0, 5, 1)).compile().err(),
+            Some(PlonkishError::UnknownVariable {
+                gate: 4,
+                variable: 5
+            })
+        );
+        assert_eq!(
+            cubic_gates().public_input(5).compile().err(),
+            Some(PlonkishError::UnknownPublicInput {
+                public_input: 1,
+                variable: 5
+            })
+        );
+
+        let gates = cubic_gates();
+        let relation = gates.compile().unwrap();
+        let (ipk, _) = PlonkishNark::index(&pp, &relation);
+        let (public_inputs, wires) = gates.assignment(&cubic_values(2)).unwrap();
+        assert_eq!(
+            PlonkishNark::prove(&ipk, &relation, &[], &wires).err(),
+            Some(PlonkishError::AssignmentShape {
+                public_inputs: 0,
+                rows: 5
+            })
+        );
+
+        assert_eq!(
+            gates.assignment(&cubic_values(2)[..3]).err(),
+            Some(PlonkishError::ValuesLength {
+                expected: 5,
+                found: 3
+            })
+        );
+        assert_eq!(
+            cubic_gates().public_input(5).assignment(&cubic_values(2)).err(),
+            Some(PlonkishError::UnknownPublicInput {
+                public_input: 1,
+                variable: 5
+            })
+        );
+
+        let other = GateList::new(1).gate(Gate::constant(0, Fr::one())).compile().unwrap();
+        assert_eq!(
+            PlonkishNark::prove(&ipk, &other, &public_inputs, &wires).err(),
+            Some(PlonkishError::KeyMismatch)
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::SynthesisError;
+
+use crate::bd_as::r1cs_nark::{self, IndexProverKey, Instance, Witness};
+use crate::bd_as::{
+    commit_accumulator, AccumulatorInstance, AccumulatorWitness, BDASAccumulationScheme,
+    CommittedVector, MergeProof, Proof, ProverError,
+};
+
+/// Stateful accumulation prover. It keeps the codewords and Merkle trees of the current
+/// accumulator between steps, so every fold only commits to the cross term and the new
+/// accumulator instead of re-encoding the old one.
+#[derive(Clone)]
+pub struct AccumulatorProver<F: PrimeField + Absorb> {
+    prover_key: IndexProverKey<F>,
+    instance: AccumulatorInstance<F>,
+    witness: AccumulatorWitness<F>,
+    w_commitment: CommittedVector<F>,
+    err_commitment: CommittedVector<F>,
+    check_inputs: bool,
+}
+
+impl<F: PrimeField + Absorb> AccumulatorProver<F> {
+    /// starts from the zero accumulator of the circuit described by `prover_key`
+    pub fn new(prover_key: IndexProverKey<F>) -> Self {
+        let instance = AccumulatorInstance::zero(prover_key.clone());
+        let witness = AccumulatorWitness::zero(prover_key.clone());
+        Self::from_accumulator(prover_key, (instance, witness))
+            .expect("the zero accumulator is committed to by its witness")
+    }
+
+    /// resumes from an existing accumulator, which is committed to once here. Fails with
+    /// `SynthesisError::Unsatisfiable` if the roots of its witness are not the ones of w
+    /// and err.
+    pub fn from_accumulator(
+        prover_key: IndexProverKey<F>,
+        accumulator: (AccumulatorInstance<F>, AccumulatorWitness<F>),
+    ) -> Result<Self, SynthesisError> {
+        let (instance, witness) = accumulator;
+        let (w_commitment, err_commitment) = commit_accumulator(
+            &prover_key.code_params,
+            &prover_key.hash_params,
+            (&instance, &witness),
+        )?;
+
+        Ok(AccumulatorProver {
+            prover_key,
+            instance,
+            witness,
+            w_commitment,
+            err_commitment,
+            check_inputs: false,
+        })
+    }
+
+    /// when enabled, every input is checked against the circuit before it is folded and
+    /// `prove` fails like `BDASAccumulationScheme::check_input`, with
+    /// `ProverError::Unsatisfied` reporting the failing rows if it does not satisfy it.
+    pub fn check_inputs(mut self, enabled: bool) -> Self {
+        self.check_inputs = enabled;
+        self
+    }
+
+    /// the current accumulator
+    pub fn accumulator(&self) -> (&AccumulatorInstance<F>, &AccumulatorWitness<F>) {
+        (&self.instance, &self.witness)
+    }
+
+    pub fn prover_key(&self) -> &IndexProverKey<F> {
+        &self.prover_key
+    }
+
+    /// folds `input` into the current accumulator and returns the new accumulator along
+    /// with the accumulation proof. If the prover already holds the commitment to the
+    /// input witness (e.g. from the NARK prover) it can be passed as
+    /// `input_commitment` to skip re-encoding it.
+    #[allow(clippy::type_complexity)]
+    pub fn prove(
+        &mut self,
+        input: (&Instance<F>, &Witness<F>),
+        input_commitment: Option<&CommittedVector<F>>,
+    ) -> Result<((AccumulatorInstance<F>, AccumulatorWitness<F>), Proof<F>), ProverError> {
+        if self.check_inputs {
+            BDASAccumulationScheme::check_input(&self.prover_key, input)?;
+        }
+
+        let ((instance, witness), proof, w_commitment, err_commitment) =
+            BDASAccumulationScheme::fold(
+                &self.prover_key,
+                (&self.instance, &self.witness),
+                input,
+                &self.w_commitment,
+                &self.err_commitment,
+                input_commitment,
+            )?;
+
+        self.instance = instance.clone();
+        self.witness = witness.clone();
+        self.w_commitment = w_commitment;
+        self.err_commitment = err_commitment;
+
+        Ok(((instance, witness), proof))
+    }
+
+    /// folds a NARK proof into the current accumulator, opening the witness through
+    /// the codeword and tree kept in the proof when they are present
+    #[allow(clippy::type_complexity)]
+    pub fn accumulate(
+        &mut self,
+        proof: &r1cs_nark::Proof<F>,
+    ) -> Result<((AccumulatorInstance<F>, AccumulatorWitness<F>), Proof<F>), ProverError> {
+        self.prove((&proof.instance, &proof.witness), proof.commitment.as_ref())
+    }
+    /// merges the accumulator `other` into the current accumulator, see
+    /// `BDASAccumulationScheme::merge`
+    #[allow(clippy::type_complexity)]
+    pub fn merge(
+        &mut self,
+        other: (&AccumulatorInstance<F>, &AccumulatorWitness<F>),
+    ) -> Result<((AccumulatorInstance<F>, AccumulatorWitness<F>), MergeProof<F>), SynthesisError>
+    {
+        let ((instance, witness), proof, w_commitment, err_commitment) =
+            BDASAccumulationScheme::merge_committed(
+                &self.prover_key,
+                (&self.instance, &self.witness),
+                other,
+                (&self.w_commitment, &self.err_commitment),
+            )?;
+
+        self.instance = instance.clone();
+        self.witness = witness.clone();
+        self.w_commitment = w_commitment;
+        self.err_commitment = err_commitment;
+
+        Ok(((instance, witness), proof))
+    }
+}
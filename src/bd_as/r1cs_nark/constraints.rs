@@ -0,0 +1,523 @@
+use ark_crypto_primitives::crh::poseidon::constraints::{
+    CRHGadget, CRHParametersVar, TwoToOneCRHGadget,
+};
+use ark_crypto_primitives::crh::{CRHSchemeGadget, TwoToOneCRHSchemeGadget};
+use ark_crypto_primitives::merkle_tree::constraints::ConfigGadget;
+use ark_crypto_primitives::merkle_tree::IdentityDigestConverter;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::{AllocatedFp, FpVar};
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, Namespace, SynthesisError,
+    Variable,
+};
+use ark_std::borrow::Borrow;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+use super::poseidon2::constraints::{
+    Poseidon2CRHGadget, Poseidon2ParametersVar, Poseidon2TwoToOneCRHGadget,
+};
+use super::{
+    CodeParameters, HashCRH, HashParameters, HashTwoToOneCRH, IndexDeciderKey, Instance,
+    MerkleHashConfig, Proof,
+};
+
+/// constraint counterpart of `MerkleHashConfig`, for use with `PathVar`
+pub struct MerkleHashConfigVar<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> ConfigGadget<MerkleHashConfig<F>, F> for MerkleHashConfigVar<F> {
+    type Leaf = [FpVar<F>];
+    type LeafDigest = FpVar<F>;
+    type LeafInnerConverter = IdentityDigestConverter<FpVar<F>>;
+    type InnerDigest = FpVar<F>;
+    type LeafHash = HashCRHGadget<F>;
+    type TwoToOneHash = HashTwoToOneCRHGadget<F>;
+}
+
+/// hash parameters baked into the circuit as constants, Poseidon or Poseidon2
+#[derive(Clone)]
+pub enum HashParametersVar<F: PrimeField + Absorb> {
+    Poseidon(CRHParametersVar<F>),
+    Poseidon2(Poseidon2ParametersVar<F>),
+}
+
+impl<F: PrimeField + Absorb> AllocVar<HashParameters<F>, F> for HashParametersVar<F> {
+    fn new_variable<T: Borrow<HashParameters<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let hash_params = f()?;
+        match hash_params.borrow() {
+            HashParameters::Poseidon(parameters) => {
+                CRHParametersVar::new_variable(cs, || Ok(parameters), mode)
+                    .map(HashParametersVar::Poseidon)
+            }
+            HashParameters::Poseidon2(parameters) => {
+                Poseidon2ParametersVar::new_variable(cs, || Ok(parameters), mode)
+                    .map(HashParametersVar::Poseidon2)
+            }
+        }
+    }
+}
+
+/// constraint counterpart of `HashCRH`
+pub struct HashCRHGadget<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHSchemeGadget<HashCRH<F>, F> for HashCRHGadget<F> {
+    type InputVar = [FpVar<F>];
+    type OutputVar = FpVar<F>;
+    type ParametersVar = HashParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        match parameters {
+            HashParametersVar::Poseidon(parameters) => CRHGadget::evaluate(parameters, input),
+            HashParametersVar::Poseidon2(parameters) => {
+                Poseidon2CRHGadget::evaluate(parameters, input)
+            }
+        }
+    }
+}
+
+/// constraint counterpart of `HashTwoToOneCRH`
+pub struct HashTwoToOneCRHGadget<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHSchemeGadget<HashTwoToOneCRH<F>, F>
+    for HashTwoToOneCRHGadget<F>
+{
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = HashParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::InputVar,
+        right_input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::OutputVar,
+        right_input: &Self::OutputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        match parameters {
+            HashParametersVar::Poseidon(parameters) => {
+                TwoToOneCRHGadget::compress(parameters, left_input, right_input)
+            }
+            HashParametersVar::Poseidon2(parameters) => {
+                Poseidon2TwoToOneCRHGadget::compress(parameters, left_input, right_input)
+            }
+        }
+    }
+}
+
+/// in-circuit NARK instance: the input and the Merkle root of the encoded witness. The
+/// circuit digest is not allocated, it is fixed by the key the gadgets are built with.
+#[derive(Clone)]
+pub struct InstanceVar<F: PrimeField> {
+    pub(crate) input: Vec<FpVar<F>>,
+    pub(crate) blinded_witness: FpVar<F>,
+}
+
+impl<F: PrimeField> InstanceVar<F> {
+    /// the input variables, starting with the constant one
+    pub fn input(&self) -> &[FpVar<F>] {
+        &self.input
+    }
+
+    /// the Merkle root of the encoded witness
+    pub fn blinded_witness(&self) -> &FpVar<F> {
+        &self.blinded_witness
+    }
+}
+
+impl<F: PrimeField + Absorb> AllocVar<Instance<F>, F> for InstanceVar<F> {
+    fn new_variable<T: Borrow<Instance<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|instance| {
+            let instance = instance.borrow();
+            let input = Vec::new_variable(
+                ark_relations::ns!(cs, "input"),
+                || Ok(instance.input.clone()),
+                mode,
+            )?;
+            let blinded_witness = FpVar::new_variable(
+                ark_relations::ns!(cs, "blinded_witness"),
+                || Ok(instance.blinded_witness),
+                mode,
+            )?;
+            Ok(InstanceVar {
+                input,
+                blinded_witness,
+            })
+        })
+    }
+}
+
+/// R1CS version of `R1CSNark::verify`
+pub struct R1CSNarkVerifierGadget<F: PrimeField + Absorb> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> R1CSNarkVerifierGadget<F> {
+    /// returns a boolean that is true iff `witness` satisfies the circuit of `decider_key`
+    /// for the input of `instance` and its encoding is committed to by
+    /// `instance.blinded_witness`. The constraint matrices are constants of the circuit,
+    /// every row costs a single multiplication.
+    pub fn verify(
+        decider_key: &IndexDeciderKey<F>,
+        instance: &InstanceVar<F>,
+        witness: &[FpVar<F>],
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let index_info = &decider_key.index_info;
+        if instance.input.len() != index_info.num_instance_variables
+            || witness.len() != index_info.num_witness_variables()
+        {
+            return Ok(Boolean::FALSE);
+        }
+
+        let hash_params = HashParametersVar::new_constant(
+            ark_relations::ns!(instance.blinded_witness.cs(), "hash_params"),
+            &decider_key.hash_params,
+        )?;
+        let root = commitment_root(&decider_key.code_params, &hash_params, witness)?;
+        let mut checks = vec![root.is_eq(&instance.blinded_witness)?];
+
+        let z: Vec<&FpVar<F>> = instance.input.iter().chain(witness).collect();
+        let rows = decider_key.a.iter().zip(&decider_key.b).zip(&decider_key.c);
+        for ((a_row, b_row), c_row) in rows {
+            let az = row_times_vector(a_row, &z)?;
+            let bz = row_times_vector(b_row, &z)?;
+            let cz = row_times_vector(c_row, &z)?;
+            checks.push((az * bz).is_eq(&cz)?);
+        }
+
+        Boolean::kary_and(&checks)
+    }
+}
+
+/// circuit verifying a NARK proof. The instance is the public input, so the verifier
+/// of the outer circuit supplies the input and the witness commitment.
+pub struct R1CSNarkVerifierCircuit<F: PrimeField + Absorb> {
+    decider_key: IndexDeciderKey<F>,
+    proof: Proof<F>,
+}
+
+impl<F: PrimeField + Absorb> R1CSNarkVerifierCircuit<F> {
+    pub fn new(decider_key: IndexDeciderKey<F>, proof: Proof<F>) -> Self {
+        R1CSNarkVerifierCircuit { decider_key, proof }
+    }
+}
+
+impl<F: PrimeField + Absorb> ConstraintSynthesizer<F> for R1CSNarkVerifierCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let instance = InstanceVar::new_input(ark_relations::ns!(cs, "instance"), || {
+            Ok(&self.proof.instance)
+        })?;
+        let witness = Vec::new_witness(ark_relations::ns!(cs, "witness"), || {
+            Ok(self.proof.witness.witness.clone())
+        })?;
+        R1CSNarkVerifierGadget::verify(&self.decider_key, &instance, &witness)?
+            .enforce_equal(&Boolean::TRUE)
+    }
+}
+
+/// in-circuit Merkle root of the Reed-Solomon encoding of `values`, as computed by
+/// `CommittedVector::commit`. The evaluation points are constants, so every position of
+/// the codeword is a linear combination of `values`.
+pub(crate) fn commitment_root<F: PrimeField + Absorb>(
+    code_params: &CodeParameters,
+    hash_params: &HashParametersVar<F>,
+    values: &[FpVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    let code_length = code_params.code_length;
+    let mut leaves = Vec::with_capacity(code_length.next_power_of_two());
+    let mut point = F::GENERATOR;
+    for _ in 0..code_length {
+        let mut power = F::one();
+        let mut terms = Vec::with_capacity(values.len());
+        for value in values {
+            terms.push((power, value));
+            power *= point;
+        }
+        leaves.push(linear_combination(terms)?);
+        point *= F::GENERATOR;
+    }
+    leaves.resize(code_length.next_power_of_two(), FpVar::zero());
+
+    let mut level = leaves
+        .iter()
+        .map(|leaf| HashCRHGadget::evaluate(hash_params, ark_std::slice::from_ref(leaf)))
+        .collect::<Result<Vec<_>, _>>()?;
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| HashTwoToOneCRHGadget::evaluate(hash_params, &pair[0], &pair[1]))
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    Ok(level.remove(0))
+}
+
+/// row · z for a sparse matrix row
+fn row_times_vector<F: PrimeField>(
+    row: &[(F, usize)],
+    z: &[&FpVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    linear_combination(row.iter().map(|(coeff, column)| (*coeff, z[*column])))
+}
+
+/// Σ coeff · var as a single linear combination, without the intermediate variables
+/// that chaining `FpVar` additions creates
+fn linear_combination<'a, F: PrimeField>(
+    terms: impl IntoIterator<Item = (F, &'a FpVar<F>)>,
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut lc = LinearCombination::zero();
+    let mut value = Some(F::zero());
+    let mut cs = ConstraintSystemRef::None;
+    for (coeff, var) in terms {
+        match var {
+            FpVar::Constant(constant) => {
+                lc += (coeff * constant, Variable::One);
+                value = value.map(|value| value + coeff * constant);
+            }
+            FpVar::Var(allocated) => {
+                lc += (coeff, allocated.variable);
+                value = value.zip(allocated.value().ok()).map(|(value, var)| value + coeff * var);
+                cs = cs.or(allocated.cs.clone());
+            }
+        }
+    }
+
+    if cs.is_none() {
+        return Ok(FpVar::Constant(value.unwrap_or_default()));
+    }
+    let variable = cs.new_lc(lc)?;
+    Ok(FpVar::Var(AllocatedFp::new(value, variable, cs)))
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::r1cs_nark::{
+        poseidon2::poseidon2_parameters, poseidon_parameters, HashParameters, IndexProverKey,
+        R1CSNark, Witness,
+    };
+    use crate::bd_as::CommittedVector;
+    use ark_crypto_primitives::merkle_tree::constraints::PathVar;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_ff::{One, Zero};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    pub fn test_merkle_path_gadget() {
+        for hash_params in [
+            HashParameters::Poseidon(poseidon_parameters::<Fr>()),
+            HashParameters::Poseidon2(poseidon2_parameters()),
+        ] {
+            let values: Vec<Fr> = (0..8u64).map(Fr::from).collect();
+            let commitment =
+                CommittedVector::commit(&values, &CodeParameters::default(), &hash_params);
+            let path = commitment.tree.generate_proof(5).unwrap();
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let params_var = HashParametersVar::new_constant(cs.clone(), &hash_params).unwrap();
+            let root_var = FpVar::new_input(cs.clone(), || Ok(commitment.root())).unwrap();
+            let leaf_var =
+                vec![FpVar::new_witness(cs.clone(), || Ok(commitment.code[5])).unwrap()];
+            let path_var =
+                PathVar::<MerkleHashConfig<Fr>, Fr, MerkleHashConfigVar<Fr>>::new_witness(
+                    cs.clone(),
+                    || Ok(path),
+                )
+                .unwrap();
+
+            let is_member = path_var
+                .verify_membership(&params_var, &params_var, &root_var, &leaf_var)
+                .unwrap();
+            assert!(is_member.value().unwrap());
+            is_member.enforce_equal(&Boolean::TRUE).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    /// x * x = x2, x2 * x = y over (1, y, x, x2)
+    fn cube_proof(prover_key: &IndexProverKey<Fr>, x: u64) -> Proof<Fr> {
+        let witness = vec![Fr::from(x), Fr::from(x * x)];
+        Proof {
+            instance: Instance {
+                circuit_digest: prover_key.digest(),
+                input: vec![Fr::one(), Fr::from(x * x * x)],
+                blinded_witness: CommittedVector::commit(
+                    &witness,
+                    &prover_key.code_params,
+                    &prover_key.hash_params,
+                )
+                .root(),
+            },
+            witness: Witness { witness },
+            commitment: None,
+        }
+    }
+
+    #[test]
+    pub fn test_nark_gadget_matches_native_verifier() {
+        let a = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 3)]];
+        let b = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 2)]];
+        let c = vec![vec![(Fr::one(), 3)], vec![(Fr::one(), 1)]];
+        let ipk = IndexProverKey::from_matrices(2, 2, a, b, c).unwrap();
+        let idk = ipk.decider_key();
+        let proof = cube_proof(&ipk, 3);
+
+        let mut cases = vec![(proof.clone(), true)];
+
+        // a wrong witness, with and without a matching commitment
+        let mut tampered = proof.clone();
+        tampered.witness.witness[1] += Fr::one();
+        cases.push((tampered.clone(), false));
+        tampered.instance.blinded_witness = CommittedVector::commit(
+            &tampered.witness.witness,
+            &ipk.code_params,
+            &ipk.hash_params,
+        )
+        .root();
+        cases.push((tampered, false));
+
+        let mut tampered = proof.clone();
+        tampered.instance.input[1] += Fr::one();
+        cases.push((tampered, false));
+
+        let mut tampered = proof.clone();
+        tampered.instance.blinded_witness = cube_proof(&ipk, 4).instance.blinded_witness;
+        cases.push((tampered, false));
+
+        let mut tampered = proof;
+        tampered.witness.witness.push(Fr::one());
+        cases.push((tampered, false));
+
+        for (case, (proof, expected)) in cases.into_iter().enumerate() {
+            let native = R1CSNark::<Fr>::verify(&idk, &proof.instance.input, &proof).is_ok();
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let instance = InstanceVar::new_input(cs.clone(), || Ok(&proof.instance)).unwrap();
+            let witness =
+                Vec::new_witness(cs.clone(), || Ok(proof.witness.witness.clone())).unwrap();
+            let in_circuit = R1CSNarkVerifierGadget::verify(&idk, &instance, &witness).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+
+            assert_eq!(native, expected, "case {case}");
+            assert_eq!(in_circuit.value().unwrap(), expected, "case {case}");
+        }
+    }
+
+    #[test]
+    pub fn test_nark_gadget_with_long_code() {
+        // more witness variables than the default code has positions, so the key commits
+        // to a longer codeword and the gadget has to rebuild the same tree
+        let rows = crate::bd_as::CODE_LENGTH + 88;
+        let a: Vec<_> = (0..rows).map(|i| vec![(Fr::one(), 1 + i)]).collect();
+        let b = vec![vec![(Fr::one(), 0)]; rows];
+        let ipk = IndexProverKey::from_matrices(1, rows, a.clone(), b, a).unwrap();
+        let idk = ipk.decider_key();
+        assert!(ipk.code_params.code_length >= 2 * rows);
+
+        let witness: Vec<Fr> = (0..rows as u64).map(Fr::from).collect();
+        let proof = Proof {
+            instance: Instance {
+                circuit_digest: ipk.digest(),
+                input: vec![Fr::one()],
+                blinded_witness: CommittedVector::commit(
+                    &witness,
+                    &ipk.code_params,
+                    &ipk.hash_params,
+                )
+                .root(),
+            },
+            witness: Witness { witness },
+            commitment: None,
+        };
+        let mut tampered = proof.clone();
+        tampered.witness.witness[rows - 1] += Fr::one();
+
+        for (proof, expected) in [(proof, true), (tampered, false)] {
+            let native = R1CSNark::<Fr>::verify(&idk, &proof.instance.input, &proof).is_ok();
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let instance = InstanceVar::new_input(cs.clone(), || Ok(&proof.instance)).unwrap();
+            let witness =
+                Vec::new_witness(cs.clone(), || Ok(proof.witness.witness.clone())).unwrap();
+            let in_circuit = R1CSNarkVerifierGadget::verify(&idk, &instance, &witness).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+
+            assert_eq!(native, expected);
+            assert_eq!(in_circuit.value().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    pub fn test_nark_verifier_circuit_size() {
+        let a = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 3)]];
+        let b = vec![vec![(Fr::one(), 2)], vec![(Fr::one(), 2)]];
+        let c = vec![vec![(Fr::one(), 3)], vec![(Fr::one(), 1)]];
+        let ipk = IndexProverKey::from_matrices(2, 2, a, b, c).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        R1CSNarkVerifierCircuit::new(ipk.decider_key(), cube_proof(&ipk, 3))
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        // the matrices are constants, only the instance is public and the witness is the
+        // only other allocated input of the gadget
+        assert_eq!(cs.num_instance_variables(), 1 + 2 + 1);
+
+        // the codeword positions and matrix rows are single linear combinations, so a
+        // wider circuit only adds the product and comparison of each new row
+        let wide_rows = 64;
+        let a: Vec<_> = (0..wide_rows).map(|i| vec![(Fr::one(), 2 + i)]).collect();
+        let c: Vec<_> = (0..wide_rows).map(|i| vec![(Fr::one(), 2 + i)]).collect();
+        let b = vec![vec![(Fr::one(), 0)]; wide_rows];
+        let wide_ipk = IndexProverKey::from_matrices(2, wide_rows, a, b, c).unwrap();
+        let witness = vec![Fr::from(7u64); wide_rows];
+        let proof = Proof {
+            instance: Instance {
+                circuit_digest: wide_ipk.digest(),
+                input: vec![Fr::one(), Fr::zero()],
+                blinded_witness: CommittedVector::commit(
+                    &witness,
+                    &wide_ipk.code_params,
+                    &wide_ipk.hash_params,
+                )
+                .root(),
+            },
+            witness: Witness { witness },
+            commitment: None,
+        };
+        let wide_cs = ConstraintSystem::<Fr>::new_ref();
+        R1CSNarkVerifierCircuit::new(wide_ipk.decider_key(), proof)
+            .generate_constraints(wide_cs.clone())
+            .unwrap();
+        assert!(wide_cs.is_satisfied().unwrap());
+        assert!(wide_cs.num_constraints() - cs.num_constraints() <= 5 * (wide_rows - 2));
+    }
+}
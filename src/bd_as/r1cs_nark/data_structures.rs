@@ -1,40 +1,290 @@
 use std::marker::PhantomData;
-use std::usize;
 
 use ark_crypto_primitives::crh::poseidon::{TwoToOneCRH, CRH};
+use ark_crypto_primitives::crh::sha256::Sha256;
 use ark_crypto_primitives::crh::{CRHScheme, TwoToOneCRHScheme};
 use ark_crypto_primitives::merkle_tree::{Config, IdentityDigestConverter};
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
 use ark_crypto_primitives::sponge::Absorb;
+use ark_crypto_primitives::Error;
 use ark_ff::PrimeField;
 use ark_relations::r1cs::Matrix;
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use ark_std::borrow::Borrow;
+use ark_std::rand::Rng;
 use ark_std::vec::Vec;
 
+use crate::bd_as::{CommittedVector, CODE_LENGTH, INVERSE_RATE, NUM_QUERIES};
+
+use super::poseidon2::{Poseidon2CRH, Poseidon2Config, Poseidon2TwoToOneCRH};
+use super::{default_poseidon_parameters, IndexError};
+
 pub struct MerkleHashConfig<F: PrimeField> {
     _field_data: PhantomData<F>,
 }
 
-// type CompressH<F> = TwoToOneCRH<F>;
-
 impl<F: PrimeField + Absorb> Config for MerkleHashConfig<F> {
     type Leaf = [F];
-    type LeafHash = CRH<F>;
-    type LeafDigest = <CRH<F> as CRHScheme>::Output;
-    type InnerDigest = <TwoToOneCRH<F> as TwoToOneCRHScheme>::Output;
+    type LeafHash = HashCRH<F>;
+    type LeafDigest = F;
+    type InnerDigest = F;
     type LeafInnerDigestConverter = IdentityDigestConverter<Self::LeafDigest>;
-    type TwoToOneHash = TwoToOneCRH<F>;
+    type TwoToOneHash = HashTwoToOneCRH<F>;
+}
+
+/// leaf hash of `MerkleHashConfig`, Poseidon or Poseidon2 as selected by the parameters
+pub struct HashCRH<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHScheme for HashCRH<F> {
+    type Input = [F];
+    type Output = F;
+    type Parameters = HashParameters<F>;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        CRH::setup(rng).map(HashParameters::Poseidon)
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        match parameters {
+            HashParameters::Poseidon(parameters) => CRH::evaluate(parameters, input),
+            HashParameters::Poseidon2(parameters) => Poseidon2CRH::evaluate(parameters, input),
+        }
+    }
+}
+
+/// two-to-one hash of `MerkleHashConfig`, selected like `HashCRH`
+pub struct HashTwoToOneCRH<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHScheme for HashTwoToOneCRH<F> {
+    type Input = F;
+    type Output = F;
+    type Parameters = HashParameters<F>;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        TwoToOneCRH::setup(rng).map(HashParameters::Poseidon)
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        match parameters {
+            HashParameters::Poseidon(parameters) => {
+                TwoToOneCRH::compress(parameters, left_input, right_input)
+            }
+            HashParameters::Poseidon2(parameters) => {
+                Poseidon2TwoToOneCRH::compress(parameters, left_input, right_input)
+            }
+        }
+    }
+}
+
+/// parameters of the hash of the Merkle commitments and of the folding challenges. They
+/// are built once by `R1CSNark::index` and carried in the keys so that proving,
+/// verifying and deciding never rebuild them. With Poseidon the challenges are derived
+/// with Blake2s, with Poseidon2 they are derived with Poseidon2 as well.
+#[derive(Clone, Debug)]
+pub enum HashParameters<F: PrimeField> {
+    Poseidon(PoseidonConfig<F>),
+    Poseidon2(Poseidon2Config<F>),
+}
+
+impl<F: PrimeField> Default for HashParameters<F> {
+    /// Poseidon with parameters generated for `F`, see `default_poseidon_parameters`
+    fn default() -> Self {
+        HashParameters::Poseidon(default_poseidon_parameters())
+    }
+}
+
+impl<F: PrimeField> CanonicalSerialize for HashParameters<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            HashParameters::Poseidon(parameters) => {
+                0u8.serialize_with_mode(&mut writer, compress)?;
+                parameters.full_rounds.serialize_with_mode(&mut writer, compress)?;
+                parameters.partial_rounds.serialize_with_mode(&mut writer, compress)?;
+                parameters.alpha.serialize_with_mode(&mut writer, compress)?;
+                parameters.ark.serialize_with_mode(&mut writer, compress)?;
+                parameters.mds.serialize_with_mode(&mut writer, compress)?;
+                parameters.rate.serialize_with_mode(&mut writer, compress)?;
+                parameters.capacity.serialize_with_mode(&mut writer, compress)
+            }
+            HashParameters::Poseidon2(parameters) => {
+                1u8.serialize_with_mode(&mut writer, compress)?;
+                parameters.serialize_with_mode(&mut writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        match self {
+            HashParameters::Poseidon(parameters) => {
+                0u8.serialized_size(compress)
+                    + parameters.full_rounds.serialized_size(compress)
+                    + parameters.partial_rounds.serialized_size(compress)
+                    + parameters.alpha.serialized_size(compress)
+                    + parameters.ark.serialized_size(compress)
+                    + parameters.mds.serialized_size(compress)
+                    + parameters.rate.serialized_size(compress)
+                    + parameters.capacity.serialized_size(compress)
+            }
+            HashParameters::Poseidon2(parameters) => {
+                1u8.serialized_size(compress) + parameters.serialized_size(compress)
+            }
+        }
+    }
+}
+
+impl<F: PrimeField> Valid for HashParameters<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            HashParameters::Poseidon(parameters) => {
+                let width = parameters.rate + parameters.capacity;
+                if parameters.ark.len() != parameters.full_rounds + parameters.partial_rounds
+                    || parameters.ark.iter().any(|round| round.len() != width)
+                    || parameters.mds.len() != width
+                    || parameters.mds.iter().any(|row| row.len() != width)
+                {
+                    return Err(SerializationError::InvalidData);
+                }
+            }
+            HashParameters::Poseidon2(parameters) => {
+                let width = parameters.width();
+                if !matches!(width, 2 | 3)
+                    || parameters.external_constants.len() != parameters.full_rounds
+                    || parameters.external_constants.iter().any(|round| round.len() != width)
+                    || parameters.internal_constants.len() != parameters.partial_rounds
+                    || parameters.internal_diagonal.len() != width
+                {
+                    return Err(SerializationError::InvalidData);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for HashParameters<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let hash_params = match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            0 => {
+                let full_rounds = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+                let partial_rounds = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+                let alpha = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+                let ark = Vec::<Vec<F>>::deserialize_with_mode(&mut reader, compress, validate)?;
+                let mds = Vec::<Vec<F>>::deserialize_with_mode(&mut reader, compress, validate)?;
+                let rate = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+                let capacity = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+                HashParameters::Poseidon(PoseidonConfig {
+                    full_rounds,
+                    partial_rounds,
+                    alpha,
+                    ark,
+                    mds,
+                    rate,
+                    capacity,
+                })
+            }
+            1 => HashParameters::Poseidon2(Poseidon2Config::deserialize_with_mode(
+                &mut reader,
+                compress,
+                Validate::No,
+            )?),
+            _ => return Err(SerializationError::InvalidData),
+        };
+        if let Validate::Yes = validate {
+            hash_params.check()?;
+        }
+        Ok(hash_params)
+    }
 }
 
 /// dummy for public params
-pub type PublicParameters = ();
+#[derive(Clone, Copy, Default)]
+pub struct PublicParameters;
 // for an IVC this is the proof for x_{i+1} = f(x_i)
 // a,b,c are r1cs constraint matrix for f
 #[derive(Clone, Copy, CanonicalDeserialize, CanonicalSerialize)]
 pub(crate) struct IndexInfo {
     pub(crate) num_constraints: usize,
     pub(crate) num_variables: usize,
-    // pub(crate) num_instance_variables: usize,
-    // pub(crate) matrices_hash: [u8; 32],
+    /// number of leading entries of an assignment that are public, including the constant one
+    pub(crate) num_instance_variables: usize,
+}
+
+impl IndexInfo {
+    pub(crate) fn num_witness_variables(&self) -> usize {
+        self.num_variables - self.num_instance_variables
+    }
+
+    /// whether an assignment split as (input, witness) has the shape of this index
+    pub(crate) fn matches_split(&self, num_input_variables: usize, num_witness_variables: usize) -> bool {
+        num_input_variables == self.num_instance_variables
+            && num_witness_variables == self.num_witness_variables()
+    }
+}
+
+/// collision-resistant digest (SHA-256) of a circuit, taken over its `IndexInfo` and
+/// constraint matrices. Keys, NARK instances and accumulators carry it so that objects
+/// belonging to different circuits are never mixed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+pub struct CircuitDigest(pub(crate) [u8; 32]);
+
+impl CircuitDigest {
+    pub(crate) fn new<F: PrimeField>(
+        index_info: &IndexInfo,
+        a: &Matrix<F>,
+        b: &Matrix<F>,
+        c: &Matrix<F>,
+    ) -> Self {
+        let mut bytes = vec![];
+        index_info.serialize_compressed(&mut bytes).unwrap();
+        a.serialize_compressed(&mut bytes).unwrap();
+        b.serialize_compressed(&mut bytes).unwrap();
+        c.serialize_compressed(&mut bytes).unwrap();
+        Self::from_bytes(bytes)
+    }
+
+    /// digest of a serialized relation other than R1CS matrices, see `ccs::CCS`
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Self {
+        let digest = <Sha256 as CRHScheme>::evaluate(&(), bytes).unwrap();
+        CircuitDigest(digest.try_into().unwrap())
+    }
+
+    /// the digest as two field elements of 16 bytes each, to absorb it into a transcript
+    pub(crate) fn to_field_elements<F: PrimeField>(self) -> [F; 2] {
+        [
+            F::from_le_bytes_mod_order(&self.0[..16]),
+            F::from_le_bytes_mod_order(&self.0[16..]),
+        ]
+    }
 }
 
 /// Prover key r1cs constraint matrices such that a.x + b.x = c.x
@@ -44,6 +294,172 @@ pub struct IndexProverKey<F: PrimeField> {
     pub(crate) a: Matrix<F>,
     pub(crate) b: Matrix<F>,
     pub(crate) c: Matrix<F>,
+    pub(crate) hash_params: HashParameters<F>,
+    pub(crate) code_params: CodeParameters,
+    pub(crate) digest: CircuitDigest,
+}
+
+impl<F: PrimeField> IndexProverKey<F> {
+    /// builds a key from constraint matrices produced elsewhere. Columns index the
+    /// assignment (instance || witness), with the constant one at column 0, and every
+    /// matrix has one row per constraint.
+    pub fn from_matrices(
+        num_instance_variables: usize,
+        num_witness_variables: usize,
+        a: Matrix<F>,
+        b: Matrix<F>,
+        c: Matrix<F>,
+    ) -> Result<Self, IndexError> {
+        if num_instance_variables == 0 {
+            return Err(IndexError::MissingConstantVariable);
+        }
+        if a.len() != b.len() || a.len() != c.len() {
+            return Err(IndexError::RowCountMismatch {
+                a: a.len(),
+                b: b.len(),
+                c: c.len(),
+            });
+        }
+
+        let num_variables = num_instance_variables + num_witness_variables;
+        for (name, matrix) in [('a', &a), ('b', &b), ('c', &c)] {
+            for (row, entries) in matrix.iter().enumerate() {
+                if let Some((_, column)) = entries.iter().find(|(_, column)| *column >= num_variables) {
+                    return Err(IndexError::ColumnOutOfRange {
+                        matrix: name,
+                        row,
+                        column: *column,
+                    });
+                }
+            }
+        }
+
+        let index_info = IndexInfo {
+            num_constraints: a.len(),
+            num_variables,
+            num_instance_variables,
+        };
+        let digest = CircuitDigest::new(&index_info, &a, &b, &c);
+
+        Ok(IndexProverKey {
+            index_info,
+            a,
+            b,
+            c,
+            hash_params: HashParameters::default(),
+            code_params: CodeParameters::default().fitted_to(&index_info),
+            digest,
+        })
+    }
+
+    /// the same key folding with `code_params`, with the code lengthened if it is too
+    /// short for the circuit (see `CodeParameters::fitted_to`). The parameters are not
+    /// part of the circuit digest, so the accumulators of both keys are interchangeable.
+    pub fn with_code_params(mut self, code_params: CodeParameters) -> Self {
+        self.code_params = code_params.fitted_to(&self.index_info);
+        self
+    }
+
+    pub fn code_params(&self) -> &CodeParameters {
+        &self.code_params
+    }
+
+    /// the same key committing with `hash_params`, e.g. Poseidon2 ones. Like the code
+    /// parameters they are not part of the circuit digest, but accumulators committed
+    /// with other parameters have other roots and are not decided by this key.
+    pub fn with_hash_params(mut self, hash_params: HashParameters<F>) -> Self {
+        self.hash_params = hash_params;
+        self
+    }
+
+    /// digest of the circuit this key was indexed from
+    pub fn digest(&self) -> CircuitDigest {
+        self.digest
+    }
+
+    /// whether `digest` describes the same circuit as this key
+    pub fn is_compatible(&self, digest: &CircuitDigest) -> bool {
+        self.digest == *digest
+    }
+
+    /// the verifier key of the same circuit
+    pub fn verifier_key(&self) -> IndexVerifierKey<F> {
+        IndexVerifierKey {
+            index_info: self.index_info,
+            code_params: self.code_params,
+            hash_params: self.hash_params.clone(),
+            digest: self.digest,
+        }
+    }
+
+    /// the decider key of the same circuit
+    pub fn decider_key(&self) -> IndexDeciderKey<F> {
+        IndexDeciderKey {
+            index_info: self.index_info,
+            a: self.a.clone(),
+            b: self.b.clone(),
+            c: self.c.clone(),
+            code_params: self.code_params,
+            hash_params: self.hash_params.clone(),
+            digest: self.digest,
+        }
+    }
+
+    /// the shape of this key, which is the envelope it was padded to if any
+    pub fn envelope(&self) -> IndexEnvelope {
+        IndexEnvelope {
+            num_constraints: self.index_info.num_constraints,
+            num_variables: self.index_info.num_variables,
+        }
+    }
+
+    /// the same circuit padded with empty constraints and unused witness variables up to
+    /// `envelope`. The padded key has its own digest, taken over the padded matrices, and
+    /// `R1CSNark::prove` pads the assignment of the original circuit to match.
+    pub fn padded(self, envelope: IndexEnvelope) -> Result<Self, IndexError> {
+        let index_info = self.index_info;
+        if index_info.num_constraints > envelope.num_constraints
+            || index_info.num_variables > envelope.num_variables
+        {
+            return Err(IndexError::ExceedsEnvelope {
+                num_constraints: index_info.num_constraints,
+                num_variables: index_info.num_variables,
+            });
+        }
+
+        let (mut a, mut b, mut c) = (self.a, self.b, self.c);
+        for matrix in [&mut a, &mut b, &mut c] {
+            matrix.resize(envelope.num_constraints, vec![]);
+        }
+        let num_instance_variables = index_info.num_instance_variables;
+        Ok(Self::from_matrices(
+            num_instance_variables,
+            envelope.num_variables - num_instance_variables,
+            a,
+            b,
+            c,
+        )?
+        .with_code_params(self.code_params)
+        .with_hash_params(self.hash_params))
+    }
+}
+
+/// fixed number of constraints and variables, constant one included, that circuits are
+/// padded up to by `R1CSNark::index_padded`. Keys padded to the same envelope have the
+/// same shape, and so the same code, accumulator and proof sizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+pub struct IndexEnvelope {
+    pub(crate) num_constraints: usize,
+    pub(crate) num_variables: usize,
+}
+
+impl IndexEnvelope {
+    pub fn new(num_constraints: usize, num_variables: usize) -> Self {
+        IndexEnvelope {
+            num_constraints,
+            num_variables,
+        }
+    }
 }
 
 // impl<F: PrimeField> IndexProverKey<F> {
@@ -69,58 +485,143 @@ pub struct IndexProverKey<F: PrimeField> {
 //     }
 // }
 
-/// Verifier and prover key are same
-pub type IndexVerifierKey<G> = IndexProverKey<G>;
+/// Reed-Solomon code length and number of spot-check queries of the accumulation scheme
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+pub struct CodeParameters {
+    pub(crate) code_length: usize,
+    pub(crate) num_queries: usize,
+}
+
+impl CodeParameters {
+    /// the default code with `num_queries` spot-checks per fold. Fewer queries make the
+    /// accumulation verifier, and any circuit running it, cheaper at the cost of
+    /// soundness.
+    pub fn with_num_queries(num_queries: usize) -> Self {
+        CodeParameters {
+            num_queries,
+            ..Self::default()
+        }
+    }
+
+    /// the same parameters with the code lengthened, to a power of two, until the
+    /// witness and error vectors of a circuit of the shape `index_info` are at most
+    /// 1 / INVERSE_RATE of its length. A code no longer than these vectors is not even
+    /// injective on them, and the spot-checks of a fold catch nothing.
+    pub(crate) fn fitted_to(self, index_info: &IndexInfo) -> Self {
+        let longest = index_info.num_witness_variables().max(index_info.num_constraints);
+        CodeParameters {
+            code_length: self.code_length.max((longest * INVERSE_RATE).next_power_of_two()),
+            ..self
+        }
+    }
+
+    pub fn code_length(&self) -> usize {
+        self.code_length
+    }
+
+    pub fn num_queries(&self) -> usize {
+        self.num_queries
+    }
+}
 
-/// an full assignment with input and witness
+impl Default for CodeParameters {
+    fn default() -> Self {
+        CodeParameters {
+            code_length: CODE_LENGTH,
+            num_queries: NUM_QUERIES,
+        }
+    }
+}
+
+/// Verifier key, only the circuit digest, sizes, code and hash parameters. The
+/// constraint matrices stay in the prover and decider keys.
 #[derive(Clone, CanonicalDeserialize, CanonicalSerialize)]
-pub struct FullAssignment<F: PrimeField> {
-    pub(crate) input: Vec<F>,
-    pub(crate) witness: Vec<F>,
+pub struct IndexVerifierKey<F: PrimeField> {
+    pub(crate) index_info: IndexInfo,
+    pub(crate) code_params: CodeParameters,
+    pub(crate) hash_params: HashParameters<F>,
+    pub(crate) digest: CircuitDigest,
 }
 
-impl<F: PrimeField> FullAssignment<F> {
-    // pub(crate) fn zero(input_len: usize, witness_len: usize) -> Self {
-    //     Self {
-    //         input: vec![F::zero(); input_len],
-    //         witness: vec![F::zero(); witness_len],
-    //     }
-    // }
+impl<F: PrimeField> IndexVerifierKey<F> {
+    /// digest of the circuit this key was indexed from
+    pub fn digest(&self) -> CircuitDigest {
+        self.digest
+    }
+
+    /// whether `digest` describes the same circuit as this key
+    pub fn is_compatible(&self, digest: &CircuitDigest) -> bool {
+        self.digest == *digest
+    }
 }
 
-// impl<F> Absorbable<F> for FullAssignment<F>
-// where
-//     F: PrimeField,
-// {
-//     fn to_sponge_bytes(&self) -> Vec<u8> {
-//         collect_sponge_bytes!(F, &self.input, &self.witness)
-//     }
+/// Decider key, the constraint matrices, code and hash parameters and circuit digest.
+/// The decider checks the relaxed relation and recomputes the commitments, which takes
+/// the code length but not the number of queries.
+#[derive(Clone, CanonicalDeserialize, CanonicalSerialize)]
+pub struct IndexDeciderKey<F: PrimeField> {
+    pub(crate) index_info: IndexInfo,
+    pub(crate) a: Matrix<F>,
+    pub(crate) b: Matrix<F>,
+    pub(crate) c: Matrix<F>,
+    pub(crate) code_params: CodeParameters,
+    pub(crate) hash_params: HashParameters<F>,
+    pub(crate) digest: CircuitDigest,
+}
 
-//     fn to_sponge_field_elements(&self) -> Vec<CF> {
-//         collect_sponge_field_elements!(&self.input, self.witness)
-//     }
-// }
+impl<F: PrimeField> IndexDeciderKey<F> {
+    /// digest of the circuit this key was indexed from
+    pub fn digest(&self) -> CircuitDigest {
+        self.digest
+    }
 
-/// commitment to the full (input,witness) vec (Merkle root)
-#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
-pub struct CommitmentFullAssignment<F: PrimeField + Absorb> {
-    pub(crate) blinded_assignment: <TwoToOneCRH<F> as TwoToOneCRHScheme>::Output, // commitment to full assignment merkle root for tree
-                                                                                  // with leaves = (input,witness)?
+    /// whether `digest` describes the same circuit as this key
+    pub fn is_compatible(&self, digest: &CircuitDigest) -> bool {
+        self.digest == *digest
+    }
 }
 
-// impl<F: PrimeField> CommitmentFullAssignment<F> {
-//     // pub(crate) fn zero(witness_len: usize) -> Self {
-//     //     Self {
-//     //         blinded_assignment: vec![F::zero(); witness_len],
-//     //     }
-//     // }
-// }
+/// public part of a NARK proof: the input and the Merkle root of the encoded witness.
+/// This is all the accumulation verifier sees of an input.
+#[derive(Clone, CanonicalDeserialize, CanonicalSerialize)]
+pub struct Instance<F: PrimeField + Absorb> {
+    pub(crate) circuit_digest: CircuitDigest,
+    pub(crate) input: Vec<F>,
+    pub(crate) blinded_witness: <TwoToOneCRH<F> as TwoToOneCRHScheme>::Output, // merkle root for tree with leaves = encoded witness
+}
 
-/// a proof for a given circuit f with (input,witness) and merkle root of the same
+impl<F: PrimeField + Absorb> Instance<F> {
+    pub fn input(&self) -> &[F] {
+        &self.input
+    }
+}
+
+/// private part of a NARK proof, kept by the prover
+#[derive(Clone, CanonicalDeserialize, CanonicalSerialize)]
+pub struct Witness<F: PrimeField> {
+    pub(crate) witness: Vec<F>,
+}
+
+/// a proof for a given circuit f: the instance (input, merkle root of the witness) and
+/// the witness it commits to
 #[derive(Clone)]
 pub struct Proof<F: PrimeField + Absorb> {
-    ///(input, witness)
-    pub instance: FullAssignment<F>,
-    ///merkle root for (input, witness),
-    pub witness: CommitmentFullAssignment<F>,
+    ///(input, merkle root of the witness)
+    pub instance: Instance<F>,
+    ///witness behind the merkle root
+    pub witness: Witness<F>,
+    ///prover-side codeword and merkle tree behind the root, kept so that the
+    ///accumulation prover can open it directly. `None` once the proof has been
+    ///stripped for sending, see `Proof::without_commitment`
+    pub commitment: Option<CommittedVector<F>>,
+}
+
+impl<F: PrimeField + Absorb> Proof<F> {
+    /// drops the prover-side commitment data
+    pub fn without_commitment(self) -> Self {
+        Proof {
+            commitment: None,
+            ..self
+        }
+    }
 }
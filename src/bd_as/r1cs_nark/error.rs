@@ -0,0 +1,139 @@
+use ark_std::fmt;
+use ark_relations::r1cs::SynthesisError;
+
+/// reason a NARK proof was rejected by `R1CSNark::verify`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// the proof was produced for a different circuit than the verifier key
+    KeyMismatch,
+    /// the input given to the verifier differs from the one in the proof
+    InputMismatch,
+    /// the input does not have the number of instance variables of the index
+    InputLength { expected: usize, found: usize },
+    /// the witness does not have the number of witness variables of the index
+    WitnessLength { expected: usize, found: usize },
+    /// the Merkle root of the encoded witness is not the committed one
+    CommitmentMismatch,
+    /// Az ∘ Bz != Cz at the given constraint row
+    UnsatisfiedConstraint { row: usize },
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::KeyMismatch => {
+                write!(f, "the proof was produced for a different circuit")
+            }
+            VerificationError::InputMismatch => {
+                write!(f, "the claimed input does not match the input of the proof")
+            }
+            VerificationError::InputLength { expected, found } => write!(
+                f,
+                "the input has {} variables but the index expects {}",
+                found, expected
+            ),
+            VerificationError::WitnessLength { expected, found } => write!(
+                f,
+                "the witness has {} variables but the index expects {}",
+                found, expected
+            ),
+            VerificationError::CommitmentMismatch => {
+                write!(f, "the witness does not match its Merkle commitment")
+            }
+            VerificationError::UnsatisfiedConstraint { row } => {
+                write!(f, "constraint {} is not satisfied", row)
+            }
+        }
+    }
+}
+
+impl ark_std::error::Error for VerificationError {}
+
+/// constraints an assignment fails, see `R1CSNark::check`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsatisfiedReport {
+    /// the first failing constraint rows, in order
+    pub rows: Vec<usize>,
+    /// total number of failing rows
+    pub num_unsatisfied: usize,
+    /// ark-relations namespaces of the first failing row, outermost first. Empty when
+    /// the constraint was not synthesized by `R1CSNark::check` or not namespaced.
+    pub namespace: Vec<String>,
+}
+
+impl fmt::Display for UnsatisfiedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} constraints are not satisfied, the first ones are {:?}",
+            self.num_unsatisfied, self.rows
+        )?;
+        if !self.namespace.is_empty() {
+            write!(f, ", row {} is in {}", self.rows[0], self.namespace.join("/"))?;
+        }
+        Ok(())
+    }
+}
+
+impl ark_std::error::Error for UnsatisfiedReport {}
+
+/// reason a set of constraint matrices was rejected by `IndexProverKey::from_matrices`,
+/// or a circuit could not be padded into an envelope or universal index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexError {
+    /// there has to be at least one instance variable for the constant one
+    MissingConstantVariable,
+    /// a, b and c do not have the same number of rows
+    RowCountMismatch { a: usize, b: usize, c: usize },
+    /// an entry of `matrix` ('a', 'b' or 'c') refers to a variable that does not exist
+    ColumnOutOfRange { matrix: char, row: usize, column: usize },
+    /// the circuit has more constraints or variables than the envelope it is padded to
+    ExceedsEnvelope { num_constraints: usize, num_variables: usize },
+    /// a universal index needs at least one member circuit
+    NoMembers,
+    /// a member of a universal index is not padded to the same envelope as the first one
+    EnvelopeMismatch { member: usize },
+    /// the circuit could not be synthesized
+    Synthesis(SynthesisError),
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::MissingConstantVariable => {
+                write!(f, "the instance must contain the constant one")
+            }
+            IndexError::RowCountMismatch { a, b, c } => write!(
+                f,
+                "the matrices have {}, {} and {} rows",
+                a, b, c
+            ),
+            IndexError::ColumnOutOfRange {
+                matrix,
+                row,
+                column,
+            } => write!(
+                f,
+                "row {} of matrix {} refers to variable {} which does not exist",
+                row, matrix, column
+            ),
+            IndexError::ExceedsEnvelope {
+                num_constraints,
+                num_variables,
+            } => write!(
+                f,
+                "the circuit has {} constraints and {} variables, more than the envelope",
+                num_constraints, num_variables
+            ),
+            IndexError::NoMembers => write!(f, "the universal index has no member circuits"),
+            IndexError::EnvelopeMismatch { member } => write!(
+                f,
+                "member {} is not padded to the envelope of the first member",
+                member
+            ),
+            IndexError::Synthesis(err) => write!(f, "synthesis failed: {}", err),
+        }
+    }
+}
+
+impl ark_std::error::Error for IndexError {}
@@ -1,30 +1,32 @@
-use ark_crypto_primitives::merkle_tree::MerkleTree;
 use ark_crypto_primitives::sponge::Absorb;
 use ark_ff::Field;
 use ark_ff::PrimeField;
-use ark_ff::Zero;
-use ark_r1cs_std::alloc::AllocVar;
-use ark_r1cs_std::eq::EqGadget;
-use ark_r1cs_std::fields::fp::FpVar;
-use ark_r1cs_std::uint16::UInt16;
-use ark_r1cs_std::R1CSVar;
 use ark_relations::r1cs::{
-    ConstraintSynthesizer, ConstraintSystem, Matrix, OptimizationGoal, SynthesisError,
-    SynthesisMode,
+    ConstraintLayer, ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, Matrix,
+    OptimizationGoal, SynthesisError, SynthesisMode, TracingMode,
 };
-use ark_std::ops::Mul;
 use ark_std::rand::RngCore;
 use ark_std::vec::Vec;
 use ark_std::{cfg_into_iter, marker::PhantomData};
-use crate::bd_as::RSCode;
+use crate::bd_as::CommittedVector;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
 
+pub mod constraints;
 mod data_structures;
+mod error;
 mod poseidon_config;
+mod poseidon_generator;
+pub mod poseidon2;
 pub use data_structures::*;
+pub use error::*;
 pub use poseidon_config::*;
+pub use poseidon_generator::*;
 
 type R1CSResult<T> = Result<T, SynthesisError>;
 pub(crate) const _CHALLENGE_SIZE: usize = 128;
+/// maximum number of failing rows listed in an `UnsatisfiedReport`
+pub(crate) const MAX_REPORTED_ROWS: usize = 16;
 
 ///This is the proof for any x_{i+1} = f(x_i)
 pub struct R1CSNark<F: PrimeField + Absorb> {
@@ -33,7 +35,9 @@ pub struct R1CSNark<F: PrimeField + Absorb> {
 
 impl<F: PrimeField + Absorb> R1CSNark<F> {
     /// generates public params
-    pub fn setup() -> PublicParameters {}
+    pub fn setup() -> PublicParameters {
+        PublicParameters
+    }
     /// generates index prover key and verifier key
     pub fn index<C: ConstraintSynthesizer<F>>(
         _pp: &PublicParameters,
@@ -61,32 +65,106 @@ impl<F: PrimeField + Absorb> R1CSNark<F> {
 
         // end_timer!(matrix_processing_time);
 
-        let num_variables = num_input_variables + num_witness_variables;
-        let index_info = IndexInfo {
-            num_variables,
-            num_constraints,
-            // num_instance_variables: num_input_variables,
-            // matrices_hash,
-        };
-        let ipk = IndexProverKey {
-            index_info,
-            a,
-            b,
-            c,
-        };
-        let ivk = ipk.clone();
+        debug_assert_eq!(num_constraints, a.len());
+        let ipk = IndexProverKey::from_matrices(num_input_variables, num_witness_variables, a, b, c)
+            .expect("the constraint system produced malformed matrices");
+        let ivk = ipk.verifier_key();
+        Ok((ipk, ivk))
+    }
+
+    /// like `index`, with the circuit padded up to `envelope` (see
+    /// `IndexProverKey::padded`). Circuits indexed under the same envelope can be
+    /// combined into a `ccs::UniversalIndex`.
+    pub fn index_padded<C: ConstraintSynthesizer<F>>(
+        pp: &PublicParameters,
+        r1cs_instance: C,
+        envelope: IndexEnvelope,
+    ) -> Result<(IndexProverKey<F>, IndexVerifierKey<F>), IndexError> {
+        let (ipk, _) = Self::index(pp, r1cs_instance).map_err(IndexError::Synthesis)?;
+        let ipk = ipk.padded(envelope)?;
+        let ivk = ipk.verifier_key();
         Ok((ipk, ivk))
     }
-    /// generates a proof for given Constraint synthesizer
+    /// generates a proof for given Constraint synthesizer. Fails with
+    /// `SynthesisError::MalformedVerifyingKey` if `ipk` was indexed from another circuit.
     pub fn prove<C: ConstraintSynthesizer<F>>(
         ipk: &IndexProverKey<F>,
         r1cs: C,
         mut _rng: Option<&mut dyn RngCore>,
     ) -> R1CSResult<Proof<F>> {
+        let (_, input, witness) = Self::synthesize(ipk, r1cs)?;
+
+        let witness_commitment =
+            CommittedVector::commit(&witness, &ipk.code_params, &ipk.hash_params);
+
+        let proof = Proof {
+            instance: Instance {
+                circuit_digest: ipk.digest,
+                input,
+                blinded_witness: witness_commitment.root(),
+            },
+            witness: Witness { witness },
+            commitment: Some(witness_commitment),
+        };
+        Ok(proof)
+    }
+
+    /// checks whether `r1cs` satisfies the circuit of `ipk` without producing a proof.
+    /// Returns `None` if it does and otherwise the failing constraint rows, along with
+    /// the ark-relations namespaces of the first failing constraint. Fails with
+    /// `SynthesisError::MalformedVerifyingKey` if `ipk` was indexed from another circuit.
+    pub fn check<C: ConstraintSynthesizer<F>>(
+        ipk: &IndexProverKey<F>,
+        r1cs: C,
+    ) -> R1CSResult<Option<UnsatisfiedReport>> {
+        // the layer records the namespaces each constraint is enforced in
+        let subscriber =
+            Registry::default().with(ConstraintLayer::new(TracingMode::OnlyConstraints));
+        let (pcs, input, witness) =
+            tracing::subscriber::with_default(subscriber, || Self::synthesize(ipk, r1cs))?;
+
+        let Some(mut report) = Self::check_assignment(ipk, &input, &witness) else {
+            return Ok(None);
+        };
+        if let Some(trace) = pcs.which_is_unsatisfied()? {
+            report.namespace = namespace_from_trace(&trace);
+        }
+        Ok(Some(report))
+    }
+
+    /// checks Az ∘ Bz = Cz for z = (input, witness) and reports the failing rows
+    pub fn check_assignment(
+        ipk: &IndexProverKey<F>,
+        input: &[F],
+        witness: &[F],
+    ) -> Option<UnsatisfiedReport> {
+        let az = matrix_vec_mul(&ipk.a, input, witness);
+        let bz = matrix_vec_mul(&ipk.b, input, witness);
+        let cz = matrix_vec_mul(&ipk.c, input, witness);
+
+        let failing_rows: Vec<usize> = (0..cz.len())
+            .filter(|row| az[*row] * bz[*row] != cz[*row])
+            .collect();
+        if failing_rows.is_empty() {
+            return None;
+        }
+
+        Some(UnsatisfiedReport {
+            num_unsatisfied: failing_rows.len(),
+            rows: failing_rows.into_iter().take(MAX_REPORTED_ROWS).collect(),
+            namespace: vec![],
+        })
+    }
+
+    /// runs `r1cs` in proving mode and checks that it is the circuit of `ipk`
+    pub(crate) fn synthesize<C: ConstraintSynthesizer<F>>(
+        ipk: &IndexProverKey<F>,
+        r1cs: C,
+    ) -> R1CSResult<(ConstraintSystemRef<F>, Vec<F>, Vec<F>)> {
         let pcs = ConstraintSystem::new_ref();
         pcs.set_optimization_goal(OptimizationGoal::Constraints);
         pcs.set_mode(ark_relations::r1cs::SynthesisMode::Prove {
-            construct_matrices: false,
+            construct_matrices: true,
         });
         r1cs.generate_constraints(pcs.clone())?;
 
@@ -100,228 +178,99 @@ impl<F: PrimeField + Absorb> R1CSNark<F> {
             )
         };
 
-        let num_input_variables = input.len();
-        let num_witness_variables = witness.len();
-        let num_variables = num_input_variables + num_witness_variables;
-
-        assert_eq!(ipk.index_info.num_variables, num_variables);
-        assert_eq!(ipk.index_info.num_constraints, num_constraints);
-
-        let full_assgn = FullAssignment {
-            input: input.clone(),
-            witness: witness.clone(),
-        };
-
-        let mut assignment = input.clone();
-        assignment.extend(witness.clone());
-
-        let inp_code = RSCode::encode(assignment, 512).code;
+        // a padded key accepts the original circuit, whose matrices and witness are
+        // padded the same way before comparing digests
+        let index_info = ipk.index_info;
+        let mut witness = witness;
+        if input.len() != index_info.num_instance_variables
+            || witness.len() > index_info.num_witness_variables()
+            || num_constraints > index_info.num_constraints
+        {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        witness.resize(index_info.num_witness_variables(), F::zero());
 
-        let mut inp_wit: Vec<[F; 1]> = vec![];
-        for i in inp_code.iter() {
-            inp_wit.push([i.clone()]);
+        let matrices = pcs.to_matrices().expect("should not be `None`");
+        let (mut a, mut b, mut c) = (matrices.a, matrices.b, matrices.c);
+        for matrix in [&mut a, &mut b, &mut c] {
+            matrix.resize(index_info.num_constraints, vec![]);
         }
-        while !inp_wit.len().is_power_of_two() {
-            inp_wit.push([F::zero()]);
+        let digest = CircuitDigest::new(&index_info, &a, &b, &c);
+        if !ipk.is_compatible(&digest) {
+            return Err(SynthesisError::MalformedVerifyingKey);
         }
-        let hash_params = poseidon_config::poseidon_parameters::<F>();
-
-        let witness_tree =
-            MerkleTree::<MerkleHashConfig<F>>::new(&hash_params, &hash_params, inp_wit).unwrap();
-
-        let commit_full_assgn = CommitmentFullAssignment {
-            blinded_assignment: witness_tree.root(),
-        };
 
-        let proof = Proof {
-            instance: full_assgn,
-            witness: commit_full_assgn,
-        };
-        Ok(proof)
+        Ok((pcs, input, witness))
     }
 
-    /// verifies a given proof and input using index verifier key
-    pub fn verify(ivk: &IndexVerifierKey<F>, input: &[F], proof: &Proof<F>) -> bool {
-        let a_times_input_witness = matrix_vec_mul(&ivk.a, &input, &proof.instance.witness);
-        let b_times_input_witness = matrix_vec_mul(&ivk.b, &input, &proof.instance.witness);
-        let c_times_input_witness = matrix_vec_mul(&ivk.c, &input, &proof.instance.witness);
-
-        // let mut comm_a = proof.first_msg.comm_a.into_projective();
-        // let mut comm_b = proof.first_msg.comm_b.into_projective();
-        // let mut comm_c = proof.first_msg.comm_c.into_projective();
-        // if let Some(first_msg_randomness) = proof.first_msg.randomness.as_ref() {
-        //     comm_a += first_msg_randomness.comm_r_a.mul(gamma);
-        //     comm_b += first_msg_randomness.comm_r_b.mul(gamma);
-        //     comm_c += first_msg_randomness.comm_r_c.mul(gamma);
-        // }
-
-        // let commit_time = start_timer!(|| "Reconstructing c_A, c_B, c_C commitments");
-        // let reconstructed_comm_a = PedersenCommitment::commit(
-        //     &ivk.ck,
-        //     &a_times_blinded_witness,
-        //     proof.second_msg.randomness.as_ref().map(|r| r.sigma_a),
-        // );
-        // let reconstructed_comm_b = PedersenCommitment::commit(
-        //     &ivk.ck,
-        //     &b_times_blinded_witness,
-        //     proof.second_msg.randomness.as_ref().map(|r| r.sigma_b),
-        // );
-        // let reconstructed_comm_c = PedersenCommitment::commit(
-        //     &ivk.ck,
-        //     &c_times_blinded_witness,
-        //     proof.second_msg.randomness.as_ref().map(|r| r.sigma_c),
-        // );
-        //
-        // let a_equal = comm_a == reconstructed_comm_a.into_projective();
-        // let b_equal = comm_b == reconstructed_comm_b.into_projective();
-        // let c_equal = comm_c == reconstructed_comm_c.into_projective();
-        // drop(c_times_blinded_witness);
-
-        let had_prod: Vec<_> = cfg_into_iter!(a_times_input_witness)
-            .zip(b_times_input_witness)
-            .map(|(a, b)| a * b)
-            .collect();
-        // let reconstructed_had_prod_comm = PedersenCommitment::commit(
-        //     &ivk.ck,
-        //     &had_prod,
-        //     proof.second_msg.randomness.as_ref().map(|r| r.sigma_o),
-        // );
-
-        // let mut had_prod_comm = proof.first_msg.comm_c.into_projective();
-        // if let Some(first_msg_randomness) = proof.first_msg.randomness.as_ref() {
-        //     had_prod_comm += first_msg_randomness.comm_1.mul(gamma);
-        //     had_prod_comm += first_msg_randomness.comm_2.mul(gamma.square());
-        // }
-        // let had_prod_equal = had_prod_comm == reconstructed_had_prod_comm.into_projective();
-        // add_to_trace!(|| "Verifier result", || format!(
-        //     "A equal: {}, B equal: {}, C equal: {}, Hadamard Product equal: {}",
-        //     a_equal, b_equal, c_equal, had_prod_equal
-        // ));
-        // a_equal & b_equal & c_equal & had_prod_equal
-        return had_prod == c_times_input_witness;
-    }
-}
-struct VerifierCircuitForR1CSNark<'a, F: PrimeField + Absorb> {
-    ivk: IndexProverKey<F>,
-    input: &'a [F],
-    proof: Proof<F>,
-}
-impl<F: PrimeField + Absorb> ConstraintSynthesizer<F> for VerifierCircuitForR1CSNark<'_, F> {
-    fn generate_constraints(
-        self,
-        cs: ark_relations::r1cs::ConstraintSystemRef<F>,
-    ) -> ark_relations::r1cs::Result<()> {
-        let mut inp_wit: Vec<(FpVar<F>, UInt16<F>)> = vec![];
-        for (ind, fp_val) in self.input.iter().enumerate() {
-            inp_wit.push((
-                FpVar::new_input(ark_relations::ns!(cs, "inp_val"), || Ok(fp_val))?,
-                UInt16::new_input(ark_relations::ns!(cs, "inp_val_ind"), || {
-                    Ok(u16::try_from(ind).unwrap())
-                })?,
-            ));
-        }
-        let inp_size = self.input.len();
-        for (ind, fp_val) in self.proof.instance.witness.iter().enumerate() {
-            inp_wit.push((
-                FpVar::new_input(ark_relations::ns!(cs, "inp_val"), || Ok(fp_val))?,
-                UInt16::new_input(ark_relations::ns!(cs, "inp_val_ind"), || {
-                    Ok(u16::try_from(ind + inp_size).unwrap())
-                })?,
-            ));
-        }
-        let mut a: Vec<Vec<(FpVar<F>, UInt16<F>)>> = vec![];
-        for row in self.ivk.a.iter() {
-            let mut fp_row: Vec<(FpVar<F>, UInt16<F>)> = vec![];
-            for val in row.iter() {
-                fp_row.push((
-                    FpVar::new_input(ark_relations::ns!(cs, "a"), || Ok(val.0))?,
-                    UInt16::new_input(ark_relations::ns!(cs, "a_row_ind"), || {
-                        Ok(u16::try_from(val.1).unwrap())
-                    })?,
-                ));
-            }
-            a.push(fp_row);
-        }
-        let mut b: Vec<Vec<(FpVar<F>, UInt16<F>)>> = vec![];
-        for row in self.ivk.b.iter() {
-            let mut fp_row: Vec<(FpVar<F>, UInt16<F>)> = vec![];
-            for val in row.iter() {
-                fp_row.push((
-                    FpVar::new_input(ark_relations::ns!(cs, "b"), || Ok(val.0))?,
-                    UInt16::new_input(ark_relations::ns!(cs, "b_row_ind"), || {
-                        Ok(u16::try_from(val.1).unwrap())
-                    })?,
-                ));
-            }
-            b.push(fp_row);
+    /// verifies a given proof and input with the decider key `idk`, since the NARK
+    /// verifier reads the constraint matrices. The witness is re-encoded and its Merkle
+    /// root compared with the commitment in the proof, and the first failed check is
+    /// reported.
+    pub fn verify(
+        idk: &IndexDeciderKey<F>,
+        input: &[F],
+        proof: &Proof<F>,
+    ) -> Result<(), VerificationError> {
+        if !idk.is_compatible(&proof.instance.circuit_digest) {
+            return Err(VerificationError::KeyMismatch);
         }
-        let mut c: Vec<Vec<(FpVar<F>, UInt16<F>)>> = vec![];
-        for row in self.ivk.c.iter() {
-            let mut fp_row: Vec<(FpVar<F>, UInt16<F>)> = vec![];
-            for val in row.iter() {
-                fp_row.push((
-                    FpVar::new_input(ark_relations::ns!(cs, "c"), || Ok(val.0))?,
-                    UInt16::new_input(ark_relations::ns!(cs, "c_row_ind"), || {
-                        Ok(u16::try_from(val.1).unwrap())
-                    })?,
-                ));
-            }
-            c.push(fp_row);
+
+        if input != proof.instance.input.as_slice() {
+            return Err(VerificationError::InputMismatch);
         }
-        let mut a_mul_inp_wit: Vec<FpVar<F>> = vec![];
-        for row in a.iter() {
-            let mut val = FpVar::new_constant(
-                ark_relations::ns!(cs, "val in a mul (inp,wit)"),
-                <F as Zero>::zero(),
-            )?;
-            for (a_row_val, ind) in row.iter() {
-                let ind_us: usize = ind.value()?.into();
-                let (inp_wit_val, inp_wit_ind) = &inp_wit[ind_us];
-                ind.enforce_equal(&inp_wit_ind)?;
-                val += a_row_val.mul(inp_wit_val);
-            }
-            a_mul_inp_wit.push(val);
+
+        if input.len() != idk.index_info.num_instance_variables {
+            return Err(VerificationError::InputLength {
+                expected: idk.index_info.num_instance_variables,
+                found: input.len(),
+            });
         }
 
-        let mut b_mul_inp_wit: Vec<FpVar<F>> = vec![];
-        for row in b.iter() {
-            let mut val = FpVar::new_constant(
-                ark_relations::ns!(cs, "val in a mul (inp,wit)"),
-                <F as Zero>::zero(),
-            )?;
-            for (b_row_val, ind) in row.iter() {
-                let ind_us: usize = ind.value()?.into();
-                let (inp_wit_val, inp_wit_ind) = &inp_wit[ind_us];
-                ind.enforce_equal(&inp_wit_ind)?;
-                val += b_row_val.mul(inp_wit_val);
-            }
-            b_mul_inp_wit.push(val);
+        if proof.witness.witness.len() != idk.index_info.num_witness_variables() {
+            return Err(VerificationError::WitnessLength {
+                expected: idk.index_info.num_witness_variables(),
+                found: proof.witness.witness.len(),
+            });
         }
 
-        let mut c_mul_inp_wit: Vec<FpVar<F>> = vec![];
-        for row in c.iter() {
-            let mut val = FpVar::new_constant(
-                ark_relations::ns!(cs, "val in a mul (inp,wit)"),
-                <F as Zero>::zero(),
-            )?;
-            for (c_row_val, ind) in row.iter() {
-                let ind_us: usize = ind.value()?.into();
-                let (inp_wit_val, inp_wit_ind) = &inp_wit[ind_us];
-                ind.enforce_equal(&inp_wit_ind)?;
-                val += c_row_val.mul(inp_wit_val);
-            }
-            c_mul_inp_wit.push(val);
+        let witness_commitment =
+            CommittedVector::commit(&proof.witness.witness, &idk.code_params, &idk.hash_params);
+        if witness_commitment.root() != proof.instance.blinded_witness {
+            return Err(VerificationError::CommitmentMismatch);
         }
-        let mut had_prod_aiw_biw: Vec<FpVar<F>> = vec![];
-        for (aiw_val, biw_val) in a_mul_inp_wit.iter().zip(b_mul_inp_wit.iter()) {
-            had_prod_aiw_biw.push(aiw_val.mul(biw_val));
+
+        let a_times_input_witness = matrix_vec_mul(&idk.a, input, &proof.witness.witness);
+        let b_times_input_witness = matrix_vec_mul(&idk.b, input, &proof.witness.witness);
+        let c_times_input_witness = matrix_vec_mul(&idk.c, input, &proof.witness.witness);
+
+        let failed_row = cfg_into_iter!(a_times_input_witness)
+            .zip(b_times_input_witness)
+            .zip(c_times_input_witness)
+            .position(|((a, b), c)| a * b != c);
+
+        match failed_row {
+            Some(row) => Err(VerificationError::UnsatisfiedConstraint { row }),
+            None => Ok(()),
         }
-        println!("had_prod: {:?}", had_prod_aiw_biw.value()?);
-        println!("c inp wit: {:?}", c_mul_inp_wit.value()?);
-        had_prod_aiw_biw.enforce_equal(&c_mul_inp_wit)?;
-        Ok(())
     }
 }
+/// namespace names of a constraint from its ark-relations trace, outermost first. The
+/// trace lists the spans innermost first as `<depth>: <module>::<name>` lines, each
+/// followed by its location.
+fn namespace_from_trace(trace: &str) -> Vec<String> {
+    let mut namespace: Vec<String> = trace
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.starts_with("at "))
+        .filter_map(|line| line.split_once(": "))
+        .filter_map(|(_, path)| path.rsplit("::").next())
+        .map(|name| name.to_string())
+        .collect();
+    namespace.reverse();
+    namespace
+}
+
 /// multiply mat*[inp||wit]
 pub(crate) fn matrix_vec_mul<F: Field>(matrix: &Matrix<F>, input: &[F], witness: &[F]) -> Vec<F> {
     ark_std::cfg_iter!(matrix)
@@ -346,8 +295,9 @@ fn inner_prod<F: Field>(row: &[(F, usize)], input: &[F], witness: &[F]) -> F {
 #[cfg(test)]
 pub mod test {
     use core::panic;
-    /// use std::borrow::Borrow;
+    // use std::borrow::Borrow;
 
+    use super::poseidon2::poseidon2_parameters;
     use super::*;
     use ark_crypto_primitives::crh::{
         poseidon::{
@@ -360,6 +310,7 @@ pub mod test {
     use ark_ff::One;
     use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
     use ark_relations::r1cs::ConstraintSystemRef;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
     #[derive(Clone)]
     pub struct HashVerifyCirc {
         inp_wit_1: Fr,
@@ -403,8 +354,8 @@ pub mod test {
         println!("inp_2: {:?}", inp_wit_2);
         let inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
             &poseidon_parameters(),
-            inp_wit_1.clone(),
-            inp_wit_2.clone(),
+            inp_wit_1,
+            inp_wit_2,
         )
         .unwrap();
         let hash_circ = HashVerifyCirc {
@@ -424,16 +375,17 @@ pub mod test {
         // };
         let pp = R1CSNark::<Fr>::setup();
 
-        let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
+        let Ok((ipk, _)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
             panic!("prover key not generated")
         };
+        let idk = ipk.decider_key();
         let mut rng = ark_std::test_rng();
         let Ok(proof) = R1CSNark::<Fr>::prove(&ipk, hash_circ, Some(&mut rng)) else {
             panic!["proof not generated"]
         };
         println!("PROOF GENERATED");
-        let verified = R1CSNark::<Fr>::verify(&ivk, &[Fr::one(), inp_hash], &proof);
-        assert_eq!(verified, true);
+        let verified = R1CSNark::<Fr>::verify(&idk, &[Fr::one(), inp_hash], &proof);
+        assert!(verified.is_ok());
     }
 
     #[test]
@@ -446,8 +398,8 @@ pub mod test {
         println!("inp_2: {:?}", inp_wit_2);
         let inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
             &poseidon_parameters(),
-            inp_wit_1.clone(),
-            inp_wit_2.clone(),
+            inp_wit_1,
+            inp_wit_2,
         )
         .unwrap();
         let hash_circ = HashVerifyCirc {
@@ -467,23 +419,550 @@ pub mod test {
         // };
         let pp = R1CSNark::<Fr>::setup();
 
-        let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
+        let Ok((ipk, _)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
             panic!("prover key not generated")
         };
         let mut rng = ark_std::test_rng();
         let Ok(proof) = R1CSNark::<Fr>::prove(&ipk, hash_circ, Some(&mut rng)) else {
             panic!["proof not generated"]
         };
+        let blinded_witness = proof.instance.blinded_witness;
         let cs: ConstraintSystemRef<Fr> = ConstraintSystem::new_ref();
-        let verifier_cir = VerifierCircuitForR1CSNark {
-            ivk: ivk.clone(),
-            input: &[Fr::one(), inp_hash],
-            proof,
-        };
-        println!("c: {:?}", ivk.clone().c);
+        let verifier_cir = constraints::R1CSNarkVerifierCircuit::new(ipk.decider_key(), proof);
         verifier_cir.generate_constraints(cs.clone()).unwrap();
         cs.finalize();
         let result = cs.is_satisfied().unwrap();
-        assert_eq!(result, true);
+        assert!(result);
+
+        // the instance is the public input of the verifier circuit
+        let public_input = cs.borrow().unwrap().instance_assignment.clone();
+        assert_eq!(public_input, vec![Fr::one(), Fr::one(), inp_hash, blinded_witness]);
+    }
+
+    #[test]
+    pub fn test_key_carries_hash_parameters() {
+        let inp_wit_1 = Fr::from(3u64);
+        let inp_wit_2 = Fr::from(5u64);
+        let inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
+            &poseidon_parameters(),
+            inp_wit_1,
+            inp_wit_2,
+        )
+        .unwrap();
+        let hash_circ = HashVerifyCirc {
+            inp_wit_1,
+            inp_wit_2,
+            inp_hash,
+        };
+        let pp = R1CSNark::<Fr>::setup();
+
+        let Ok((ipk, _)) = R1CSNark::<Fr>::index(&pp, hash_circ) else {
+            panic!("prover key not generated")
+        };
+
+        let mut bytes = vec![];
+        ipk.serialize_compressed(&mut bytes).unwrap();
+        let deserialized = IndexProverKey::<Fr>::deserialize_compressed(&bytes[..]).unwrap();
+
+        let HashParameters::Poseidon(hash_params) = &deserialized.hash_params else {
+            panic!("default hash parameters are not Poseidon")
+        };
+        assert_eq!(hash_params.ark, default_poseidon_parameters::<Fr>().ark);
+        assert_eq!(hash_params.mds, default_poseidon_parameters::<Fr>().mds);
+        assert_eq!(deserialized.a, ipk.a);
+
+        let ipk = ipk.with_hash_params(HashParameters::Poseidon2(poseidon2_parameters()));
+        let mut bytes = vec![];
+        ipk.serialize_compressed(&mut bytes).unwrap();
+        let deserialized = IndexProverKey::<Fr>::deserialize_compressed(&bytes[..]).unwrap();
+        let HashParameters::Poseidon2(hash_params) = &deserialized.hash_params else {
+            panic!("Poseidon2 hash parameters not kept")
+        };
+        assert_eq!(*hash_params, poseidon2_parameters::<Fr>());
+        assert_eq!(deserialized.digest(), ipk.digest());
+    }
+
+    #[test]
+    pub fn test_proof_keeps_commitment() {
+        let inp_wit_1 = Fr::from(3u64);
+        let inp_wit_2 = Fr::from(5u64);
+        let inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
+            &poseidon_parameters(),
+            inp_wit_1,
+            inp_wit_2,
+        )
+        .unwrap();
+        let hash_circ = HashVerifyCirc {
+            inp_wit_1,
+            inp_wit_2,
+            inp_hash,
+        };
+        let pp = R1CSNark::<Fr>::setup();
+
+        let Ok((ipk, _)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
+            panic!("prover key not generated")
+        };
+        let mut rng = ark_std::test_rng();
+        let proof = R1CSNark::<Fr>::prove(&ipk, hash_circ.clone(), Some(&mut rng)).unwrap();
+
+        let Some(commitment) = proof.commitment.as_ref() else {
+            panic!["commitment not kept"]
+        };
+        assert_eq!(commitment.root(), proof.instance.blinded_witness);
+        assert_eq!(commitment.code.len(), ipk.code_params.code_length);
+
+        let stripped = proof.without_commitment();
+        assert!(stripped.commitment.is_none());
+
+        // a key with a longer code commits, and is verified, at that length
+        let longer = ipk.clone().with_code_params(CodeParameters {
+            code_length: 2 * ipk.code_params.code_length,
+            ..ipk.code_params
+        });
+        let longer_proof = R1CSNark::<Fr>::prove(&longer, hash_circ, Some(&mut rng)).unwrap();
+        let Some(commitment) = longer_proof.commitment.as_ref() else {
+            panic!["commitment not kept"]
+        };
+        assert_eq!(commitment.code.len(), 2 * ipk.code_params.code_length);
+        assert_ne!(longer_proof.instance.blinded_witness, stripped.instance.blinded_witness);
+        let input = longer_proof.instance.input.clone();
+        assert!(R1CSNark::<Fr>::verify(&longer.decider_key(), &input, &longer_proof).is_ok());
+        assert!(R1CSNark::<Fr>::verify(&ipk.decider_key(), &input, &longer_proof).is_err());
+        assert_ne!(
+            crate::bd_as::AccumulatorWitness::zero(longer).blinded_w,
+            crate::bd_as::AccumulatorWitness::zero(ipk).blinded_w
+        );
+    }
+
+    #[test]
+    pub fn test_verify_reports_failed_check() {
+        let inp_wit_1 = Fr::from(3u64);
+        let inp_wit_2 = Fr::from(5u64);
+        let inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
+            &poseidon_parameters(),
+            inp_wit_1,
+            inp_wit_2,
+        )
+        .unwrap();
+        let hash_circ = HashVerifyCirc {
+            inp_wit_1,
+            inp_wit_2,
+            inp_hash,
+        };
+        let pp = R1CSNark::<Fr>::setup();
+
+        let Ok((ipk, _)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
+            panic!("prover key not generated")
+        };
+        let idk = ipk.decider_key();
+        let mut rng = ark_std::test_rng();
+        let proof = R1CSNark::<Fr>::prove(&ipk, hash_circ, Some(&mut rng)).unwrap();
+        let input = [Fr::one(), inp_hash];
+
+        assert_eq!(R1CSNark::<Fr>::verify(&idk, &input, &proof), Ok(()));
+        assert_eq!(
+            R1CSNark::<Fr>::verify(&idk, &[Fr::one(), inp_hash + Fr::one()], &proof),
+            Err(VerificationError::InputMismatch)
+        );
+
+        let mut short_proof = proof.clone();
+        short_proof.witness.witness.pop();
+        assert_eq!(
+            R1CSNark::<Fr>::verify(&idk, &input, &short_proof),
+            Err(VerificationError::WitnessLength {
+                expected: ipk.index_info.num_witness_variables(),
+                found: ipk.index_info.num_witness_variables() - 1,
+            })
+        );
+
+        // changing the witness without recommitting is caught by the commitment
+        let mut tampered_proof = proof.clone();
+        tampered_proof.witness.witness[0] += Fr::one();
+        assert_eq!(
+            R1CSNark::<Fr>::verify(&idk, &input, &tampered_proof),
+            Err(VerificationError::CommitmentMismatch)
+        );
+
+        // a recommitted but wrong witness fails the constraints
+        tampered_proof.instance.blinded_witness = CommittedVector::commit(
+            &tampered_proof.witness.witness,
+            &ipk.code_params,
+            &ipk.hash_params,
+        )
+        .root();
+        assert!(matches!(
+            R1CSNark::<Fr>::verify(&idk, &input, &tampered_proof),
+            Err(VerificationError::UnsatisfiedConstraint { .. })
+        ));
+    }
+
+    #[derive(Clone)]
+    pub struct SquareCirc {
+        x: Fr,
+    }
+    impl ConstraintSynthesizer<Fr> for SquareCirc {
+        fn generate_constraints(
+            self,
+            cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+        ) -> ark_relations::r1cs::Result<()> {
+            let y = FpVar::new_input(ark_relations::ns!(cs, "y"), || Ok(self.x * self.x))?;
+            let x = FpVar::new_witness(ark_relations::ns!(cs, "x"), || Ok(self.x))?;
+            (&x * &x).enforce_equal(&y)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_circuit_digest() {
+        let pp = R1CSNark::<Fr>::setup();
+        let hash_circ = |inp_wit_1: Fr, inp_wit_2: Fr| HashVerifyCirc {
+            inp_wit_1,
+            inp_wit_2,
+            inp_hash: <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
+                &poseidon_parameters(),
+                inp_wit_1,
+                inp_wit_2,
+            )
+            .unwrap(),
+        };
+
+        let (hash_ipk, hash_ivk) =
+            R1CSNark::<Fr>::index(&pp, hash_circ(Fr::from(3u64), Fr::from(5u64))).unwrap();
+        let hash_idk = hash_ipk.decider_key();
+        let (other_ipk, _) =
+            R1CSNark::<Fr>::index(&pp, hash_circ(Fr::from(7u64), Fr::from(11u64))).unwrap();
+        let (square_ipk, _) =
+            R1CSNark::<Fr>::index(&pp, SquareCirc { x: Fr::from(4u64) }).unwrap();
+        let square_idk = square_ipk.decider_key();
+
+        // the digest depends on the circuit only, not on the assignment
+        assert_eq!(hash_ipk.digest(), other_ipk.digest());
+        assert_eq!(hash_ipk.digest(), hash_ivk.digest());
+        assert_ne!(hash_ipk.digest(), square_ipk.digest());
+
+        let mut bytes = vec![];
+        hash_ipk.serialize_compressed(&mut bytes).unwrap();
+        let deserialized = IndexProverKey::<Fr>::deserialize_compressed(&bytes[..]).unwrap();
+        assert!(deserialized.is_compatible(&hash_ipk.digest()));
+
+        let mut rng = ark_std::test_rng();
+        let Err(SynthesisError::MalformedVerifyingKey) =
+            R1CSNark::<Fr>::prove(&square_ipk, hash_circ(Fr::from(3u64), Fr::from(5u64)), Some(&mut rng))
+        else {
+            panic!["proof generated with the key of another circuit"]
+        };
+
+        let proof = R1CSNark::<Fr>::prove(&square_ipk, SquareCirc { x: Fr::from(4u64) }, Some(&mut rng))
+            .unwrap();
+        assert_eq!(
+            R1CSNark::<Fr>::verify(&hash_idk, &proof.instance.input, &proof),
+            Err(VerificationError::KeyMismatch)
+        );
+        assert_eq!(
+            R1CSNark::<Fr>::verify(&square_idk, &proof.instance.input, &proof),
+            Ok(())
+        );
+    }
+
+    #[test]
+    pub fn test_verifier_key_is_compact() {
+        let pp = R1CSNark::<Fr>::setup();
+        let inp_wit_1 = Fr::from(3u64);
+        let inp_wit_2 = Fr::from(5u64);
+        let hash_circ = HashVerifyCirc {
+            inp_wit_1,
+            inp_wit_2,
+            inp_hash: <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
+                &poseidon_parameters(),
+                inp_wit_1,
+                inp_wit_2,
+            )
+            .unwrap(),
+        };
+
+        let (hash_ipk, hash_ivk) = R1CSNark::<Fr>::index(&pp, hash_circ).unwrap();
+        let (_, square_ivk) = R1CSNark::<Fr>::index(&pp, SquareCirc { x: Fr::from(4u64) }).unwrap();
+
+        // no matrices, so the size does not depend on the circuit
+        let mut hash_ivk_bytes = vec![];
+        hash_ivk.serialize_compressed(&mut hash_ivk_bytes).unwrap();
+        assert_eq!(hash_ivk_bytes.len(), square_ivk.compressed_size());
+        assert!(hash_ivk_bytes.len() < hash_ipk.compressed_size());
+
+        let deserialized = IndexVerifierKey::<Fr>::deserialize_compressed(&hash_ivk_bytes[..]).unwrap();
+        assert_eq!(deserialized.digest(), hash_ipk.digest());
+        assert_eq!(deserialized.code_params, hash_ipk.code_params);
+        assert_eq!(
+            deserialized.index_info.num_constraints,
+            hash_ipk.index_info.num_constraints
+        );
+
+        // the decider key keeps the matrices and the code parameters, it recommits to
+        // accumulators at the code length
+        let hash_idk = hash_ipk.decider_key();
+        let mut hash_idk_bytes = vec![];
+        hash_idk.serialize_compressed(&mut hash_idk_bytes).unwrap();
+        assert!(hash_idk_bytes.len() > hash_ivk_bytes.len());
+        let deserialized =
+            IndexDeciderKey::<Fr>::deserialize_compressed(&hash_idk_bytes[..]).unwrap();
+        assert_eq!(deserialized.digest(), hash_idk.digest());
+        assert_eq!(deserialized.code_params, hash_ipk.code_params);
+    }
+
+    /// x * x = y as a single constraint over (1, y, x)
+    #[derive(Clone)]
+    pub struct SingleSquareCirc {
+        x: Fr,
+    }
+    impl ConstraintSynthesizer<Fr> for SingleSquareCirc {
+        fn generate_constraints(
+            self,
+            cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+        ) -> ark_relations::r1cs::Result<()> {
+            let y = cs.new_input_variable(|| Ok(self.x * self.x))?;
+            let x = cs.new_witness_variable(|| Ok(self.x))?;
+            cs.enforce_constraint(
+                ark_relations::lc!() + x,
+                ark_relations::lc!() + x,
+                ark_relations::lc!() + y,
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_key_from_matrices() {
+        let a: Matrix<Fr> = vec![vec![(Fr::one(), 2)]];
+        let b: Matrix<Fr> = vec![vec![(Fr::one(), 2)]];
+        let c: Matrix<Fr> = vec![vec![(Fr::one(), 1)]];
+        let ipk = IndexProverKey::from_matrices(2, 1, a.clone(), b.clone(), c.clone()).unwrap();
+        let idk = ipk.decider_key();
+
+        // same circuit as the one synthesized by arkworks
+        let pp = R1CSNark::<Fr>::setup();
+        let (indexed_ipk, _) =
+            R1CSNark::<Fr>::index(&pp, SingleSquareCirc { x: Fr::from(2u64) }).unwrap();
+        assert_eq!(ipk.digest(), indexed_ipk.digest());
+
+        let mut rng = ark_std::test_rng();
+        let proof =
+            R1CSNark::<Fr>::prove(&ipk, SingleSquareCirc { x: Fr::from(5u64) }, Some(&mut rng))
+                .unwrap();
+        assert_eq!(
+            R1CSNark::<Fr>::verify(&idk, &[Fr::one(), Fr::from(25u64)], &proof),
+            Ok(())
+        );
+
+        assert_eq!(
+            IndexProverKey::from_matrices(0, 3, a.clone(), b.clone(), c.clone()).err(),
+            Some(IndexError::MissingConstantVariable)
+        );
+        assert_eq!(
+            IndexProverKey::from_matrices(2, 1, a.clone(), vec![], c.clone()).err(),
+            Some(IndexError::RowCountMismatch { a: 1, b: 0, c: 1 })
+        );
+        assert_eq!(
+            IndexProverKey::from_matrices(2, 0, a, b, c).err(),
+            Some(IndexError::ColumnOutOfRange {
+                matrix: 'a',
+                row: 0,
+                column: 2
+            })
+        );
+    }
+
+    /// w_i · 1 = w_i for each of `num_witness_variables` witness variables
+    fn identity_key(num_witness_variables: usize) -> IndexProverKey<Fr> {
+        let a: Matrix<Fr> = (0..num_witness_variables).map(|i| vec![(Fr::one(), 1 + i)]).collect();
+        let b: Matrix<Fr> = vec![vec![(Fr::one(), 0)]; num_witness_variables];
+        IndexProverKey::from_matrices(1, num_witness_variables, a.clone(), b, a).unwrap()
+    }
+
+    #[test]
+    pub fn test_code_fits_circuit() {
+        let code_length = crate::bd_as::CODE_LENGTH;
+        let rate = crate::bd_as::INVERSE_RATE;
+        assert_eq!(identity_key(code_length / rate).code_params.code_length, code_length);
+
+        // one more witness variable, or constraint, and the code doubles
+        let ipk = identity_key(code_length / rate + 1);
+        assert_eq!(ipk.code_params.code_length, 2 * code_length);
+        let a: Matrix<Fr> = vec![vec![(Fr::one(), 1)]; code_length / rate + 1];
+        let ipk_rows = IndexProverKey::from_matrices(1, 1, a.clone(), a.clone(), a).unwrap();
+        assert_eq!(ipk_rows.code_params.code_length, 2 * code_length);
+
+        // a shorter code cannot be asked for afterwards, a longer one can
+        let shorter = ipk.clone().with_code_params(CodeParameters::default());
+        assert_eq!(shorter.code_params, ipk.code_params);
+        let longer = CodeParameters { code_length: 8 * code_length, num_queries: 2 };
+        assert_eq!(ipk.clone().with_code_params(longer).code_params, longer);
+
+        let witness = vec![Fr::from(7u64); ipk.index_info.num_witness_variables()];
+        let commitment = CommittedVector::commit(&witness, &ipk.code_params, &ipk.hash_params);
+        assert_eq!(commitment.code.len(), 2 * code_length);
+        let proof = Proof {
+            instance: Instance {
+                circuit_digest: ipk.digest(),
+                input: vec![Fr::one()],
+                blinded_witness: commitment.root(),
+            },
+            witness: Witness { witness },
+            commitment: None,
+        };
+        assert_eq!(R1CSNark::<Fr>::verify(&ipk.decider_key(), &[Fr::one()], &proof), Ok(()));
+    }
+
+    #[test]
+    pub fn test_instance_split() {
+        let pp = R1CSNark::<Fr>::setup();
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, SingleSquareCirc { x: Fr::from(2u64) }).unwrap();
+        let idk = ipk.decider_key();
+        assert_eq!(ipk.index_info.num_instance_variables, 2);
+        assert_eq!(ipk.index_info.num_witness_variables(), 1);
+
+        let mut bytes = vec![];
+        ipk.serialize_compressed(&mut bytes).unwrap();
+        let deserialized = IndexProverKey::<Fr>::deserialize_compressed(&bytes[..]).unwrap();
+        assert_eq!(deserialized.index_info.num_instance_variables, 2);
+
+        // same matrices with the output moved into the witness
+        let shifted_ipk =
+            IndexProverKey::from_matrices(1, 2, ipk.a.clone(), ipk.b.clone(), ipk.c.clone())
+                .unwrap();
+        let mut rng = ark_std::test_rng();
+        let Err(SynthesisError::MalformedVerifyingKey) = R1CSNark::<Fr>::prove(
+            &shifted_ipk,
+            SingleSquareCirc { x: Fr::from(5u64) },
+            Some(&mut rng),
+        ) else {
+            panic!["proof generated for a different instance/witness split"]
+        };
+
+        let mut proof =
+            R1CSNark::<Fr>::prove(&ipk, SingleSquareCirc { x: Fr::from(5u64) }, Some(&mut rng))
+                .unwrap();
+        let output = proof.instance.input.pop().unwrap();
+        proof.witness.witness.insert(0, output);
+        proof.instance.blinded_witness = CommittedVector::commit(
+            &proof.witness.witness,
+            &ipk.code_params,
+            &ipk.hash_params,
+        )
+        .root();
+        assert_eq!(
+            R1CSNark::<Fr>::verify(&idk, &[Fr::one()], &proof),
+            Err(VerificationError::InputLength {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    /// x * x = y enforced inside nested namespaces
+    #[derive(Clone)]
+    pub struct NamespacedSquareCirc {
+        x: Fr,
+        y: Fr,
+    }
+    impl ConstraintSynthesizer<Fr> for NamespacedSquareCirc {
+        fn generate_constraints(
+            self,
+            cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+        ) -> ark_relations::r1cs::Result<()> {
+            let y = FpVar::new_input(ark_relations::ns!(cs, "y"), || Ok(self.y))?;
+            let x = FpVar::new_witness(ark_relations::ns!(cs, "x"), || Ok(self.x))?;
+            let _outer = ark_relations::ns!(cs, "square_check");
+            let _inner = ark_relations::ns!(cs, "output");
+            (&x * &x).enforce_equal(&y)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_check_reports_failing_constraint() {
+        let pp = R1CSNark::<Fr>::setup();
+        let circ = |x: u64, y: u64| NamespacedSquareCirc {
+            x: Fr::from(x),
+            y: Fr::from(y),
+        };
+        let (ipk, _) = R1CSNark::<Fr>::index(&pp, circ(2, 4)).unwrap();
+
+        assert_eq!(R1CSNark::<Fr>::check(&ipk, circ(3, 9)), Ok(None));
+
+        let Ok(Some(report)) = R1CSNark::<Fr>::check(&ipk, circ(3, 10)) else {
+            panic!["unsatisfying assignment passed the check"]
+        };
+        // row 0 computes x * x, row 1 compares it to y
+        assert_eq!(report.rows, vec![1]);
+        assert_eq!(report.num_unsatisfied, 1);
+        // followed by the spans of the gadgets that enforced it
+        assert!(report
+            .namespace
+            .starts_with(&["square_check".to_string(), "output".to_string()]));
+
+        // the assignment check alone cannot name the constraint
+        let report = R1CSNark::<Fr>::check_assignment(
+            &ipk,
+            &[Fr::one(), Fr::from(10u64)],
+            &[Fr::from(3u64), Fr::from(9u64)],
+        )
+        .unwrap();
+        assert_eq!(report.rows, vec![1]);
+        assert!(report.namespace.is_empty());
+
+        let Err(SynthesisError::MalformedVerifyingKey) =
+            R1CSNark::<Fr>::check(&ipk, SingleSquareCirc { x: Fr::from(3u64) })
+        else {
+            panic!["checked against the key of another circuit"]
+        };
+    }
+
+    #[test]
+    pub fn test_padded_index() {
+        let pp = R1CSNark::<Fr>::setup();
+        let envelope = IndexEnvelope::new(4, 6);
+        let (square_ipk, square_ivk) =
+            R1CSNark::<Fr>::index_padded(&pp, SquareCirc { x: Fr::from(2u64) }, envelope).unwrap();
+        let square_idk = square_ipk.decider_key();
+        let (single_ipk, _) =
+            R1CSNark::<Fr>::index_padded(&pp, SingleSquareCirc { x: Fr::from(2u64) }, envelope)
+                .unwrap();
+
+        // both keys have the shape of the envelope but stay bound to their circuit
+        assert_eq!(square_ipk.envelope(), envelope);
+        assert_eq!(single_ipk.envelope(), envelope);
+        assert_eq!(square_ivk.index_info.num_witness_variables(), 4);
+        assert_ne!(square_ipk.digest(), single_ipk.digest());
+        let (unpadded_ipk, _) =
+            R1CSNark::<Fr>::index(&pp, SquareCirc { x: Fr::from(2u64) }).unwrap();
+        assert_ne!(square_ipk.digest(), unpadded_ipk.digest());
+
+        let mut rng = ark_std::test_rng();
+        let proof =
+            R1CSNark::<Fr>::prove(&square_ipk, SquareCirc { x: Fr::from(3u64) }, Some(&mut rng))
+                .unwrap();
+        assert_eq!(proof.witness.witness.len(), 4);
+        assert_eq!(
+            R1CSNark::<Fr>::verify(&square_idk, &[Fr::one(), Fr::from(9u64)], &proof),
+            Ok(())
+        );
+        let Err(SynthesisError::MalformedVerifyingKey) =
+            R1CSNark::<Fr>::prove(&single_ipk, SquareCirc { x: Fr::from(3u64) }, Some(&mut rng))
+        else {
+            panic!["proof generated with the padded key of another circuit"]
+        };
+
+        assert_eq!(
+            R1CSNark::<Fr>::index_padded(
+                &pp,
+                SquareCirc { x: Fr::from(2u64) },
+                IndexEnvelope::new(1, 6)
+            )
+            .err(),
+            Some(IndexError::ExceedsEnvelope {
+                num_constraints: 2,
+                num_variables: 4
+            })
+        );
     }
 }
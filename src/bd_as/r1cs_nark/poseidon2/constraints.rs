@@ -0,0 +1,246 @@
+use ark_crypto_primitives::crh::{CRHSchemeGadget, TwoToOneCRHSchemeGadget};
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_std::borrow::Borrow;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+use super::{Poseidon2CRH, Poseidon2Config, Poseidon2TwoToOneCRH};
+
+/// Poseidon2 parameters are baked into the circuit as constants
+#[derive(Clone)]
+pub struct Poseidon2ParametersVar<F: PrimeField> {
+    pub parameters: Poseidon2Config<F>,
+}
+
+impl<F: PrimeField> AllocVar<Poseidon2Config<F>, F> for Poseidon2ParametersVar<F> {
+    fn new_variable<T: Borrow<Poseidon2Config<F>>>(
+        _cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        f().map(|parameters| Self {
+            parameters: parameters.borrow().clone(),
+        })
+    }
+}
+
+impl<F: PrimeField> Poseidon2ParametersVar<F> {
+    /// in-circuit version of `Poseidon2Config::permute`
+    pub fn permute(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let parameters = &self.parameters;
+        assert_eq!(state.len(), parameters.width());
+        let half_full_rounds = parameters.full_rounds / 2;
+
+        Self::external_linear_layer(state);
+        for constants in &parameters.external_constants[..half_full_rounds] {
+            self.external_round(state, constants)?;
+        }
+        for constant in &parameters.internal_constants {
+            state[0] += *constant;
+            state[0] = state[0].pow_by_constant([parameters.alpha])?;
+            self.internal_linear_layer(state);
+        }
+        for constants in &parameters.external_constants[half_full_rounds..] {
+            self.external_round(state, constants)?;
+        }
+        Ok(())
+    }
+
+    fn external_round(
+        &self,
+        state: &mut [FpVar<F>],
+        constants: &[F],
+    ) -> Result<(), SynthesisError> {
+        for (elem, constant) in state.iter_mut().zip(constants) {
+            *elem += *constant;
+            *elem = elem.pow_by_constant([self.parameters.alpha])?;
+        }
+        Self::external_linear_layer(state);
+        Ok(())
+    }
+
+    fn external_linear_layer(state: &mut [FpVar<F>]) {
+        let sum = Self::sum(state);
+        for elem in state.iter_mut() {
+            *elem += &sum;
+        }
+    }
+
+    fn internal_linear_layer(&self, state: &mut [FpVar<F>]) {
+        let sum = Self::sum(state);
+        for (elem, diagonal) in state.iter_mut().zip(&self.parameters.internal_diagonal) {
+            *elem = &sum + &*elem * *diagonal;
+        }
+    }
+
+    /// sum of the state. Unlike `Sum` for `FpVar` this does not panic on a state of
+    /// constants, which the first permutation sees when the first inputs are constants.
+    fn sum(state: &[FpVar<F>]) -> FpVar<F> {
+        state.iter().fold(FpVar::zero(), |sum, elem| sum + elem)
+    }
+
+    /// in-circuit version of `Poseidon2Config::hash`
+    pub fn hash(&self, input: &[FpVar<F>]) -> Result<FpVar<F>, SynthesisError> {
+        let parameters = &self.parameters;
+        let mut state = vec![FpVar::<F>::zero(); parameters.width()];
+        state[0] = FpVar::constant(F::from(input.len() as u64));
+
+        if input.is_empty() {
+            self.permute(&mut state)?;
+        }
+        for chunk in input.chunks(parameters.rate) {
+            for (i, elem) in chunk.iter().enumerate() {
+                state[parameters.capacity + i] += elem;
+            }
+            self.permute(&mut state)?;
+        }
+        Ok(state[parameters.capacity].clone())
+    }
+}
+
+pub struct Poseidon2CRHGadget<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField> CRHSchemeGadget<Poseidon2CRH<F>, F> for Poseidon2CRHGadget<F> {
+    type InputVar = [FpVar<F>];
+    type OutputVar = FpVar<F>;
+    type ParametersVar = Poseidon2ParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        if input.cs().is_none() {
+            let constant_input = input
+                .iter()
+                .map(|var| var.value())
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(FpVar::Constant(parameters.parameters.hash(&constant_input)));
+        }
+        parameters.hash(input)
+    }
+}
+
+pub struct Poseidon2TwoToOneCRHGadget<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField> TwoToOneCRHSchemeGadget<Poseidon2TwoToOneCRH<F>, F>
+    for Poseidon2TwoToOneCRHGadget<F>
+{
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = Poseidon2ParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::InputVar,
+        right_input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::OutputVar,
+        right_input: &Self::OutputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        <Poseidon2CRHGadget<F> as CRHSchemeGadget<_, _>>::evaluate(
+            parameters,
+            &[left_input.clone(), right_input.clone()],
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::r1cs_nark::constraints::{HashParametersVar, MerkleHashConfigVar};
+    use crate::bd_as::r1cs_nark::poseidon2::poseidon2_parameters;
+    use crate::bd_as::r1cs_nark::{HashParameters, MerkleHashConfig};
+    use ark_crypto_primitives::crh::{CRHScheme, TwoToOneCRHScheme};
+    use ark_crypto_primitives::merkle_tree::constraints::PathVar;
+    use ark_crypto_primitives::merkle_tree::MerkleTree;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_r1cs_std::eq::EqGadget;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    pub fn test_gadget_consistency() {
+        let params = poseidon2_parameters::<Fr>();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let params_var =
+            Poseidon2ParametersVar::new_constant(cs.clone(), params.clone()).unwrap();
+
+        let inputs = [Fr::from(3u64), Fr::from(11u64), Fr::from(19u64)];
+        let input_vars: Vec<FpVar<Fr>> = inputs
+            .iter()
+            .map(|input| FpVar::new_witness(cs.clone(), || Ok(*input)).unwrap())
+            .collect();
+
+        let hash = <Poseidon2CRH<Fr> as CRHScheme>::evaluate(&params, inputs).unwrap();
+        let hash_var = <Poseidon2CRHGadget<Fr> as CRHSchemeGadget<_, _>>::evaluate(
+            &params_var,
+            &input_vars,
+        )
+        .unwrap();
+        assert_eq!(hash_var.value().unwrap(), hash);
+
+        // a first chunk of constants followed by witnesses
+        let mixed_vars = [
+            FpVar::constant(inputs[0]),
+            FpVar::constant(inputs[1]),
+            input_vars[2].clone(),
+        ];
+        let hash_var = <Poseidon2CRHGadget<Fr> as CRHSchemeGadget<_, _>>::evaluate(
+            &params_var,
+            &mixed_vars,
+        )
+        .unwrap();
+        assert_eq!(hash_var.value().unwrap(), hash);
+
+        let compressed =
+            <Poseidon2TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(&params, inputs[0], inputs[1])
+                .unwrap();
+        let compressed_var =
+            <Poseidon2TwoToOneCRHGadget<Fr> as TwoToOneCRHSchemeGadget<_, _>>::evaluate(
+                &params_var,
+                &input_vars[0],
+                &input_vars[1],
+            )
+            .unwrap();
+        assert_eq!(compressed_var.value().unwrap(), compressed);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    pub fn test_merkle_path_gadget() {
+        let params = HashParameters::Poseidon2(poseidon2_parameters::<Fr>());
+        let leaves: Vec<[Fr; 1]> = (0..8u64).map(|i| [Fr::from(i * i)]).collect();
+        let tree = MerkleTree::<MerkleHashConfig<Fr>>::new(&params, &params, leaves).unwrap();
+        let path = tree.generate_proof(3).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let params_var = HashParametersVar::new_constant(cs.clone(), &params).unwrap();
+        let root_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        let leaf_var = vec![FpVar::new_witness(cs.clone(), || Ok(Fr::from(9u64))).unwrap()];
+        let path_var = PathVar::<MerkleHashConfig<Fr>, Fr, MerkleHashConfigVar<Fr>>::new_witness(
+            cs.clone(),
+            || Ok(path),
+        )
+        .unwrap();
+
+        path_var
+            .verify_membership(&params_var, &params_var, &root_var, &leaf_var)
+            .unwrap()
+            .enforce_equal(&ark_r1cs_std::boolean::Boolean::TRUE)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
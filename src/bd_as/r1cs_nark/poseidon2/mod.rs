@@ -0,0 +1,290 @@
+use ark_crypto_primitives::crh::{CRHScheme, TwoToOneCRHScheme};
+use ark_crypto_primitives::sponge::poseidon::find_poseidon_ark_and_mds;
+use ark_crypto_primitives::Error;
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::borrow::Borrow;
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+use ark_std::vec::Vec;
+
+use super::{is_permutation_exponent, poseidon_round_numbers, POSEIDON_CAPACITY};
+
+pub mod constraints;
+
+/// s-box exponents tried, in order, when picking the default Poseidon2 parameters
+const ALPHA_CANDIDATES: [u64; 6] = [3, 5, 7, 11, 13, 17];
+
+/// Poseidon2 permutation parameters for state width 2 or 3. The external linear layer is
+/// circ(2, 1) / circ(2, 1, 1) and the internal layer is 1 + diag(internal_diagonal).
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Poseidon2Config<F: PrimeField> {
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    pub alpha: u64,
+    /// one vector of `width` constants per external (full) round
+    pub external_constants: Vec<Vec<F>>,
+    /// one constant per internal (partial) round, added to the first state element
+    pub internal_constants: Vec<F>,
+    pub internal_diagonal: Vec<F>,
+    pub rate: usize,
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> Poseidon2Config<F> {
+    /// generates Poseidon2 parameters for the given state `width`, s-box `x^alpha` and
+    /// `security_level`. The round numbers are the Poseidon ones and the round constants
+    /// are taken from the same Grain LFSR stream.
+    pub fn generate(width: usize, alpha: u64, security_level: usize) -> Self {
+        let internal_diagonal: Vec<F> = match width {
+            2 => vec![F::one(), F::from(2u64)],
+            3 => vec![F::one(), F::one(), F::from(2u64)],
+            _ => panic!("Poseidon2 is only supported for state width 2 or 3"),
+        };
+        assert!(
+            is_permutation_exponent::<F>(alpha),
+            "x^alpha is not a permutation of the field, gcd(alpha, p - 1) != 1"
+        );
+
+        let rate = width - POSEIDON_CAPACITY;
+        let (full_rounds, partial_rounds) =
+            poseidon_round_numbers::<F>(width, alpha, security_level);
+        let (ark, _) = find_poseidon_ark_and_mds::<F>(
+            F::MODULUS_BIT_SIZE as u64,
+            rate,
+            full_rounds as u64,
+            partial_rounds as u64,
+            0,
+        );
+
+        let half_full_rounds = full_rounds / 2;
+        let mut external_constants = ark[..half_full_rounds].to_vec();
+        external_constants.extend_from_slice(&ark[half_full_rounds + partial_rounds..]);
+        let internal_constants = ark[half_full_rounds..half_full_rounds + partial_rounds]
+            .iter()
+            .map(|round| round[0])
+            .collect();
+
+        Poseidon2Config {
+            full_rounds,
+            partial_rounds,
+            alpha,
+            external_constants,
+            internal_constants,
+            internal_diagonal,
+            rate,
+            capacity: POSEIDON_CAPACITY,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.rate + self.capacity
+    }
+
+    /// applies the Poseidon2 permutation to `state` in place
+    pub fn permute(&self, state: &mut [F]) {
+        assert_eq!(state.len(), self.width());
+        let half_full_rounds = self.full_rounds / 2;
+
+        self.external_linear_layer(state);
+        for constants in &self.external_constants[..half_full_rounds] {
+            self.external_round(state, constants);
+        }
+        for constant in &self.internal_constants {
+            state[0] += constant;
+            state[0] = self.sbox(state[0]);
+            self.internal_linear_layer(state);
+        }
+        for constants in &self.external_constants[half_full_rounds..] {
+            self.external_round(state, constants);
+        }
+    }
+
+    fn external_round(&self, state: &mut [F], constants: &[F]) {
+        for (elem, constant) in state.iter_mut().zip(constants) {
+            *elem += constant;
+            *elem = self.sbox(*elem);
+        }
+        self.external_linear_layer(state);
+    }
+
+    /// x^alpha, with the common exponents unrolled since `pow` multiplies more than needed
+    fn sbox(&self, x: F) -> F {
+        match self.alpha {
+            3 => x.square() * x,
+            5 => x.square().square() * x,
+            alpha => x.pow([alpha]),
+        }
+    }
+
+    /// circ(2, 1) and circ(2, 1, 1) both map s_i to s_i + sum(s)
+    fn external_linear_layer(&self, state: &mut [F]) {
+        let sum: F = state.iter().sum();
+        for elem in state.iter_mut() {
+            *elem += sum;
+        }
+    }
+
+    /// (1 + diag(d)) maps s_i to sum(s) + d_i * s_i. The default diagonals only hold ones
+    /// and twos, which are added instead of multiplied.
+    fn internal_linear_layer(&self, state: &mut [F]) {
+        let sum: F = state.iter().sum();
+        for (elem, diagonal) in state.iter_mut().zip(&self.internal_diagonal) {
+            let scaled = if diagonal.is_one() {
+                *elem
+            } else if *diagonal == F::one().double() {
+                elem.double()
+            } else {
+                *diagonal * *elem
+            };
+            *elem = sum + scaled;
+        }
+    }
+
+    /// sponge hash of `input`, the input length is written to the capacity as domain
+    /// separation and the first rate element is squeezed
+    pub fn hash(&self, input: &[F]) -> F {
+        let mut state = vec![F::zero(); self.width()];
+        state[0] = F::from(input.len() as u64);
+
+        if input.is_empty() {
+            self.permute(&mut state);
+        }
+        for chunk in input.chunks(self.rate) {
+            for (i, elem) in chunk.iter().enumerate() {
+                state[self.capacity + i] += elem;
+            }
+            self.permute(&mut state);
+        }
+        state[self.capacity]
+    }
+}
+
+/// default Poseidon2 parameters (width 3, 128 bit security) using the smallest s-box
+/// exponent that is a permutation of `F`
+pub fn poseidon2_parameters<F: PrimeField>() -> Poseidon2Config<F> {
+    let alpha = ALPHA_CANDIDATES
+        .into_iter()
+        .find(|alpha| is_permutation_exponent::<F>(*alpha))
+        .expect("no small s-box exponent is a permutation of the field");
+    Poseidon2Config::generate(3, alpha, 128)
+}
+
+pub struct Poseidon2CRH<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField> CRHScheme for Poseidon2CRH<F> {
+    type Input = [F];
+    type Output = F;
+    type Parameters = Poseidon2Config<F>;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(poseidon2_parameters())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        Ok(parameters.hash(input.borrow()))
+    }
+}
+
+pub struct Poseidon2TwoToOneCRH<F: PrimeField> {
+    _field_data: PhantomData<F>,
+}
+
+impl<F: PrimeField> TwoToOneCRHScheme for Poseidon2TwoToOneCRH<F> {
+    type Input = F;
+    type Output = F;
+    type Parameters = Poseidon2Config<F>;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(poseidon2_parameters())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Ok(parameters.hash(&[*left_input.borrow(), *right_input.borrow()]))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::r1cs_nark::{HashParameters, MerkleHashConfig};
+    use ark_crypto_primitives::merkle_tree::MerkleTree;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_ff::{One, Zero};
+
+    #[test]
+    pub fn test_default_parameters() {
+        let params = poseidon2_parameters::<Fr>();
+        // 3 divides p - 1 for the Jubjub scalar field, so the s-box is x^5
+        assert_eq!(params.alpha, 5);
+        assert_eq!(params.width(), 3);
+        assert_eq!(params.external_constants.len(), params.full_rounds);
+        assert_eq!(params.internal_constants.len(), params.partial_rounds);
+    }
+
+    #[test]
+    pub fn test_permutation_is_not_linear() {
+        let params = poseidon2_parameters::<Fr>();
+        let mut zero_state = vec![Fr::zero(); 3];
+        let mut one_state = vec![Fr::one(), Fr::zero(), Fr::zero()];
+        let mut two_state = vec![Fr::from(2u64), Fr::zero(), Fr::zero()];
+        params.permute(&mut zero_state);
+        params.permute(&mut one_state);
+        params.permute(&mut two_state);
+
+        let doubled: Vec<Fr> = one_state
+            .iter()
+            .zip(&zero_state)
+            .map(|(one, zero)| *one + *one - *zero)
+            .collect();
+        assert_ne!(doubled, two_state);
+    }
+
+    #[test]
+    pub fn test_hash_domain_separation() {
+        let params = poseidon2_parameters::<Fr>();
+        let left = Fr::from(7u64);
+        let right = Fr::zero();
+
+        let compressed = <Poseidon2TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
+            &params, left, right,
+        )
+        .unwrap();
+        let hashed = <Poseidon2CRH<Fr> as CRHScheme>::evaluate(&params, [left]).unwrap();
+        assert_ne!(compressed, hashed);
+        assert_eq!(compressed, params.hash(&[left, right]));
+    }
+
+    #[test]
+    pub fn test_merkle_tree() {
+        let params = HashParameters::Poseidon2(poseidon2_parameters::<Fr>());
+        let leaves: Vec<[Fr; 1]> = (0..16u64).map(|i| [Fr::from(i)]).collect();
+
+        let tree = MerkleTree::<MerkleHashConfig<Fr>>::new(&params, &params, leaves).unwrap();
+        let path = tree.generate_proof(5).unwrap();
+
+        assert!(path
+            .verify(&params, &params, &tree.root(), [Fr::from(5u64)])
+            .unwrap());
+        assert!(!path
+            .verify(&params, &params, &tree.root(), [Fr::from(6u64)])
+            .unwrap());
+    }
+}
@@ -1,6 +1,12 @@
 use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
 use ark_ff::PrimeField;
 
+/// fixed Poseidon parameters (width 3, x^17, 8 full and 29 partial rounds). The constants
+/// are parsed into any `F` but were not derived for it, and they are not the output of
+/// `generate_poseidon_parameters` for any field: the matrix is the near-MDS one below
+/// rather than a Cauchy matrix sampled with Grain, and the reference bounds ask for 31
+/// partial rounds at this width and s-box. `HashParameters::default()` uses
+/// `default_poseidon_parameters` instead.
 pub fn poseidon_parameters<F: PrimeField>() -> PoseidonConfig<F> {
     let full_rounds = 8;
     let partial_rounds = 29;
@@ -0,0 +1,369 @@
+use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::vec::Vec;
+
+/// capacity of every generated sponge, the state width is rate + capacity
+pub const POSEIDON_CAPACITY: usize = 1;
+/// full rounds added on top of the minimum that satisfies the security bounds
+const FULL_ROUNDS_MARGIN: usize = 2;
+/// factor applied to the minimum number of partial rounds
+const PARTIAL_ROUNDS_MARGIN: f64 = 1.075;
+
+/// generates Poseidon parameters for the field `F` with the given state `width`,
+/// s-box `x^alpha` and `security_level` (in bits). The round numbers are derived from
+/// the bounds of the Poseidon paper (including the 2023/537 Groebner bound) with the
+/// reference security margin, and the round constants and MDS matrix are sampled from
+/// the Grain LFSR exactly as the reference `generate_parameters_grain.sage` script does.
+pub fn generate_poseidon_parameters<F: PrimeField>(
+    width: usize,
+    alpha: u64,
+    security_level: usize,
+) -> PoseidonConfig<F> {
+    assert!(width > POSEIDON_CAPACITY, "width must leave room for a non-zero rate");
+    assert!(alpha > 1, "alpha must be greater than one");
+    assert!(
+        is_permutation_exponent::<F>(alpha),
+        "x^alpha is not a permutation of the field, gcd(alpha, p - 1) != 1"
+    );
+
+    let rate = width - POSEIDON_CAPACITY;
+    let (full_rounds, partial_rounds) = poseidon_round_numbers::<F>(width, alpha, security_level);
+
+    // the reference script rejects matrices built from repeated samples, arkworks
+    // exposes that as the number of matrices to skip
+    let mut skip_matrices = 0;
+    let (ark, mds) = loop {
+        let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+            F::MODULUS_BIT_SIZE as u64,
+            rate,
+            full_rounds as u64,
+            partial_rounds as u64,
+            skip_matrices,
+        );
+        if is_cauchy_matrix_distinct(&mds) {
+            break (ark, mds);
+        }
+        skip_matrices += 1;
+    };
+
+    PoseidonConfig::<F>::new(
+        full_rounds,
+        partial_rounds,
+        alpha,
+        mds,
+        ark,
+        rate,
+        POSEIDON_CAPACITY,
+    )
+}
+
+/// the Poseidon parameters of `HashParameters::default()`, generated for `F` with the
+/// width and s-box of `poseidon_parameters` at 128 bits of security. Where x^17 does not
+/// permute `F` the next exponent that does is taken.
+pub fn default_poseidon_parameters<F: PrimeField>() -> PoseidonConfig<F> {
+    let alpha = (17..).find(|alpha| is_permutation_exponent::<F>(*alpha)).unwrap();
+    generate_poseidon_parameters(3, alpha, 128)
+}
+
+/// computes (full_rounds, partial_rounds) for a Poseidon instance over `F` with the
+/// given state `width`, s-box `x^alpha` and `security_level`, minimising the number of
+/// s-boxes (`width * full_rounds + partial_rounds`)
+pub fn poseidon_round_numbers<F: PrimeField>(
+    width: usize,
+    alpha: u64,
+    security_level: usize,
+) -> (usize, usize) {
+    let log_p = log2_modulus::<F>();
+    let security_level = security_level as f64;
+
+    let mut full_rounds = 0;
+    let mut partial_rounds = 0;
+    let mut min_cost = usize::MAX;
+
+    for min_partial_rounds in 1..500 {
+        // no candidate from here on is cheaper than the cheapest one found
+        let lowest_cost = width * (4 + FULL_ROUNDS_MARGIN)
+            + (min_partial_rounds as f64 * PARTIAL_ROUNDS_MARGIN).ceil() as usize;
+        if lowest_cost > min_cost {
+            break;
+        }
+
+        // like the reference script, the margin applied to the partial rounds carries
+        // over to the remaining full round candidates
+        let mut partial_rounds_candidate = min_partial_rounds;
+        for full_rounds_candidate in (4..100).step_by(2) {
+            if !satisfies_security_bounds(
+                log_p,
+                width,
+                full_rounds_candidate,
+                partial_rounds_candidate,
+                alpha,
+                security_level,
+            ) {
+                continue;
+            }
+
+            let rf = full_rounds_candidate + FULL_ROUNDS_MARGIN;
+            partial_rounds_candidate =
+                (partial_rounds_candidate as f64 * PARTIAL_ROUNDS_MARGIN).ceil() as usize;
+
+            let cost = width * rf + partial_rounds_candidate;
+            if cost < min_cost || (cost == min_cost && rf < full_rounds) {
+                full_rounds = rf;
+                partial_rounds = partial_rounds_candidate;
+                min_cost = cost;
+            }
+            // the bounds only get easier with more full rounds, so the remaining
+            // candidates hold as well, with more partial rounds and at a higher cost
+            break;
+        }
+    }
+
+    (full_rounds, partial_rounds)
+}
+
+/// checks the statistical, interpolation and Groebner basis bounds for the given round numbers
+fn satisfies_security_bounds(
+    log_p: f64,
+    width: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    alpha: u64,
+    security_level: f64,
+) -> bool {
+    let n = log_p.ceil();
+    let t = width as f64;
+    let rf = full_rounds as f64;
+    let rp = partial_rounds as f64;
+    let alpha = alpha as f64;
+    let log2_alpha = alpha.log2();
+
+    // statistical attacks
+    let rf_1: f64 = if security_level <= (log_p - (alpha - 1.0) / 2.0).floor() * (t + 1.0) {
+        6.0
+    } else {
+        10.0
+    };
+    // interpolation attack
+    let rf_2 = 1.0 + (security_level.min(n) / log2_alpha).ceil() + (t.log2() / log2_alpha).ceil() - rp;
+    // Groebner basis attacks
+    let rf_3 = security_level.min(log_p) / log2_alpha - rp;
+    let rf_4 = t - 1.0 + (security_level / (t + 1.0)).min(log_p / 2.0) / log2_alpha - rp;
+    let rf_5 = (t - 2.0 + security_level / (2.0 * log2_alpha) - rp) / (t - 1.0);
+
+    let rf_max = [rf_1, rf_2, rf_3.ceil(), rf_4.ceil(), rf_5.ceil()]
+        .into_iter()
+        .fold(f64::MIN, f64::max);
+
+    // additional Groebner basis bound from https://eprint.iacr.org/2023/537
+    let r_temp = (t / 3.0).floor();
+    let over = (rf - 1.0) * t + rp + r_temp + r_temp * (rf / 2.0) + rp + alpha;
+    let under = r_temp * (rf / 2.0) + rp + alpha;
+    let mut binom_log = log2_binomial(over, under);
+    if !binom_log.is_finite() {
+        binom_log = security_level + 1.0;
+    }
+    let cost_gb4 = (2.0 * binom_log).ceil();
+
+    rf >= rf_max && cost_gb4 >= security_level
+}
+
+/// log2 of the binomial coefficient (n choose k)
+fn log2_binomial(n: f64, k: f64) -> f64 {
+    let mut result = 0.0;
+    let mut i = 1.0;
+    while i <= k {
+        result += ((n - k + i) / i).log2();
+        i += 1.0;
+    }
+    result
+}
+
+/// log2 of the field modulus as a float
+fn log2_modulus<F: PrimeField>() -> f64 {
+    F::MODULUS
+        .to_bytes_be()
+        .iter()
+        .fold(0.0, |acc: f64, byte| acc * 256.0 + *byte as f64)
+        .log2()
+}
+
+/// x^alpha is a permutation of F iff gcd(alpha, p - 1) = 1
+pub(crate) fn is_permutation_exponent<F: PrimeField>(alpha: u64) -> bool {
+    let mut p_minus_one = F::MODULUS;
+    p_minus_one.sub_with_borrow(&F::BigInt::from(1u64));
+
+    let mut remaining = alpha;
+    let mut factor = 2;
+    while remaining > 1 {
+        if remaining.is_multiple_of(factor) {
+            if bigint_mod_u64::<F>(&p_minus_one, factor) == 0 {
+                return false;
+            }
+            while remaining.is_multiple_of(factor) {
+                remaining /= factor;
+            }
+        }
+        factor += 1;
+    }
+    true
+}
+
+fn bigint_mod_u64<F: PrimeField>(value: &F::BigInt, modulus: u64) -> u64 {
+    value
+        .to_bytes_be()
+        .iter()
+        .fold(0u128, |acc, byte| (acc * 256 + *byte as u128) % modulus as u128) as u64
+}
+
+/// a Cauchy matrix 1/(x_i + y_j) is MDS only if the x_i and the y_j are distinct,
+/// which is the case iff no two rows and no two columns coincide
+fn is_cauchy_matrix_distinct<F: PrimeField>(mds: &[Vec<F>]) -> bool {
+    let width = mds.len();
+    for i in 0..width {
+        for j in (i + 1)..width {
+            if mds[i] == mds[j] {
+                return false;
+            }
+            if (0..width).all(|row| mds[row][i] == mds[row][j]) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_crypto_primitives::sponge::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use crate::bd_as::r1cs_nark::{poseidon_parameters, HashParameters};
+    use ark_ed_on_bls12_381::{Fq, Fr};
+    use ark_ff::{One, Zero};
+    use ark_std::str::FromStr;
+
+    #[test]
+    pub fn test_round_numbers_match_reference() {
+        // (width, alpha, full_rounds, partial_rounds) from the reference tables for the
+        // BLS12-381 scalar field, which is the base field of Jubjub
+        let reference = [
+            (3, 17, 8, 31),
+            (4, 5, 8, 56),
+            (5, 5, 8, 56),
+            (6, 5, 8, 57),
+            (9, 5, 8, 57),
+            (3, 257, 8, 13),
+        ];
+        for (width, alpha, full_rounds, partial_rounds) in reference {
+            assert_eq!(
+                poseidon_round_numbers::<Fq>(width, alpha, 128),
+                (full_rounds, partial_rounds)
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_grain_constants_known_answer() {
+        // first two round constants of the reference script for n = 255, t = 3,
+        // R_F = 8, R_P = 31
+        let params = generate_poseidon_parameters::<Fq>(3, 17, 128);
+        assert_eq!(params.full_rounds, 8);
+        assert_eq!(params.partial_rounds, 31);
+        assert_eq!(
+            params.ark[0][0],
+            Fq::from_str(
+                "27117311055620256798560880810000042840428971800021819916023577129547249660720"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            params.ark[0][1],
+            Fq::from_str(
+                "51641662388546346858987925410984003801092143452466182801674685248597955169158"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_matches_reference_default_parameters() {
+        // (width, alpha, ark[0][0], mds[0][0]) of the default BLS12-381 scalar field
+        // parameters published with the arkworks sponge, which use the same round numbers
+        let reference = [
+            (
+                3,
+                17,
+                "27117311055620256798560880810000042840428971800021819916023577129547249660720",
+                "26017457457808754696901916760153646963713419596921330311675236858336250747575",
+            ),
+            (
+                4,
+                5,
+                "11865901593870436687704696210307853465124332568266803587887584059192277437537",
+                "18791275321793747281053101601584820964683215017313972132092847596434094368732",
+            ),
+            (
+                5,
+                5,
+                "41775194144383840477168997387904574072980173775424253289429546852163474914621",
+                "42906651709148432559075674119637355642263148226238482628104108168707874713729",
+            ),
+            (
+                6,
+                5,
+                "24877380261526996562448766783081897666376381975344509826094208368479247894723",
+                "30022080821787948421423927053079656488514459012053372877891553084525866347732",
+            ),
+        ];
+        for (width, alpha, ark, mds) in reference {
+            let params = generate_poseidon_parameters::<Fq>(width, alpha, 128);
+            assert_eq!(params.ark[0][0], Fq::from_str(ark).unwrap());
+            assert_eq!(params.mds[0][0], Fq::from_str(mds).unwrap());
+        }
+
+        // the published squeeze of the width 3 sponge after absorbing (0, 1, 2)
+        let params = generate_poseidon_parameters::<Fq>(3, 17, 128);
+        let mut sponge = PoseidonSponge::<Fq>::new(&params);
+        sponge.absorb(&vec![Fq::zero(), Fq::one(), Fq::from(2u64)]);
+        let expected = [
+            "40442793463571304028337753002242186710310163897048962278675457993207843616876",
+            "2664374461699898000291153145224099287711224021716202960480903840045233645301",
+            "50191078828066923662070228256530692951801504043422844038937334196346054068797",
+        ]
+        .map(|value| Fq::from_str(value).unwrap());
+        assert_eq!(sponge.squeeze_native_field_elements(3), expected);
+    }
+
+    #[test]
+    pub fn test_default_parameters_are_generated() {
+        let HashParameters::Poseidon(params) = HashParameters::<Fq>::default() else {
+            panic!("default hash is not Poseidon")
+        };
+        let generated = generate_poseidon_parameters::<Fq>(3, 17, 128);
+        assert_eq!(
+            (params.full_rounds, params.partial_rounds, params.alpha),
+            (8, 31, 17)
+        );
+        assert_eq!(params.ark, generated.ark);
+        assert_eq!(params.mds, generated.mds);
+
+        // the fixed parameters differ in their partial rounds and their matrix
+        let fixed = poseidon_parameters::<Fq>();
+        assert_eq!((fixed.full_rounds, fixed.partial_rounds, fixed.alpha), (8, 29, 17));
+        assert_ne!(fixed.mds, generated.mds);
+
+        // and are generated for every field, not parsed into it
+        let HashParameters::Poseidon(params) = HashParameters::<Fr>::default() else {
+            panic!("default hash is not Poseidon")
+        };
+        assert_eq!(params.ark, generate_poseidon_parameters::<Fr>(3, 17, 128).ark);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn test_rejects_non_permutation_alpha() {
+        // 3 divides p - 1 for the Jubjub scalar field
+        generate_poseidon_parameters::<Fr>(3, 3, 128);
+    }
+}
@@ -2,8 +2,12 @@ use ark_ff::PrimeField;
 use ark_poly::{
     polynomial::DenseUVPolynomial,
     univariate::DensePolynomial,
-    Polynomial,
+    EvaluationDomain, GeneralEvaluationDomain,
 };
+
+/// below this length products of polynomials are computed term by term
+const SCHOOLBOOK_LENGTH: usize = 32;
+
 #[derive(Clone)]
 pub struct RSCode<F: PrimeField> {
     pub evaluation_domain: Vec<F>,
@@ -20,19 +24,190 @@ impl<F: PrimeField> RSCode<F> {
         let mut evaluation_domain: Vec<F> = vec![];
         let mut field_prim = F::GENERATOR;
         let d: u64 = coeff.len() as u64;
-        let mut evals: Vec<F> = vec![];
         for _pow in 1..(t+1) {
-            evals.push(poly.evaluate(&field_prim));
-            evaluation_domain.push(field_prim.clone());
+            evaluation_domain.push(field_prim);
             field_prim *= F::GENERATOR;
         }
         RSCode {
             evaluation_domain,
-            code: evals,
+            code: evaluate_geometric(&coeff, t as usize),
             coeffs: coeff,
             poly,
             t,
             d,
         }
     }
-}
\ No newline at end of file
+}
+
+/// p(g), p(g²), …, p(gᵗ) for g = F::GENERATOR and p given by `coeffs`, with the chirp-z
+/// transform: since ij = C(i + j, 2) - C(i, 2) - C(j, 2), the evaluations are the
+/// coefficients of one product of polynomials, which is far cheaper than evaluating p
+/// at every point when codewords get long
+fn evaluate_geometric<F: PrimeField>(coeffs: &[F], t: usize) -> Vec<F> {
+    if coeffs.is_empty() || t == 0 {
+        return vec![F::zero(); t];
+    }
+    let k = coeffs.len();
+
+    // p = p_0 + x^t·p_1 + x^2t·p_2 + … with every p_j shorter than the code, so that no
+    // product is longer than twice the code
+    if k > t {
+        let mut code = vec![F::zero(); t];
+        let mut shifts = vec![F::one(); t];
+        let mut point = F::GENERATOR;
+        let point_shifts: Vec<F> = (0..t)
+            .map(|_| {
+                let shift = point.pow([t as u64]);
+                point *= F::GENERATOR;
+                shift
+            })
+            .collect();
+        for chunk in coeffs.chunks(t) {
+            let chunk_code = evaluate_geometric(chunk, t);
+            for (i, value) in chunk_code.into_iter().enumerate() {
+                code[i] += shifts[i] * value;
+                shifts[i] *= point_shifts[i];
+            }
+        }
+        return code;
+    }
+
+    // g^C(m, 2) for m < k + t and g^-C(m, 2) for m < max(k, t)
+    let mut chirp = Vec::with_capacity(k + t - 1);
+    let mut power = F::one();
+    let mut current = F::one();
+    for _ in 0..(k + t - 1) {
+        chirp.push(current);
+        current *= power;
+        power *= F::GENERATOR;
+    }
+    let generator_inverse = F::GENERATOR.inverse().unwrap();
+    let mut inverse_chirp = Vec::with_capacity(k.max(t));
+    let mut power = F::one();
+    let mut current = F::one();
+    for _ in 0..k.max(t) {
+        inverse_chirp.push(current);
+        current *= power;
+        power *= generator_inverse;
+    }
+
+    // the points start at g, so p(gx) is evaluated at g⁰, …, gᵗ⁻¹
+    let mut shift = F::one();
+    let mut scaled: Vec<F> = coeffs
+        .iter()
+        .zip(&inverse_chirp)
+        .map(|(coeff, inverse)| {
+            let term = *coeff * shift * inverse;
+            shift *= F::GENERATOR;
+            term
+        })
+        .collect();
+    scaled.reverse();
+
+    let product = multiply(&scaled, &chirp);
+    inverse_chirp
+        .iter()
+        .take(t)
+        .zip(&product[(k - 1)..])
+        .map(|(inverse, sum)| *inverse * sum)
+        .collect()
+}
+
+/// coefficients of the product of two non-empty polynomials, through an FFT where the
+/// field has a large enough domain and Karatsuba multiplication otherwise
+fn multiply<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+    let length = a.len() + b.len() - 1;
+    if length > SCHOOLBOOK_LENGTH {
+        if let Some(domain) = GeneralEvaluationDomain::<F>::new(length) {
+            let a = domain.fft(a);
+            let b = domain.fft(b);
+            let evaluations: Vec<F> = a.iter().zip(&b).map(|(a, b)| *a * b).collect();
+            let mut product = domain.ifft(&evaluations);
+            product.truncate(length);
+            return product;
+        }
+    }
+    karatsuba(a, b)
+}
+
+fn karatsuba<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut product = vec![F::zero(); a.len() + b.len() - 1];
+
+    if b.len() <= SCHOOLBOOK_LENGTH {
+        for (i, a) in a.iter().enumerate() {
+            for (j, b) in b.iter().enumerate() {
+                product[i + j] += *a * b;
+            }
+        }
+        return product;
+    }
+
+    // the longer factor is cut into pieces as long as the shorter one
+    if a.len() > b.len() {
+        for (chunk, piece) in a.chunks(b.len()).enumerate() {
+            let offset = chunk * b.len();
+            for (i, value) in karatsuba(piece, b).into_iter().enumerate() {
+                product[offset + i] += value;
+            }
+        }
+        return product;
+    }
+
+    let half = a.len() / 2;
+    let (a_low, a_high) = a.split_at(half);
+    let (b_low, b_high) = b.split_at(half);
+    let low = karatsuba(a_low, b_low);
+    let high = karatsuba(a_high, b_high);
+    let mut a_sum = a_high.to_vec();
+    let mut b_sum = b_high.to_vec();
+    for (sum, value) in a_sum.iter_mut().zip(a_low) {
+        *sum += value;
+    }
+    for (sum, value) in b_sum.iter_mut().zip(b_low) {
+        *sum += value;
+    }
+    let middle = karatsuba(&a_sum, &b_sum);
+
+    for (i, value) in low.iter().enumerate() {
+        product[i] += value;
+        product[half + i] -= value;
+    }
+    for (i, value) in high.iter().enumerate() {
+        product[2 * half + i] += value;
+        product[half + i] -= value;
+    }
+    for (i, value) in middle.into_iter().enumerate() {
+        product[half + i] += value;
+    }
+    product
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use ark_ed_on_bls12_381::{Fq, Fr};
+    use ark_poly::Polynomial;
+    use ark_std::UniformRand;
+
+    #[test]
+    pub fn test_encode_matches_evaluation() {
+        let mut rng = ark_std::test_rng();
+        for (k, t) in [(1, 8), (5, 3), (33, 64), (300, 1024), (1000, 700)] {
+            let coeffs: Vec<Fr> = (0..k).map(|_| Fr::rand(&mut rng)).collect();
+            let code = RSCode::encode(coeffs, t as u64);
+            assert_eq!(code.code.len(), t);
+            for (point, value) in code.evaluation_domain.iter().zip(&code.code) {
+                assert_eq!(code.poly.evaluate(point), *value);
+            }
+
+            // a field with an FFT domain takes the other product
+            let coeffs: Vec<Fq> = (0..k).map(|_| Fq::rand(&mut rng)).collect();
+            let code = RSCode::encode(coeffs, t as u64);
+            for (point, value) in code.evaluation_domain.iter().zip(&code.code) {
+                assert_eq!(code.poly.evaluate(point), *value);
+            }
+        }
+        assert_eq!(RSCode::<Fr>::encode(vec![], 4).code, vec![Fr::from(0u64); 4]);
+    }
+}
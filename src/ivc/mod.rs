@@ -0,0 +1,488 @@
+pub mod non_uniform;
+
+use ark_crypto_primitives::crh::{CRHScheme, CRHSchemeGadget};
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+};
+use ark_std::vec::Vec;
+
+use crate::bd_as::constraints::{
+    AccumulationVerifierGadget, CommittedAccumulatorVar, CommittedProofVar,
+};
+use crate::bd_as::r1cs_nark::constraints::{HashCRHGadget, HashParametersVar, InstanceVar};
+use crate::bd_as::r1cs_nark::{
+    self, CircuitDigest, CodeParameters, HashCRH, HashParameters, IndexDeciderKey,
+    IndexProverKey, R1CSNark,
+};
+use crate::bd_as::{
+    AccumulatorInstance, AccumulatorProver, AccumulatorWitness, BDASAccumulationScheme,
+    CommittedAccumulator, Proof,
+};
+use crate::AccumulationScheme;
+
+/// number of instance variables of the augmented circuit: the constant one and the hash
+/// of the step counter, the states and the running accumulator
+const NUM_INSTANCE_VARIABLES: usize = 2;
+
+/// a function F applied once per IVC step, z_{i+1} = F(z_i), with states of `arity`
+/// field elements
+pub trait StepCircuit<F: PrimeField>: Clone {
+    /// number of field elements in the state
+    fn arity(&self) -> usize;
+
+    /// enforces one step from `state` and returns the next state
+    fn generate_step_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        state: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError>;
+}
+
+/// F applied to `state` outside of any proof, by running the step on a scratch
+/// constraint system
+fn step_native<F: PrimeField, SC: StepCircuit<F>>(
+    step_circuit: &SC,
+    state: &[F],
+) -> Result<Vec<F>, SynthesisError> {
+    let cs = ConstraintSystem::new_ref();
+    let state = Vec::new_witness(cs.clone(), || Ok(state.to_vec()))?;
+    let next_state = step_circuit.generate_step_constraints(cs, &state)?;
+    if next_state.len() != step_circuit.arity() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    next_state.iter().map(|value| value.value()).collect()
+}
+
+/// indexes the circuits `circuits` builds for a given code, lengthening the code until it
+/// fits all of them (see `CodeParameters::fitted_to`). The circuits check Merkle paths as
+/// deep as the code is long, so a longer code can grow them again, and they are indexed
+/// until it stops changing. All keys fold with the same code.
+pub(crate) fn index_fitted<F, C>(
+    code_params: CodeParameters,
+    circuits: impl Fn(CodeParameters) -> Vec<C>,
+) -> Result<Vec<IndexProverKey<F>>, SynthesisError>
+where
+    F: PrimeField + Absorb,
+    C: ConstraintSynthesizer<F>,
+{
+    let pp = R1CSNark::<F>::setup();
+    let mut code_params = code_params;
+    loop {
+        let prover_keys = circuits(code_params)
+            .into_iter()
+            .map(|circuit| Ok(R1CSNark::index(&pp, circuit)?.0))
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        let fitted = prover_keys
+            .iter()
+            .fold(code_params, |fitted, key| fitted.fitted_to(&key.index_info));
+        if fitted == code_params {
+            return Ok(prover_keys
+                .into_iter()
+                .map(|key| key.with_code_params(code_params))
+                .collect());
+        }
+        code_params = fitted;
+    }
+}
+
+/// H(vk, i, z_0, z_i, U_i), the single public input of the augmented circuit, where vk
+/// is the digest of the augmented circuit itself
+fn state_hash<F: PrimeField + Absorb>(
+    hash_params: &HashParameters<F>,
+    digest: CircuitDigest,
+    num_steps: usize,
+    z0: &[F],
+    state: &[F],
+    accumulator: &CommittedAccumulator<F>,
+) -> Result<F, SynthesisError> {
+    let mut elements = digest.to_field_elements().to_vec();
+    elements.push(F::from(num_steps as u64));
+    elements.extend_from_slice(z0);
+    elements.extend_from_slice(state);
+    elements.extend(accumulator.to_field_elements());
+    HashCRH::evaluate(hash_params, elements).map_err(|_| SynthesisError::Unsatisfiable)
+}
+
+/// in-circuit `state_hash`
+fn state_hash_var<F: PrimeField + Absorb>(
+    hash_params: &HashParametersVar<F>,
+    digest: &[FpVar<F>],
+    num_steps: &FpVar<F>,
+    z0: &[FpVar<F>],
+    state: &[FpVar<F>],
+    accumulator: &CommittedAccumulatorVar<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut elements = digest.to_vec();
+    elements.push(num_steps.clone());
+    elements.extend_from_slice(z0);
+    elements.extend_from_slice(state);
+    elements.extend(accumulator.to_field_vars());
+    HashCRHGadget::evaluate(hash_params, &elements)
+}
+
+/// Step i of the IVC: applies F to z_i and verifies the fold of the previous step's
+/// proof u_i into the running accumulator U_i, giving U_{i+1}. Only the commitments to
+/// the accumulators are carried, and u_i is identified by its public input
+/// H(vk, i, z_0, z_i, U_i). The single public input of the circuit is
+/// H(vk, i + 1, z_0, z_{i+1}, U_{i+1}). The circuit cannot hold its own digest vk, so it
+/// is a witness, passed from step to step through the hashes and checked by the
+/// verifier against its key. The first step has no proof to fold, so it starts from z_0
+/// and outputs the zero accumulator.
+#[derive(Clone)]
+pub struct AugmentedCircuit<F: PrimeField + Absorb, SC: StepCircuit<F>> {
+    step_circuit: SC,
+    hash_params: HashParameters<F>,
+    code_params: CodeParameters,
+    digest: CircuitDigest,
+    num_steps: usize,
+    z0: Vec<F>,
+    state: Vec<F>,
+    accumulator: CommittedAccumulator<F>,
+    input_blinded_witness: F,
+    proof: Proof<F>,
+    new_accumulator: CommittedAccumulator<F>,
+}
+
+impl<F: PrimeField + Absorb, SC: StepCircuit<F>> AugmentedCircuit<F, SC> {
+    /// the first step from `z0`, also used to index the circuit
+    fn base(
+        step_circuit: SC,
+        hash_params: HashParameters<F>,
+        code_params: CodeParameters,
+        z0: Vec<F>,
+    ) -> Self {
+        let zero_accumulator =
+            CommittedAccumulator::zero(NUM_INSTANCE_VARIABLES, &code_params, &hash_params);
+        AugmentedCircuit {
+            step_circuit,
+            hash_params,
+            code_params,
+            digest: CircuitDigest([0; 32]),
+            num_steps: 0,
+            state: z0.clone(),
+            z0,
+            accumulator: zero_accumulator.clone(),
+            input_blinded_witness: F::zero(),
+            proof: Proof::dummy(&code_params),
+            new_accumulator: zero_accumulator,
+        }
+    }
+}
+
+impl<F: PrimeField + Absorb, SC: StepCircuit<F>> ConstraintSynthesizer<F>
+    for AugmentedCircuit<F, SC>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let arity = self.step_circuit.arity();
+        if self.z0.len() != arity || self.state.len() != arity {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let hash_params = HashParametersVar::new_constant(
+            ark_relations::ns!(cs, "hash_params"),
+            &self.hash_params,
+        )?;
+        let zero_accumulator = CommittedAccumulatorVar::new_constant(
+            ark_relations::ns!(cs, "zero_accumulator"),
+            CommittedAccumulator::zero(
+                NUM_INSTANCE_VARIABLES,
+                &self.code_params,
+                &self.hash_params,
+            ),
+        )?;
+
+        let digest = Vec::new_witness(ark_relations::ns!(cs, "digest"), || {
+            Ok(self.digest.to_field_elements().to_vec())
+        })?;
+        let num_steps = FpVar::new_witness(ark_relations::ns!(cs, "num_steps"), || {
+            Ok(F::from(self.num_steps as u64))
+        })?;
+        let z0 = Vec::new_witness(ark_relations::ns!(cs, "z0"), || Ok(self.z0.clone()))?;
+        let state = Vec::new_witness(ark_relations::ns!(cs, "state"), || Ok(self.state.clone()))?;
+        let accumulator = CommittedAccumulatorVar::new_witness(
+            ark_relations::ns!(cs, "accumulator"),
+            || Ok(&self.accumulator),
+        )?;
+        let input_instance = InstanceVar {
+            input: vec![
+                FpVar::one(),
+                state_hash_var(&hash_params, &digest, &num_steps, &z0, &state, &accumulator)?,
+            ],
+            blinded_witness: FpVar::new_witness(
+                ark_relations::ns!(cs, "input_blinded_witness"),
+                || Ok(self.input_blinded_witness),
+            )?,
+        };
+        let proof =
+            CommittedProofVar::new_witness(ark_relations::ns!(cs, "proof"), || Ok(&self.proof))?;
+        let new_accumulator = CommittedAccumulatorVar::new_witness(
+            ark_relations::ns!(cs, "new_accumulator"),
+            || Ok(&self.new_accumulator),
+        )?;
+
+        let is_base = num_steps.is_zero()?;
+        for (initial, current) in z0.iter().zip(&state) {
+            initial.conditional_enforce_equal(current, &is_base)?;
+        }
+
+        let folded = AccumulationVerifierGadget::verify_committed(
+            &self.code_params,
+            &hash_params,
+            &digest,
+            &proof,
+            &accumulator,
+            &new_accumulator,
+            &input_instance,
+        )?;
+        is_base.or(&folded)?.enforce_equal(&Boolean::TRUE)?;
+        let new_accumulator = CommittedAccumulatorVar::conditionally_select(
+            &is_base,
+            &zero_accumulator,
+            &new_accumulator,
+        )?;
+
+        let next_state = self.step_circuit.generate_step_constraints(cs.clone(), &state)?;
+        if next_state.len() != arity {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let next_hash = state_hash_var(
+            &hash_params,
+            &digest,
+            &(num_steps + FpVar::one()),
+            &z0,
+            &next_state,
+            &new_accumulator,
+        )?;
+        let output = FpVar::new_input(ark_relations::ns!(cs, "output"), || next_hash.value())?;
+        output.enforce_equal(&next_hash)
+    }
+}
+
+/// what the verifier of an IVC needs: the number of steps, the initial and latest
+/// state, the running accumulator and the NARK proof of the last step
+#[derive(Clone)]
+pub struct IVCProof<F: PrimeField + Absorb> {
+    pub num_steps: usize,
+    pub z0: Vec<F>,
+    pub state: Vec<F>,
+    pub accumulator: (AccumulatorInstance<F>, AccumulatorWitness<F>),
+    pub proof: r1cs_nark::Proof<F>,
+}
+
+/// Incrementally verifiable computation of z_n = F^n(z_0) for a `StepCircuit` F. Every
+/// step proves the augmented circuit with the NARK and folds the previous step's proof
+/// into the running accumulator, so verifying n steps is one NARK verification and one
+/// call to the decider.
+pub struct IVC<F: PrimeField + Absorb, SC: StepCircuit<F>> {
+    step_circuit: SC,
+    prover_key: IndexProverKey<F>,
+    decider_key: IndexDeciderKey<F>,
+    num_steps: usize,
+    z0: Vec<F>,
+    state: Vec<F>,
+    prover: AccumulatorProver<F>,
+    last_proof: Option<r1cs_nark::Proof<F>>,
+}
+
+impl<F: PrimeField + Absorb, SC: StepCircuit<F>> IVC<F, SC> {
+    /// indexes the augmented circuit of `step_circuit` and starts from `z0`. Every step
+    /// commits with `hash_params` and folds with `code_params`, whose number of queries
+    /// drives the size of the circuit. The code is lengthened to fit the augmented
+    /// circuit, which runs to tens of thousands of constraints; Poseidon2 parameters
+    /// keep it several times smaller, as the challenges are then derived without Blake2s.
+    pub fn setup(
+        step_circuit: SC,
+        z0: Vec<F>,
+        hash_params: HashParameters<F>,
+        code_params: CodeParameters,
+    ) -> Result<Self, SynthesisError> {
+        let mut prover_keys = index_fitted(code_params, |code_params| {
+            vec![AugmentedCircuit::base(
+                step_circuit.clone(),
+                hash_params.clone(),
+                code_params,
+                z0.clone(),
+            )]
+        })?;
+        let prover_key = prover_keys.remove(0).with_hash_params(hash_params);
+
+        Ok(IVC {
+            step_circuit,
+            prover: AccumulatorProver::new(prover_key.clone()),
+            decider_key: prover_key.decider_key(),
+            prover_key,
+            num_steps: 0,
+            state: z0.clone(),
+            z0,
+            last_proof: None,
+        })
+    }
+
+    /// the key `verify` needs, the decider key of the augmented circuit
+    pub fn decider_key(&self) -> &IndexDeciderKey<F> {
+        &self.decider_key
+    }
+
+    pub fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    /// the latest state z_i
+    pub fn state(&self) -> &[F] {
+        &self.state
+    }
+
+    /// applies the step circuit once, folding the proof of the previous step into the
+    /// running accumulator
+    pub fn prove_step(&mut self) -> Result<(), SynthesisError> {
+        let hash_params = self.prover_key.hash_params.clone();
+        let mut circuit = AugmentedCircuit::base(
+            self.step_circuit.clone(),
+            hash_params,
+            self.prover_key.code_params,
+            self.z0.clone(),
+        );
+        circuit.digest = self.prover_key.digest;
+        circuit.num_steps = self.num_steps;
+        circuit.state = self.state.clone();
+
+        if let Some(last_proof) = &self.last_proof {
+            let (instance, witness) = self.prover.accumulator();
+            circuit.accumulator = CommittedAccumulator::new(instance, witness);
+            let ((instance, witness), proof) = self.prover.accumulate(last_proof)?;
+            circuit.new_accumulator = CommittedAccumulator::new(&instance, &witness);
+            circuit.input_blinded_witness = last_proof.instance.blinded_witness;
+            circuit.proof = proof;
+        }
+
+        let next_state = step_native(&self.step_circuit, &self.state)?;
+        let proof = R1CSNark::prove(&self.prover_key, circuit, None)?;
+
+        self.num_steps += 1;
+        self.state = next_state;
+        self.last_proof = Some(proof);
+        Ok(())
+    }
+
+    /// the proof of the steps so far, `None` before the first step
+    pub fn proof(&self) -> Option<IVCProof<F>> {
+        let last_proof = self.last_proof.as_ref()?;
+        let (instance, witness) = self.prover.accumulator();
+        Some(IVCProof {
+            num_steps: self.num_steps,
+            z0: self.z0.clone(),
+            state: self.state.clone(),
+            accumulator: (instance.clone(), witness.clone()),
+            proof: last_proof.clone().without_commitment(),
+        })
+    }
+
+    /// checks that `proof.state` is the result of `proof.num_steps` steps from
+    /// `proof.z0`: the last NARK proof has to be for the hash of exactly these values and
+    /// the accumulator of the earlier steps has to pass the decider
+    pub fn verify(
+        decider_key: &IndexDeciderKey<F>,
+        proof: &IVCProof<F>,
+    ) -> Result<bool, SynthesisError> {
+        if proof.num_steps == 0 {
+            return Ok(false);
+        }
+
+        let (instance, witness) = &proof.accumulator;
+        let output = state_hash(
+            &decider_key.hash_params,
+            decider_key.digest,
+            proof.num_steps,
+            &proof.z0,
+            &proof.state,
+            &CommittedAccumulator::new(instance, witness),
+        )?;
+        if R1CSNark::verify(decider_key, &[F::one(), output], &proof.proof).is_err() {
+            return Ok(false);
+        }
+
+        BDASAccumulationScheme::decide(decider_key, (instance, witness))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::r1cs_nark::poseidon2::poseidon2_parameters;
+    use ark_ed_on_bls12_381::Fq;
+    use ark_ff::One;
+
+    /// z -> z^3 + z + 5
+    #[derive(Clone)]
+    pub struct CubicStep;
+
+    impl StepCircuit<Fq> for CubicStep {
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn generate_step_constraints(
+            &self,
+            _cs: ConstraintSystemRef<Fq>,
+            state: &[FpVar<Fq>],
+        ) -> Result<Vec<FpVar<Fq>>, SynthesisError> {
+            let z = &state[0];
+            Ok(vec![z * z * z + z + FpVar::constant(Fq::from(5u64))])
+        }
+    }
+
+    /// over the scalar field of BLS12-381, whose FFTs keep encoding the codewords cheap
+    /// at the length the augmented circuit needs
+    #[test]
+    pub fn test_ivc() {
+        let z0 = vec![Fq::from(2u64)];
+        let hash_params = HashParameters::Poseidon2(poseidon2_parameters());
+        let code_params = CodeParameters::with_num_queries(2);
+        let Ok(mut ivc) = IVC::setup(CubicStep, z0.clone(), hash_params, code_params) else {
+            panic!("augmented circuit not indexed");
+        };
+        assert!(ivc.proof().is_none());
+
+        // the code is far longer than the default one and fits the augmented circuit,
+        // which is built for that same code
+        let index_info = ivc.prover_key.index_info;
+        let code_length = ivc.prover_key.code_params.code_length();
+        assert!(code_length > code_params.code_length());
+        assert!(code_length >= 2 * index_info.num_witness_variables());
+        assert!(code_length >= 2 * index_info.num_constraints);
+        assert_eq!(ivc.decider_key().code_params, ivc.prover_key.code_params);
+
+        let mut expected = z0[0];
+        for _ in 0..3 {
+            ivc.prove_step().unwrap();
+            expected = expected * expected * expected + expected + Fq::from(5u64);
+        }
+        assert_eq!(ivc.num_steps(), 3);
+        assert_eq!(ivc.state(), &[expected]);
+
+        let proof = ivc.proof().unwrap();
+        assert!(IVC::<Fq, CubicStep>::verify(ivc.decider_key(), &proof).unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.state[0] += Fq::one();
+        assert!(!IVC::<Fq, CubicStep>::verify(ivc.decider_key(), &tampered).unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.num_steps = 2;
+        assert!(!IVC::<Fq, CubicStep>::verify(ivc.decider_key(), &tampered).unwrap());
+
+        let mut tampered = proof;
+        tampered.accumulator.0.err[0] += Fq::one();
+        assert!(!IVC::<Fq, CubicStep>::verify(ivc.decider_key(), &tampered).unwrap());
+    }
+}
@@ -0,0 +1,624 @@
+use ark_crypto_primitives::crh::{CRHScheme, CRHSchemeGadget};
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+};
+use ark_std::vec::Vec;
+
+use super::{index_fitted, NUM_INSTANCE_VARIABLES};
+use crate::bd_as::constraints::{
+    AccumulationVerifierGadget, CommittedAccumulatorVar, CommittedProofVar,
+};
+use crate::bd_as::r1cs_nark::constraints::{HashCRHGadget, HashParametersVar, InstanceVar};
+use crate::bd_as::r1cs_nark::{
+    self, CircuitDigest, CodeParameters, HashCRH, HashParameters, IndexDeciderKey,
+    IndexProverKey, R1CSNark,
+};
+use crate::bd_as::{
+    AccumulatorInstance, AccumulatorProver, AccumulatorWitness, BDASAccumulationScheme,
+    CommittedAccumulator, Proof,
+};
+use crate::AccumulationScheme;
+
+/// a set of step circuits F_0, ..., F_{l-1} over states of `arity` field elements. Each
+/// step runs the circuit selected by the program counter pc_i, which also computes the
+/// program counter of the next step: (pc_{i+1}, z_{i+1}) = F_{pc_i}(z_i).
+pub trait NonUniformStepCircuit<F: PrimeField>: Clone {
+    /// number of step circuits l
+    fn num_circuits(&self) -> usize;
+
+    /// number of field elements in the state
+    fn arity(&self) -> usize;
+
+    /// enforces one step of circuit `circuit_index` from `state` and returns the next
+    /// program counter along with the next state
+    #[allow(clippy::type_complexity)]
+    fn generate_step_constraints(
+        &self,
+        circuit_index: usize,
+        cs: ConstraintSystemRef<F>,
+        state: &[FpVar<F>],
+    ) -> Result<(FpVar<F>, Vec<FpVar<F>>), SynthesisError>;
+}
+
+/// F_{circuit_index} applied to `state` outside of any proof. Fails with
+/// `SynthesisError::Unsatisfiable` if the next program counter is not a circuit index.
+fn step_native<F: PrimeField, SC: NonUniformStepCircuit<F>>(
+    step_circuits: &SC,
+    circuit_index: usize,
+    state: &[F],
+) -> Result<(usize, Vec<F>), SynthesisError> {
+    let cs = ConstraintSystem::new_ref();
+    let state = Vec::new_witness(cs.clone(), || Ok(state.to_vec()))?;
+    let (program_counter, next_state) =
+        step_circuits.generate_step_constraints(circuit_index, cs, &state)?;
+    if next_state.len() != step_circuits.arity() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let program_counter = program_counter.value()?;
+    let program_counter = (0..step_circuits.num_circuits())
+        .find(|index| F::from(*index as u64) == program_counter)
+        .ok_or(SynthesisError::Unsatisfiable)?;
+    let next_state = next_state
+        .iter()
+        .map(|value| value.value())
+        .collect::<Result<_, _>>()?;
+    Ok((program_counter, next_state))
+}
+
+/// H(vk_0, ..., vk_{l-1}, i, pc_i, c_{i-1}, z_0, z_i, U_i[0], ..., U_i[l-1]) where vk_j
+/// is the digest of augmented circuit j and c_{i-1} is the circuit that ran the previous
+/// step, the single public input of every augmented circuit
+fn state_hash<F: PrimeField + Absorb>(
+    hash_params: &HashParameters<F>,
+    digests: &[CircuitDigest],
+    header: [usize; 3],
+    z0: &[F],
+    state: &[F],
+    accumulators: &[CommittedAccumulator<F>],
+) -> Result<F, SynthesisError> {
+    let mut elements: Vec<F> =
+        digests.iter().flat_map(|digest| digest.to_field_elements()).collect();
+    elements.extend(header.iter().map(|value| F::from(*value as u64)));
+    elements.extend_from_slice(z0);
+    elements.extend_from_slice(state);
+    for accumulator in accumulators {
+        elements.extend(accumulator.to_field_elements());
+    }
+    HashCRH::evaluate(hash_params, elements).map_err(|_| SynthesisError::Unsatisfiable)
+}
+
+/// in-circuit `state_hash`
+fn state_hash_var<F: PrimeField + Absorb>(
+    hash_params: &HashParametersVar<F>,
+    digests: &[FpVar<F>],
+    header: [&FpVar<F>; 3],
+    z0: &[FpVar<F>],
+    state: &[FpVar<F>],
+    accumulators: &[CommittedAccumulatorVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut elements = digests.to_vec();
+    elements.extend(header.into_iter().cloned());
+    elements.extend_from_slice(z0);
+    elements.extend_from_slice(state);
+    for accumulator in accumulators {
+        elements.extend(accumulator.to_field_vars());
+    }
+    HashCRHGadget::evaluate(hash_params, &elements)
+}
+
+/// Step i run by circuit j = pc_i. Next to F_j it verifies the fold of the previous
+/// step's proof u_i into the running accumulator of the circuit c_{i-1} that produced
+/// it, leaving the other accumulators as they are. u_i is identified by its public input
+/// H(i, pc_i, c_{i-1}, z_0, z_i, U_i) and the circuit outputs
+/// H(i + 1, pc_{i+1}, j, z_0, z_{i+1}, U_{i+1}), both also over the digests of all
+/// augmented circuits, which are witnesses as in `ivc::AugmentedCircuit`. The first step
+/// starts from z_0 with every accumulator zero.
+#[derive(Clone)]
+pub struct NonUniformAugmentedCircuit<F: PrimeField + Absorb, SC: NonUniformStepCircuit<F>> {
+    step_circuits: SC,
+    circuit_index: usize,
+    hash_params: HashParameters<F>,
+    code_params: CodeParameters,
+    digests: Vec<CircuitDigest>,
+    num_steps: usize,
+    last_circuit: usize,
+    z0: Vec<F>,
+    state: Vec<F>,
+    accumulators: Vec<CommittedAccumulator<F>>,
+    input_blinded_witness: F,
+    proof: Proof<F>,
+    new_accumulator: CommittedAccumulator<F>,
+}
+
+impl<F: PrimeField + Absorb, SC: NonUniformStepCircuit<F>> NonUniformAugmentedCircuit<F, SC> {
+    /// the first step from `z0` run by circuit `circuit_index`, also used to index it
+    fn base(
+        step_circuits: SC,
+        circuit_index: usize,
+        hash_params: HashParameters<F>,
+        code_params: CodeParameters,
+        z0: Vec<F>,
+    ) -> Self {
+        let zero_accumulator =
+            CommittedAccumulator::zero(NUM_INSTANCE_VARIABLES, &code_params, &hash_params);
+        NonUniformAugmentedCircuit {
+            accumulators: vec![zero_accumulator.clone(); step_circuits.num_circuits()],
+            digests: vec![CircuitDigest([0; 32]); step_circuits.num_circuits()],
+            step_circuits,
+            circuit_index,
+            hash_params,
+            code_params,
+            num_steps: 0,
+            last_circuit: 0,
+            state: z0.clone(),
+            z0,
+            input_blinded_witness: F::zero(),
+            proof: Proof::dummy(&code_params),
+            new_accumulator: zero_accumulator,
+        }
+    }
+}
+
+impl<F: PrimeField + Absorb, SC: NonUniformStepCircuit<F>> ConstraintSynthesizer<F>
+    for NonUniformAugmentedCircuit<F, SC>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let arity = self.step_circuits.arity();
+        let num_circuits = self.step_circuits.num_circuits();
+        if self.z0.len() != arity
+            || self.state.len() != arity
+            || self.accumulators.len() != num_circuits
+            || self.digests.len() != num_circuits
+            || self.circuit_index >= num_circuits
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let hash_params = HashParametersVar::new_constant(
+            ark_relations::ns!(cs, "hash_params"),
+            &self.hash_params,
+        )?;
+        let zero_accumulator = CommittedAccumulatorVar::new_constant(
+            ark_relations::ns!(cs, "zero_accumulator"),
+            CommittedAccumulator::zero(
+                NUM_INSTANCE_VARIABLES,
+                &self.code_params,
+                &self.hash_params,
+            ),
+        )?;
+        let circuit_index = FpVar::constant(F::from(self.circuit_index as u64));
+
+        let digests = Vec::new_witness(ark_relations::ns!(cs, "digests"), || {
+            Ok(self
+                .digests
+                .iter()
+                .flat_map(|digest| digest.to_field_elements())
+                .collect::<Vec<_>>())
+        })?;
+        let num_steps = FpVar::new_witness(ark_relations::ns!(cs, "num_steps"), || {
+            Ok(F::from(self.num_steps as u64))
+        })?;
+        let last_circuit = FpVar::new_witness(ark_relations::ns!(cs, "last_circuit"), || {
+            Ok(F::from(self.last_circuit as u64))
+        })?;
+        let z0 = Vec::new_witness(ark_relations::ns!(cs, "z0"), || Ok(self.z0.clone()))?;
+        let state = Vec::new_witness(ark_relations::ns!(cs, "state"), || Ok(self.state.clone()))?;
+        let accumulators = self
+            .accumulators
+            .iter()
+            .map(|accumulator| {
+                CommittedAccumulatorVar::new_witness(
+                    ark_relations::ns!(cs, "accumulator"),
+                    || Ok(accumulator),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // this circuit only runs the steps the program counter selects it for
+        let input_instance = InstanceVar {
+            input: vec![
+                FpVar::one(),
+                state_hash_var(
+                    &hash_params,
+                    &digests,
+                    [&num_steps, &circuit_index, &last_circuit],
+                    &z0,
+                    &state,
+                    &accumulators,
+                )?,
+            ],
+            blinded_witness: FpVar::new_witness(
+                ark_relations::ns!(cs, "input_blinded_witness"),
+                || Ok(self.input_blinded_witness),
+            )?,
+        };
+        let proof =
+            CommittedProofVar::new_witness(ark_relations::ns!(cs, "proof"), || Ok(&self.proof))?;
+        let new_accumulator = CommittedAccumulatorVar::new_witness(
+            ark_relations::ns!(cs, "new_accumulator"),
+            || Ok(&self.new_accumulator),
+        )?;
+
+        let is_base = num_steps.is_zero()?;
+        for (initial, current) in z0.iter().zip(&state) {
+            initial.conditional_enforce_equal(current, &is_base)?;
+        }
+
+        // the accumulator and digest of the circuit that produced u_i
+        let is_last_circuit = (0..num_circuits)
+            .map(|index| last_circuit.is_eq(&FpVar::constant(F::from(index as u64))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut accumulator = accumulators[0].clone();
+        let mut digest = digests[..2].to_vec();
+        for (index, selected) in is_last_circuit.iter().enumerate().skip(1) {
+            accumulator = CommittedAccumulatorVar::conditionally_select(
+                selected,
+                &accumulators[index],
+                &accumulator,
+            )?;
+            for (current, candidate) in digest.iter_mut().zip(&digests[2 * index..]) {
+                *current = FpVar::conditionally_select(selected, candidate, current)?;
+            }
+        }
+
+        let folded = AccumulationVerifierGadget::verify_committed(
+            &self.code_params,
+            &hash_params,
+            &digest,
+            &proof,
+            &accumulator,
+            &new_accumulator,
+            &input_instance,
+        )?;
+        is_base
+            .or(&folded.and(&Boolean::kary_or(&is_last_circuit)?)?)?
+            .enforce_equal(&Boolean::TRUE)?;
+
+        let new_accumulators = is_last_circuit
+            .iter()
+            .zip(&accumulators)
+            .map(|(selected, accumulator)| {
+                let updated = CommittedAccumulatorVar::conditionally_select(
+                    selected,
+                    &new_accumulator,
+                    accumulator,
+                )?;
+                CommittedAccumulatorVar::conditionally_select(&is_base, &zero_accumulator, &updated)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (program_counter, next_state) = self.step_circuits.generate_step_constraints(
+            self.circuit_index,
+            cs.clone(),
+            &state,
+        )?;
+        if next_state.len() != arity {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let next_hash = state_hash_var(
+            &hash_params,
+            &digests,
+            [&(num_steps + FpVar::one()), &program_counter, &circuit_index],
+            &z0,
+            &next_state,
+            &new_accumulators,
+        )?;
+        let output = FpVar::new_input(ark_relations::ns!(cs, "output"), || next_hash.value())?;
+        output.enforce_equal(&next_hash)
+    }
+}
+
+/// what the verifier of a non-uniform IVC needs: the number of steps, the next program
+/// counter, the circuit that ran the last step, the initial and latest state, one
+/// running accumulator per circuit and the NARK proof of the last step
+#[derive(Clone)]
+pub struct NonUniformIVCProof<F: PrimeField + Absorb> {
+    pub num_steps: usize,
+    pub program_counter: usize,
+    pub last_circuit: usize,
+    pub z0: Vec<F>,
+    pub state: Vec<F>,
+    pub accumulators: Vec<(AccumulatorInstance<F>, AccumulatorWitness<F>)>,
+    pub proof: r1cs_nark::Proof<F>,
+}
+
+/// IVC where every step runs one of several step circuits, picked by a program counter
+/// the previous step computed. Each circuit has its own key and running accumulator, so
+/// a step only pays for the circuit it runs, and verification is one NARK verification
+/// plus one call to the decider per circuit.
+pub struct NonUniformIVC<F: PrimeField + Absorb, SC: NonUniformStepCircuit<F>> {
+    step_circuits: SC,
+    prover_keys: Vec<IndexProverKey<F>>,
+    decider_keys: Vec<IndexDeciderKey<F>>,
+    num_steps: usize,
+    program_counter: usize,
+    last_circuit: usize,
+    z0: Vec<F>,
+    state: Vec<F>,
+    provers: Vec<AccumulatorProver<F>>,
+    last_proof: Option<r1cs_nark::Proof<F>>,
+}
+
+impl<F: PrimeField + Absorb, SC: NonUniformStepCircuit<F>> NonUniformIVC<F, SC> {
+    /// indexes the augmented circuit of every step circuit and starts from `z0` with the
+    /// circuit `program_counter`. All circuits commit with `hash_params` and fold with
+    /// the same code, lengthened to fit the largest of them (see `IVC::setup`).
+    pub fn setup(
+        step_circuits: SC,
+        z0: Vec<F>,
+        program_counter: usize,
+        hash_params: HashParameters<F>,
+        code_params: CodeParameters,
+    ) -> Result<Self, SynthesisError> {
+        if program_counter >= step_circuits.num_circuits() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let prover_keys: Vec<IndexProverKey<F>> = index_fitted(code_params, |code_params| {
+            (0..step_circuits.num_circuits())
+                .map(|circuit_index| {
+                    NonUniformAugmentedCircuit::base(
+                        step_circuits.clone(),
+                        circuit_index,
+                        hash_params.clone(),
+                        code_params,
+                        z0.clone(),
+                    )
+                })
+                .collect()
+        })?
+        .into_iter()
+        .map(|prover_key| prover_key.with_hash_params(hash_params.clone()))
+        .collect();
+
+        Ok(NonUniformIVC {
+            step_circuits,
+            provers: prover_keys.iter().cloned().map(AccumulatorProver::new).collect(),
+            decider_keys: prover_keys.iter().map(IndexProverKey::decider_key).collect(),
+            prover_keys,
+            num_steps: 0,
+            program_counter,
+            last_circuit: 0,
+            state: z0.clone(),
+            z0,
+            last_proof: None,
+        })
+    }
+
+    /// the keys `verify` needs, the decider keys of the augmented circuits in the order
+    /// of the circuit indices
+    pub fn decider_keys(&self) -> &[IndexDeciderKey<F>] {
+        &self.decider_keys
+    }
+
+    pub fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    /// the circuit the next step runs
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    /// the latest state z_i
+    pub fn state(&self) -> &[F] {
+        &self.state
+    }
+
+    /// runs the circuit selected by the program counter, folding the proof of the
+    /// previous step into the accumulator of the circuit that produced it
+    pub fn prove_step(&mut self) -> Result<(), SynthesisError> {
+        let circuit_index = self.program_counter;
+        let prover_key = &self.prover_keys[circuit_index];
+        let mut circuit = NonUniformAugmentedCircuit::base(
+            self.step_circuits.clone(),
+            circuit_index,
+            prover_key.hash_params.clone(),
+            prover_key.code_params,
+            self.z0.clone(),
+        );
+        circuit.digests = self.prover_keys.iter().map(|key| key.digest).collect();
+        circuit.num_steps = self.num_steps;
+        circuit.state = self.state.clone();
+
+        if let Some(last_proof) = &self.last_proof {
+            circuit.last_circuit = self.last_circuit;
+            circuit.accumulators = self.committed_accumulators();
+            let prover = &mut self.provers[self.last_circuit];
+            let ((instance, witness), proof) = prover.accumulate(last_proof)?;
+            circuit.new_accumulator = CommittedAccumulator::new(&instance, &witness);
+            circuit.input_blinded_witness = last_proof.instance.blinded_witness;
+            circuit.proof = proof;
+        }
+
+        let (program_counter, next_state) =
+            step_native(&self.step_circuits, circuit_index, &self.state)?;
+        let proof = R1CSNark::prove(&self.prover_keys[circuit_index], circuit, None)?;
+
+        self.num_steps += 1;
+        self.program_counter = program_counter;
+        self.last_circuit = circuit_index;
+        self.state = next_state;
+        self.last_proof = Some(proof);
+        Ok(())
+    }
+
+    /// the proof of the steps so far, `None` before the first step
+    pub fn proof(&self) -> Option<NonUniformIVCProof<F>> {
+        let last_proof = self.last_proof.as_ref()?;
+        let accumulators = self
+            .provers
+            .iter()
+            .map(|prover| {
+                let (instance, witness) = prover.accumulator();
+                (instance.clone(), witness.clone())
+            })
+            .collect();
+        Some(NonUniformIVCProof {
+            num_steps: self.num_steps,
+            program_counter: self.program_counter,
+            last_circuit: self.last_circuit,
+            z0: self.z0.clone(),
+            state: self.state.clone(),
+            accumulators,
+            proof: last_proof.clone().without_commitment(),
+        })
+    }
+
+    fn committed_accumulators(&self) -> Vec<CommittedAccumulator<F>> {
+        self.provers
+            .iter()
+            .map(|prover| {
+                let (instance, witness) = prover.accumulator();
+                CommittedAccumulator::new(instance, witness)
+            })
+            .collect()
+    }
+
+    /// checks that `proof.state` and `proof.program_counter` are the result of
+    /// `proof.num_steps` steps from `proof.z0`: the last NARK proof has to verify under
+    /// the key of the circuit that ran the last step, for the hash of exactly these
+    /// values, and every accumulator has to pass the decider of its circuit
+    pub fn verify(
+        decider_keys: &[IndexDeciderKey<F>],
+        proof: &NonUniformIVCProof<F>,
+    ) -> Result<bool, SynthesisError> {
+        if proof.num_steps == 0
+            || proof.accumulators.len() != decider_keys.len()
+            || proof.last_circuit >= decider_keys.len()
+        {
+            return Ok(false);
+        }
+
+        let accumulators: Vec<_> = proof
+            .accumulators
+            .iter()
+            .map(|(instance, witness)| CommittedAccumulator::new(instance, witness))
+            .collect();
+        let digests: Vec<_> = decider_keys.iter().map(|key| key.digest).collect();
+        let decider_key = &decider_keys[proof.last_circuit];
+        let output = state_hash(
+            &decider_key.hash_params,
+            &digests,
+            [proof.num_steps, proof.program_counter, proof.last_circuit],
+            &proof.z0,
+            &proof.state,
+            &accumulators,
+        )?;
+        if R1CSNark::verify(decider_key, &[F::one(), output], &proof.proof).is_err() {
+            return Ok(false);
+        }
+
+        for (decider_key, (instance, witness)) in decider_keys.iter().zip(&proof.accumulators) {
+            if !BDASAccumulationScheme::decide(decider_key, (instance, witness))? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::r1cs_nark::poseidon2::poseidon2_parameters;
+    use ark_ed_on_bls12_381::Fq;
+    use ark_ff::One;
+    use ark_r1cs_std::ToBitsGadget;
+
+    /// circuit 0 adds one, circuit 1 doubles; the next step runs circuit 1 iff the new
+    /// state is odd
+    #[derive(Clone)]
+    pub struct IncrementOrDouble;
+
+    impl NonUniformStepCircuit<Fq> for IncrementOrDouble {
+        fn num_circuits(&self) -> usize {
+            2
+        }
+
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn generate_step_constraints(
+            &self,
+            circuit_index: usize,
+            _cs: ConstraintSystemRef<Fq>,
+            state: &[FpVar<Fq>],
+        ) -> Result<(FpVar<Fq>, Vec<FpVar<Fq>>), SynthesisError> {
+            let z = &state[0];
+            let next = match circuit_index {
+                0 => z + FpVar::one(),
+                _ => z.double()?,
+            };
+            let is_odd = next.to_bits_le()?[0].clone();
+            Ok((FpVar::from(is_odd), vec![next]))
+        }
+    }
+
+    /// over the scalar field of BLS12-381 for the FFTs, see `ivc::test::test_ivc`
+    #[test]
+    pub fn test_non_uniform_ivc() {
+        let z0 = vec![Fq::from(3u64)];
+        let hash_params = HashParameters::Poseidon2(poseidon2_parameters());
+        let code_params = CodeParameters::with_num_queries(2);
+        let Ok(mut ivc) = NonUniformIVC::setup(IncrementOrDouble, z0, 0, hash_params, code_params)
+        else {
+            panic!("augmented circuits not indexed");
+        };
+
+        // one code for both circuits, long enough for either of them
+        let code_length = ivc.prover_keys[0].code_params.code_length();
+        assert!(code_length > code_params.code_length());
+        for prover_key in &ivc.prover_keys {
+            assert_eq!(prover_key.code_params.code_length(), code_length);
+            assert!(code_length >= 2 * prover_key.index_info.num_witness_variables());
+            assert!(code_length >= 2 * prover_key.index_info.num_constraints);
+        }
+        // the circuits differ in their step, so they have their own keys
+        assert_ne!(
+            ivc.decider_keys()[0].digest(),
+            ivc.decider_keys()[1].digest()
+        );
+
+        // 3 -> 4 -> 5 -> 10 -> 11 running circuits 0, 0, 1, 0
+        let mut circuits = vec![];
+        for _ in 0..4 {
+            circuits.push(ivc.program_counter());
+            ivc.prove_step().unwrap();
+        }
+        assert_eq!(circuits, vec![0, 0, 1, 0]);
+        assert_eq!(ivc.state(), &[Fq::from(11u64)]);
+        assert_eq!(ivc.program_counter(), 1);
+
+        let decider_keys = ivc.decider_keys();
+        let proof = ivc.proof().unwrap();
+        assert!(NonUniformIVC::<Fq, IncrementOrDouble>::verify(decider_keys, &proof).unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.state[0] += Fq::one();
+        assert!(!NonUniformIVC::<Fq, IncrementOrDouble>::verify(decider_keys, &tampered).unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.program_counter = 0;
+        assert!(!NonUniformIVC::<Fq, IncrementOrDouble>::verify(decider_keys, &tampered).unwrap());
+
+        // the accumulator of each circuit is decided with that circuit's key
+        let mut tampered = proof.clone();
+        tampered.accumulators.swap(0, 1);
+        assert!(!NonUniformIVC::<Fq, IncrementOrDouble>::verify(decider_keys, &tampered).unwrap());
+
+        let swapped_keys = [decider_keys[1].clone(), decider_keys[0].clone()];
+        assert!(!NonUniformIVC::<Fq, IncrementOrDouble>::verify(&swapped_keys, &proof).unwrap());
+    }
+}
@@ -7,6 +7,10 @@ pub mod data_structures;
 
 pub mod bd_as;
 
+pub mod ivc;
+
+pub mod pcd;
+
 pub trait AccumulationScheme<F: PrimeField> : Sized{
     type Proof: Clone;
     type AccumulatorInstance: Clone + CanonicalDeserialize + CanonicalSerialize;
@@ -17,6 +21,7 @@ pub trait AccumulationScheme<F: PrimeField> : Sized{
     type VerifierKey: Clone + CanonicalDeserialize + CanonicalSerialize;
     type DeciderKey: Clone + CanonicalDeserialize + CanonicalSerialize;
 
+    #[allow(clippy::type_complexity)]
     fn prove<'a> (
         prover_key: &'a Self::ProverKey, 
         old_accumulator: (&'a Self::AccumulatorInstance,&'a Self::AccumulatorWitness), 
@@ -28,7 +33,7 @@ pub trait AccumulationScheme<F: PrimeField> : Sized{
         proof: &Self::Proof, 
         old_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness), 
         new_accumulator: (&'a Self::AccumulatorInstance, &'a Self::AccumulatorWitness), 
-        input: (&'a Self::InputInstance, &'a Self::InputWitness),
+        input: &'a Self::InputInstance,
     ) -> Result<bool,SynthesisError>;
 
     fn decide<'a> (
@@ -110,14 +115,14 @@ pub mod test {
         println!("inp_2: {:?}", inp_wit_2);
         let mut inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
             &poseidon_parameters(),
-            inp_wit_1.clone(),
-            inp_wit_2.clone(),
+            inp_wit_1,
+            inp_wit_2,
         )
         .unwrap();
         let mut hash_circ = HashVerifyCirc {
-            inp_wit_1: inp_wit_1,
-            inp_wit_2: inp_wit_2,
-            inp_hash: inp_hash,
+            inp_wit_1,
+            inp_wit_2,
+            inp_hash,
         };
 
         let mut rng = ark_std::test_rng();
@@ -127,6 +132,7 @@ pub mod test {
         let Ok((ipk, ivk)) = R1CSNark::<Fr>::index(&pp, hash_circ.clone()) else {
             panic!("prover key not generated");
         };
+        let idk = ipk.decider_key();
 
 
         let mut old_acc_instance = AccumulatorInstance::zero(ipk.clone());
@@ -138,25 +144,21 @@ pub mod test {
                 panic!("prover key not generated");
             };
 
-            if temp_ipk.a == ipk.a {
-                if temp_ipk.b == ipk.b {
-                    if temp_ipk.c == ipk.c {
-                        println!("Iteration {}: Matrices match", i);
-                    }
-                }
+            if temp_ipk.is_compatible(&ipk.digest()) {
+                println!("Iteration {}: Matrices match", i);
             }
 
             inp_hash = <TwoToOneCRH<Fr> as TwoToOneCRHScheme>::evaluate(
                 &poseidon_parameters(),
-                inp_wit_1.clone(),
-                inp_wit_2.clone(),
+                inp_wit_1,
+                inp_wit_2,
             )
             .unwrap();
 
             hash_circ = HashVerifyCirc {
-                inp_wit_1: inp_wit_1,
-                inp_wit_2: inp_wit_2,
-                inp_hash: inp_hash,
+                inp_wit_1,
+                inp_wit_2,
+                inp_hash,
             };
 
             let proof = R1CSNark::<Fr>::prove(&ipk, hash_circ.clone(), Some(&mut rng)).unwrap();
@@ -174,7 +176,7 @@ pub mod test {
                 &acc_proof, 
                 (&old_acc_instance, &old_acc_witness), 
                 (&new_acc_instance, &new_acc_witness), 
-                (&proof.instance, &proof.witness)
+                &proof.instance
             ).unwrap() {
                 panic!["proof accumulation unsuccessful"]
             }
@@ -182,12 +184,12 @@ pub mod test {
             old_acc_instance = new_acc_instance.clone();
             old_acc_witness = new_acc_witness.clone();
 
-            inp_wit_1 = inp_wit_2.clone();
-            inp_wit_2 = inp_hash.clone();
+            inp_wit_1 = inp_wit_2;
+            inp_wit_2 = inp_hash;
         }
 
         let decider_result = BDASAccumulationScheme::decide(
-            &ivk, 
+            &idk, 
             (&old_acc_instance, &old_acc_witness)
         ).unwrap();
 
@@ -200,26 +202,26 @@ pub mod test {
 
     #[test]
     pub fn test_case_1() {
-        assert_eq!(test_template(1), true);
+        assert!(test_template(1));
     }
 
     #[test]
     pub fn test_case_2() {
-        assert_eq!(test_template(5), true);
+        assert!(test_template(5));
     }
 
     #[test]
     pub fn test_case_3() {
-        assert_eq!(test_template(10), true);
+        assert!(test_template(10));
     }
 
     #[test]
     pub fn test_case_4() {
-        assert_eq!(test_template(50), true);
+        assert!(test_template(50));
     }
 
     #[test]
     pub fn test_case_5() {
-        assert_eq!(test_template(100), true);
+        assert!(test_template(100));
     }
 }
\ No newline at end of file
@@ -0,0 +1,524 @@
+use ark_crypto_primitives::crh::{CRHScheme, CRHSchemeGadget};
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+};
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+use crate::bd_as::constraints::{
+    AccumulationVerifierGadget, CommittedAccumulatorVar, CommittedMergeProofVar,
+    CommittedProofVar,
+};
+use crate::bd_as::r1cs_nark::constraints::{HashCRHGadget, HashParametersVar, InstanceVar};
+use crate::bd_as::r1cs_nark::{
+    self, CircuitDigest, CodeParameters, HashCRH, HashParameters, IndexDeciderKey,
+    IndexProverKey, R1CSNark,
+};
+use crate::ivc::index_fitted;
+use crate::bd_as::{
+    AccumulatorInstance, AccumulatorProver, AccumulatorWitness, BDASAccumulationScheme,
+    CommittedAccumulator, MergeProof, Proof,
+};
+use crate::AccumulationScheme;
+
+/// number of instance variables of the node circuit: the constant one and the hash of
+/// the outgoing message and accumulator
+const NUM_INSTANCE_VARIABLES: usize = 2;
+
+/// the compliance predicate of a PCD node: the local computation from the messages of
+/// up to `fan_in` predecessors to the message the node sends on
+pub trait PCDPredicate<F: PrimeField>: Clone {
+    /// number of field elements in a message
+    fn arity(&self) -> usize;
+
+    /// maximum number of incoming messages of a node
+    fn fan_in(&self) -> usize;
+
+    /// enforces the local computation of a node and returns its outgoing message.
+    /// There are always `fan_in` incoming messages, `present` tells which of them come
+    /// from a predecessor; the others are zero.
+    fn generate_predicate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        incoming: &[Vec<FpVar<F>>],
+        present: &[Boolean<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError>;
+}
+
+/// the predicate evaluated outside of any proof, on a scratch constraint system
+fn predicate_native<F: PrimeField, P: PCDPredicate<F>>(
+    predicate: &P,
+    incoming: &[Vec<F>],
+    present: &[bool],
+) -> Result<Vec<F>, SynthesisError> {
+    let cs = ConstraintSystem::new_ref();
+    let incoming = incoming
+        .iter()
+        .map(|message| Vec::new_witness(cs.clone(), || Ok(message.clone())))
+        .collect::<Result<Vec<_>, _>>()?;
+    let present = Vec::new_witness(cs.clone(), || Ok(present.to_vec()))?;
+    let message = predicate.generate_predicate_constraints(cs, &incoming, &present)?;
+    if message.len() != predicate.arity() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    message.iter().map(|value| value.value()).collect()
+}
+
+/// H(vk, z, U), the single public input of the node circuit, where vk is the digest of
+/// the node circuit itself
+fn message_hash<F: PrimeField + Absorb>(
+    hash_params: &HashParameters<F>,
+    digest: CircuitDigest,
+    message: &[F],
+    accumulator: &CommittedAccumulator<F>,
+) -> Result<F, SynthesisError> {
+    let mut elements = digest.to_field_elements().to_vec();
+    elements.extend_from_slice(message);
+    elements.extend(accumulator.to_field_elements());
+    HashCRH::evaluate(hash_params, elements).map_err(|_| SynthesisError::Unsatisfiable)
+}
+
+/// in-circuit `message_hash`
+fn message_hash_var<F: PrimeField + Absorb>(
+    hash_params: &HashParametersVar<F>,
+    digest: &[FpVar<F>],
+    message: &[FpVar<F>],
+    accumulator: &CommittedAccumulatorVar<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut elements = digest.to_vec();
+    elements.extend_from_slice(message);
+    elements.extend(accumulator.to_field_vars());
+    HashCRHGadget::evaluate(hash_params, &elements)
+}
+
+/// an incoming edge of a node: the predecessor's message z, its accumulator U and the
+/// root of its NARK witness, together with the proofs that U was merged into the running
+/// accumulator and the predecessor's NARK instance folded into the result
+#[derive(Clone)]
+struct IncomingSlot<F: PrimeField + Absorb> {
+    present: bool,
+    message: Vec<F>,
+    accumulator: CommittedAccumulator<F>,
+    input_blinded_witness: F,
+    merge_proof: MergeProof<F>,
+    merged: CommittedAccumulator<F>,
+    fold_proof: Proof<F>,
+    folded: CommittedAccumulator<F>,
+}
+
+impl<F: PrimeField + Absorb> IncomingSlot<F> {
+    /// a slot without predecessor
+    fn absent(
+        arity: usize,
+        hash_params: &HashParameters<F>,
+        code_params: &CodeParameters,
+    ) -> Self {
+        let zero_accumulator =
+            CommittedAccumulator::zero(NUM_INSTANCE_VARIABLES, code_params, hash_params);
+        IncomingSlot {
+            present: false,
+            message: vec![F::zero(); arity],
+            accumulator: zero_accumulator.clone(),
+            input_blinded_witness: F::zero(),
+            merge_proof: MergeProof::dummy(code_params),
+            merged: zero_accumulator.clone(),
+            fold_proof: Proof::dummy(code_params),
+            folded: zero_accumulator,
+        }
+    }
+}
+
+/// The circuit proven at every node. For each incoming edge it merges the predecessor's
+/// accumulator U_j into the running accumulator and folds the predecessor's NARK
+/// instance u_j into the result, where u_j is identified by its public input
+/// H(vk, z_j, U_j). The running accumulator starts at the first incoming accumulator, so
+/// a node with one predecessor does no merge, and at zero for a source. The single
+/// public input is H(vk, z, U) for the outgoing message z and the final running
+/// accumulator U. The digest vk of the node circuit is a witness, as for
+/// `ivc::AugmentedCircuit`.
+#[derive(Clone)]
+pub struct NodeCircuit<F: PrimeField + Absorb, P: PCDPredicate<F>> {
+    predicate: P,
+    hash_params: HashParameters<F>,
+    code_params: CodeParameters,
+    digest: CircuitDigest,
+    slots: Vec<IncomingSlot<F>>,
+}
+
+impl<F: PrimeField + Absorb, P: PCDPredicate<F>> NodeCircuit<F, P> {
+    /// a source node, also used to index the circuit
+    fn source(predicate: P, hash_params: HashParameters<F>, code_params: CodeParameters) -> Self {
+        let slot = IncomingSlot::absent(predicate.arity(), &hash_params, &code_params);
+        NodeCircuit {
+            slots: vec![slot; predicate.fan_in()],
+            predicate,
+            hash_params,
+            code_params,
+            digest: CircuitDigest([0; 32]),
+        }
+    }
+}
+
+impl<F: PrimeField + Absorb, P: PCDPredicate<F>> ConstraintSynthesizer<F> for NodeCircuit<F, P> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let arity = self.predicate.arity();
+        if self.slots.len() != self.predicate.fan_in() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let code_params = &self.code_params;
+        let hash_params = HashParametersVar::new_constant(
+            ark_relations::ns!(cs, "hash_params"),
+            &self.hash_params,
+        )?;
+        let mut accumulator = CommittedAccumulatorVar::new_constant(
+            ark_relations::ns!(cs, "zero_accumulator"),
+            CommittedAccumulator::zero(
+                NUM_INSTANCE_VARIABLES,
+                &self.code_params,
+                &self.hash_params,
+            ),
+        )?;
+
+        let digest = Vec::new_witness(ark_relations::ns!(cs, "digest"), || {
+            Ok(self.digest.to_field_elements().to_vec())
+        })?;
+
+        let mut incoming = vec![];
+        let mut present = vec![];
+        for (slot_index, slot) in self.slots.iter().enumerate() {
+            let is_present =
+                Boolean::new_witness(ark_relations::ns!(cs, "present"), || Ok(slot.present))?;
+            let message: Vec<FpVar<F>> =
+                Vec::new_witness(ark_relations::ns!(cs, "message"), || Ok(slot.message.clone()))?;
+            if message.len() != arity {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+            for value in &message {
+                value.conditional_enforce_equal(&FpVar::zero(), &is_present.not())?;
+            }
+
+            let incoming_accumulator = CommittedAccumulatorVar::new_witness(
+                ark_relations::ns!(cs, "accumulator"),
+                || Ok(&slot.accumulator),
+            )?;
+            let input_instance = InstanceVar {
+                input: vec![
+                    FpVar::one(),
+                    message_hash_var(&hash_params, &digest, &message, &incoming_accumulator)?,
+                ],
+                blinded_witness: FpVar::new_witness(
+                    ark_relations::ns!(cs, "input_blinded_witness"),
+                    || Ok(slot.input_blinded_witness),
+                )?,
+            };
+
+            // the first incoming accumulator is taken as it is, the others are merged
+            // into the running accumulator
+            let (merged, merge_accepted) = if slot_index == 0 {
+                (incoming_accumulator, Boolean::TRUE)
+            } else {
+                let merge_proof = CommittedMergeProofVar::new_witness(
+                    ark_relations::ns!(cs, "merge_proof"),
+                    || Ok(&slot.merge_proof),
+                )?;
+                let merged = CommittedAccumulatorVar::new_witness(
+                    ark_relations::ns!(cs, "merged"),
+                    || Ok(&slot.merged),
+                )?;
+                let accepted = AccumulationVerifierGadget::verify_merge_committed(
+                    code_params,
+                    &hash_params,
+                    &digest,
+                    &merge_proof,
+                    &accumulator,
+                    &incoming_accumulator,
+                    &merged,
+                )?;
+                (merged, accepted)
+            };
+
+            let fold_proof = CommittedProofVar::new_witness(
+                ark_relations::ns!(cs, "fold_proof"),
+                || Ok(&slot.fold_proof),
+            )?;
+            let folded = CommittedAccumulatorVar::new_witness(
+                ark_relations::ns!(cs, "folded"),
+                || Ok(&slot.folded),
+            )?;
+            let fold_accepted = AccumulationVerifierGadget::verify_committed(
+                code_params,
+                &hash_params,
+                &digest,
+                &fold_proof,
+                &merged,
+                &folded,
+                &input_instance,
+            )?;
+
+            is_present
+                .not()
+                .or(&merge_accepted.and(&fold_accepted)?)?
+                .enforce_equal(&Boolean::TRUE)?;
+            accumulator =
+                CommittedAccumulatorVar::conditionally_select(&is_present, &folded, &accumulator)?;
+
+            incoming.push(message);
+            present.push(is_present);
+        }
+
+        let message =
+            self.predicate
+                .generate_predicate_constraints(cs.clone(), &incoming, &present)?;
+        if message.len() != arity {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let message_hash = message_hash_var(&hash_params, &digest, &message, &accumulator)?;
+        let output = FpVar::new_input(ark_relations::ns!(cs, "output"), || message_hash.value())?;
+        output.enforce_equal(&message_hash)
+    }
+}
+
+/// the outgoing message of a node together with what proves it: the accumulator of all
+/// proofs upstream of the node and the NARK proof of the node circuit
+#[derive(Clone)]
+pub struct PCDProof<F: PrimeField + Absorb> {
+    pub message: Vec<F>,
+    pub accumulator: (AccumulatorInstance<F>, AccumulatorWitness<F>),
+    pub proof: r1cs_nark::Proof<F>,
+}
+
+/// Proof-carrying data over a DAG whose nodes all satisfy the predicate `P`. Every node
+/// folds the proofs of its predecessors into a single accumulator with
+/// `BDASAccumulationScheme`, so any node's message is checked with one NARK verification
+/// and one call to the decider, however large the DAG behind it.
+pub struct PCD<F: PrimeField + Absorb, P: PCDPredicate<F>> {
+    prover_key: IndexProverKey<F>,
+    decider_key: IndexDeciderKey<F>,
+    _predicate: PhantomData<P>,
+}
+
+impl<F: PrimeField + Absorb, P: PCDPredicate<F>> PCD<F, P> {
+    /// indexes the node circuit of `predicate`. Only its shape, i.e. the arity, the
+    /// fan-in and the constraints, is fixed by the key. Every incoming edge costs a fold
+    /// and a merge verification in the circuit, sized by the queries of `code_params`.
+    /// The code is lengthened to fit the node circuit, see `IVC::setup`.
+    pub fn setup(
+        predicate: &P,
+        hash_params: HashParameters<F>,
+        code_params: CodeParameters,
+    ) -> Result<Self, SynthesisError> {
+        let mut prover_keys = index_fitted(code_params, |code_params| {
+            vec![NodeCircuit::source(predicate.clone(), hash_params.clone(), code_params)]
+        })?;
+        let prover_key = prover_keys.remove(0).with_hash_params(hash_params);
+        Ok(PCD {
+            decider_key: prover_key.decider_key(),
+            prover_key,
+            _predicate: PhantomData,
+        })
+    }
+
+    /// the key `verify` needs, the decider key of the node circuit
+    pub fn decider_key(&self) -> &IndexDeciderKey<F> {
+        &self.decider_key
+    }
+
+    /// proves a node with the given predecessors, in order. Fails with
+    /// `SynthesisError::Unsatisfiable` if there are more than `fan_in` of them.
+    pub fn prove_node(
+        &self,
+        predicate: P,
+        incoming: &[&PCDProof<F>],
+    ) -> Result<PCDProof<F>, SynthesisError> {
+        let arity = predicate.arity();
+        let fan_in = predicate.fan_in();
+        if incoming.len() > fan_in {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let hash_params = &self.prover_key.hash_params;
+        let code_params = &self.prover_key.code_params;
+        let mut prover: Option<AccumulatorProver<F>> = None;
+        let mut slots = vec![];
+        for predecessor in incoming {
+            let (instance, witness) = &predecessor.accumulator;
+            let mut slot = IncomingSlot::absent(arity, hash_params, code_params);
+            slot.present = true;
+            slot.message = predecessor.message.clone();
+            slot.accumulator = CommittedAccumulator::new(instance, witness);
+            slot.input_blinded_witness = predecessor.proof.instance.blinded_witness;
+
+            let prover = match &mut prover {
+                Some(prover) => {
+                    let ((merged_instance, merged_witness), merge_proof) =
+                        prover.merge((instance, witness))?;
+                    slot.merge_proof = merge_proof;
+                    slot.merged = CommittedAccumulator::new(&merged_instance, &merged_witness);
+                    prover
+                }
+                None => {
+                    slot.merged = slot.accumulator.clone();
+                    prover.insert(AccumulatorProver::from_accumulator(
+                        self.prover_key.clone(),
+                        (instance.clone(), witness.clone()),
+                    )?)
+                }
+            };
+
+            let ((folded_instance, folded_witness), fold_proof) =
+                prover.accumulate(&predecessor.proof)?;
+            slot.fold_proof = fold_proof;
+            slot.folded = CommittedAccumulator::new(&folded_instance, &folded_witness);
+            slots.push(slot);
+        }
+        slots.resize(fan_in, IncomingSlot::absent(arity, hash_params, code_params));
+
+        let accumulator = match &prover {
+            Some(prover) => {
+                let (instance, witness) = prover.accumulator();
+                (instance.clone(), witness.clone())
+            }
+            None => (
+                AccumulatorInstance::zero(self.prover_key.clone()),
+                AccumulatorWitness::zero(self.prover_key.clone()),
+            ),
+        };
+
+        let messages: Vec<_> = slots.iter().map(|slot| slot.message.clone()).collect();
+        let present: Vec<_> = slots.iter().map(|slot| slot.present).collect();
+        let message = predicate_native(&predicate, &messages, &present)?;
+
+        let circuit = NodeCircuit {
+            predicate,
+            hash_params: hash_params.clone(),
+            code_params: *code_params,
+            digest: self.prover_key.digest,
+            slots,
+        };
+        let proof = R1CSNark::prove(&self.prover_key, circuit, None)?;
+
+        Ok(PCDProof {
+            message,
+            accumulator,
+            proof,
+        })
+    }
+
+    /// checks that `proof.message` is the output of a DAG of nodes satisfying the
+    /// predicate: the NARK proof has to be for the hash of the message and the
+    /// accumulator, and the accumulator has to pass the decider
+    pub fn verify(
+        decider_key: &IndexDeciderKey<F>,
+        proof: &PCDProof<F>,
+    ) -> Result<bool, SynthesisError> {
+        let (instance, witness) = &proof.accumulator;
+        let output = message_hash(
+            &decider_key.hash_params,
+            decider_key.digest,
+            &proof.message,
+            &CommittedAccumulator::new(instance, witness),
+        )?;
+        if R1CSNark::verify(decider_key, &[F::one(), output], &proof.proof).is_err() {
+            return Ok(false);
+        }
+
+        BDASAccumulationScheme::decide(decider_key, (instance, witness))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::bd_as::r1cs_nark::poseidon2::poseidon2_parameters;
+    use ark_ed_on_bls12_381::Fq;
+    use ark_ff::One;
+
+    /// sends on its local value plus the sum of the incoming messages
+    #[derive(Clone)]
+    pub struct SumPredicate {
+        local: Fq,
+    }
+
+    impl PCDPredicate<Fq> for SumPredicate {
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn fan_in(&self) -> usize {
+            2
+        }
+
+        fn generate_predicate_constraints(
+            &self,
+            cs: ConstraintSystemRef<Fq>,
+            incoming: &[Vec<FpVar<Fq>>],
+            _present: &[Boolean<Fq>],
+        ) -> Result<Vec<FpVar<Fq>>, SynthesisError> {
+            let local = FpVar::new_witness(cs, || Ok(self.local))?;
+            Ok(vec![incoming
+                .iter()
+                .fold(local, |sum, message| sum + &message[0])])
+        }
+    }
+
+    /// over the scalar field of BLS12-381 for the FFTs, see `ivc::test::test_ivc`
+    #[test]
+    pub fn test_pcd_over_dag() {
+        let node = |local: u64| SumPredicate {
+            local: Fq::from(local),
+        };
+        let hash_params = HashParameters::Poseidon2(poseidon2_parameters());
+        let code_params = CodeParameters::with_num_queries(2);
+        let Ok(pcd) = PCD::setup(&node(0), hash_params, code_params) else {
+            panic!("node circuit not indexed");
+        };
+        let decider_key = pcd.decider_key();
+
+        // the node circuit, built for the code it folds with, fits that code
+        let index_info = pcd.prover_key.index_info;
+        let code_length = pcd.prover_key.code_params.code_length();
+        assert!(code_length > code_params.code_length());
+        assert!(code_length >= 2 * index_info.num_witness_variables());
+        assert!(code_length >= 2 * index_info.num_constraints);
+        assert_eq!(decider_key.code_params, pcd.prover_key.code_params);
+
+        // a   b
+        // |\ /
+        // | c
+        // |/
+        // d
+        let a = pcd.prove_node(node(1), &[]).unwrap();
+        let b = pcd.prove_node(node(2), &[]).unwrap();
+        let c = pcd.prove_node(node(3), &[&a, &b]).unwrap();
+        let d = pcd.prove_node(node(4), &[&c, &a]).unwrap();
+
+        assert_eq!(d.message, vec![Fq::from(11u64)]);
+        for proof in [&a, &c, &d] {
+            assert!(PCD::<Fq, SumPredicate>::verify(decider_key, proof).unwrap());
+        }
+
+        let mut tampered = d.clone();
+        tampered.message[0] += Fq::one();
+        assert!(!PCD::<Fq, SumPredicate>::verify(decider_key, &tampered).unwrap());
+
+        let mut tampered = d.clone();
+        tampered.accumulator = c.accumulator.clone();
+        assert!(!PCD::<Fq, SumPredicate>::verify(decider_key, &tampered).unwrap());
+
+        assert!(matches!(
+            pcd.prove_node(node(6), &[&a, &b, &c]),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}